@@ -0,0 +1,447 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use github_research_tool::error::{ResearchError, ResearchResult};
+use github_research_tool::github_data_fetchers::{
+    get_contributors, get_user_language_portfolio, search_issue_hits, search_repository_hits,
+    search_users_hits, IssueHit, RepositoryHit, UserHit,
+};
+use github_research_tool::llm::llm_from_env;
+use github_research_tool::reports::{
+    activity_report, org_report, ActivityReport, ContributorProfile, OrgReport, RepoFilter, Report,
+    ReportEngine,
+};
+use github_research_tool::schedule::{
+    report_schedule, run_subscriptions, subscribe, subscriptions, unsubscribe,
+};
+use github_research_tool::sinks::{self, sinks_from_env};
+use github_research_tool::store;
+use github_research_tool::time_window::TimeWindow;
+use github_research_tool::transport::transport_from_env;
+use serde::Serialize;
+use serde_json::json;
+use std::env;
+
+// Runs the report and search commands from a terminal, configured by the same
+// environment variables as the Discord bot.
+#[derive(Parser)]
+#[command(name = "github-research", version, about)]
+struct Cli {
+    #[arg(long, value_enum, default_value_t = Format::Markdown, global = true)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Markdown,
+    Html,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Summarize the activity in a repo, optionally for one contributor.
+    WeeklyReport {
+        owner: String,
+        repo: String,
+        #[arg(long)]
+        user: Option<String>,
+        #[arg(long)]
+        days: Option<i64>,
+        /// A `YYYY-MM-DD` date or a release tag.
+        #[arg(long)]
+        since: Option<String>,
+        /// A `YYYY-MM-DD` date or a release tag.
+        #[arg(long)]
+        until: Option<String>,
+        /// `month` or `quarter`, the last full one.
+        #[arg(long)]
+        period: Option<String>,
+        /// Also send the report to the Slack, Matrix and webhook sinks set in the environment.
+        #[arg(long)]
+        deliver: bool,
+    },
+    /// Summarize the activity across the repos of an org or user.
+    OrgReport {
+        owner: String,
+        /// Only the repos with this topic.
+        #[arg(long)]
+        topic: Option<String>,
+        /// Report on these repos instead of listing the owner's; can be repeated.
+        #[arg(long = "repo")]
+        repos: Vec<String>,
+        /// Also report on archived repos.
+        #[arg(long)]
+        include_archived: bool,
+        #[arg(long)]
+        user: Option<String>,
+        #[arg(long)]
+        days: Option<i64>,
+        /// `month` or `quarter`, the last full one.
+        #[arg(long)]
+        period: Option<String>,
+    },
+    /// Analyze how a user could fit a repo, from their activity across GitHub.
+    ContributorProfile {
+        user: String,
+        owner: String,
+        repo: String,
+        /// How many days of the user's activity to look at.
+        #[arg(long, default_value_t = 30)]
+        days: i64,
+    },
+    /// Summarize a user's contributions across all public repos.
+    UserActivity {
+        user: String,
+        /// 30 by default.
+        #[arg(long, conflicts_with = "period")]
+        days: Option<i64>,
+        /// `month` or `quarter`, the last full one.
+        #[arg(long)]
+        period: Option<String>,
+    },
+    /// Rank a user's languages across the repos they own or contribute to.
+    Portfolio { user: String },
+    /// Run a GitHub issue search, e.g. `repo:owner/repo is:open label:bug`.
+    SearchIssues { query: String },
+    /// Run a GitHub repository search.
+    SearchRepos { query: String },
+    /// Run a GitHub user search.
+    SearchUsers { query: String },
+    /// List the contributors of a repo.
+    Contributors { owner: String, repo: String },
+    /// Add a repo, optionally for one contributor, to the scheduled reports.
+    Subscribe {
+        owner: String,
+        repo: String,
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// Remove a repo from the scheduled reports.
+    Unsubscribe {
+        owner: String,
+        repo: String,
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// List the scheduled reports.
+    Subscriptions,
+    /// Send the scheduled reports to the configured sinks whenever `report_schedule` is due.
+    Schedule {
+        /// Send them once right away and exit, e.g. when run from the system's cron.
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+impl Command {
+    // Lists and search hits come as Markdown or JSON only.
+    fn has_html(&self) -> bool {
+        !matches!(
+            self,
+            Command::SearchIssues { .. }
+                | Command::SearchRepos { .. }
+                | Command::SearchUsers { .. }
+                | Command::Contributors { .. }
+                | Command::Subscriptions
+        )
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+
+    let res = run(cli).await;
+    store::flush();
+    if let Err(e) = res {
+        eprintln!("github-research: {e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> ResearchResult<()> {
+    let github_token = env::var("github_token").unwrap_or_default();
+    let github = transport_from_env(&github_token);
+    let github = github.as_ref();
+    let format = cli.format;
+    if format == Format::Html && !cli.command.has_html() {
+        return Err(ResearchError::InvalidInput(
+            "`--format html` is only available for the reports and `portfolio`".to_string(),
+        ));
+    }
+
+    match cli.command {
+        Command::WeeklyReport {
+            owner,
+            repo,
+            user,
+            days,
+            since,
+            until,
+            period,
+            deliver,
+        } => {
+            let llm = llm_from_env();
+            let window = TimeWindow::resolve(
+                github,
+                &owner,
+                &repo,
+                days,
+                since.as_deref(),
+                until.as_deref(),
+                period.as_deref(),
+            )
+            .await?;
+            let report = ReportEngine::new(github, llm.as_ref(), &owner, &repo)
+                .with_user(user)
+                .with_window(window)
+                .run()
+                .await?;
+            print_report(&report, format);
+            if deliver {
+                let sinks = sinks_from_env();
+                let delivered = sinks::deliver(&sinks, &report).await;
+                eprintln!("delivered to {delivered} of {} sinks", sinks.len());
+            }
+        }
+        Command::OrgReport {
+            owner,
+            topic,
+            repos,
+            include_archived,
+            user,
+            days,
+            period,
+        } => {
+            let llm = llm_from_env();
+            let window =
+                TimeWindow::resolve(github, "", "", days, None, None, period.as_deref()).await?;
+            let filter = RepoFilter {
+                topic,
+                include_archived,
+                repos,
+            };
+            let report = org_report(github, llm.as_ref(), &owner, &filter, user, window).await?;
+            print_report(&report, format);
+        }
+        Command::ContributorProfile {
+            user,
+            owner,
+            repo,
+            days,
+        } => {
+            let llm = llm_from_env();
+            let profile = ReportEngine::new(github, llm.as_ref(), &owner, &repo)
+                .with_user(Some(user))
+                .with_days(days)
+                .contributor_profile()
+                .await?;
+            print_report(&profile, format);
+        }
+        Command::UserActivity { user, days, period } => {
+            let llm = llm_from_env();
+            let window = TimeWindow::resolve(
+                github,
+                "",
+                "",
+                days.or(period.is_none().then_some(30)),
+                None,
+                None,
+                period.as_deref(),
+            )
+            .await?;
+            let report = activity_report(github, llm.as_ref(), &user, window).await?;
+            print_report(&report, format);
+        }
+        Command::Portfolio { user } => {
+            let portfolio = get_user_language_portfolio(github, &user).await?;
+            match format {
+                Format::Markdown => print!("{}", portfolio.to_markdown()),
+                Format::Html => print!("{}", portfolio.to_html()),
+                Format::Json => println!("{}", json!(portfolio)),
+            }
+        }
+        Command::SearchIssues { query } => print_search(
+            "Issues",
+            &query,
+            &search_issue_hits(github, &query).await?,
+            IssueHit::to_line,
+            format,
+        ),
+        Command::SearchRepos { query } => print_search(
+            "Repositories",
+            &query,
+            &search_repository_hits(github, &query).await?,
+            RepositoryHit::to_line,
+            format,
+        ),
+        Command::SearchUsers { query } => print_search(
+            "Users",
+            &query,
+            &search_users_hits(github, &query).await?,
+            UserHit::to_line,
+            format,
+        ),
+        Command::Contributors { owner, repo } => {
+            let contributors = get_contributors(github, &owner, &repo).await?;
+            match format {
+                Format::Json => println!("{}", json!(contributors)),
+                Format::Markdown | Format::Html => {
+                    println!("# Contributors to {owner}/{repo}\n");
+                    for login in contributors {
+                        println!("- {login}");
+                    }
+                }
+            }
+        }
+        Command::Subscribe { owner, repo, user } => {
+            match subscribe(&owner, &repo, user.as_deref()) {
+                true => eprintln!(
+                    "subscribed, reports run on `{}`",
+                    report_schedule().as_str()
+                ),
+                false => eprintln!("already subscribed"),
+            }
+        }
+        Command::Unsubscribe { owner, repo, user } => {
+            match unsubscribe(&owner, &repo, user.as_deref()) {
+                true => eprintln!("unsubscribed"),
+                false => eprintln!("no such subscription"),
+            }
+        }
+        Command::Subscriptions => {
+            let all = subscriptions();
+            match format {
+                Format::Json => println!("{}", json!(all)),
+                Format::Markdown | Format::Html => {
+                    println!("# Scheduled reports on `{}`\n", report_schedule().as_str());
+                    for subscription in all {
+                        let last_run = subscription
+                            .last_run
+                            .map(|at| format!(", last run {}", at.format("%Y-%m-%d %H:%M")))
+                            .unwrap_or_default();
+                        println!("- {}{}", subscription.describe(), last_run);
+                    }
+                }
+            }
+        }
+        Command::Schedule { once } => {
+            let llm = llm_from_env();
+            let sinks = sinks_from_env();
+            if sinks.is_empty() {
+                eprintln!("no sinks configured, the reports won't be sent anywhere");
+            }
+            let schedule = report_schedule();
+            loop {
+                if !once {
+                    let now = chrono::Utc::now();
+                    let Some(next) = schedule.next_after(now) else {
+                        eprintln!("`{}` never runs", schedule.as_str());
+                        break;
+                    };
+                    eprintln!("next reports at {}", next.format("%Y-%m-%d %H:%M UTC"));
+                    let wait = (next - now).to_std().unwrap_or_default();
+                    tokio::time::sleep(wait).await;
+                }
+                // A fresh transport per run, so the rate limit state of one doesn't carry over
+                // into the next, as in the bot's `on_schedule`.
+                let github = transport_from_env(&github_token);
+                let delivered = run_subscriptions(github.as_ref(), llm.as_ref(), &sinks).await;
+                store::flush();
+                eprintln!(
+                    "delivered {delivered} of {} scheduled reports",
+                    subscriptions().len()
+                );
+                if once {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// The renderings every kind of report has, so they are printed the same way.
+trait Rendered {
+    fn to_markdown(&self) -> String;
+    fn to_html(&self) -> String;
+    fn to_json(&self) -> String;
+}
+
+impl Rendered for Report {
+    fn to_markdown(&self) -> String {
+        Report::to_markdown(self)
+    }
+    fn to_html(&self) -> String {
+        Report::to_html(self)
+    }
+    fn to_json(&self) -> String {
+        Report::to_json(self)
+    }
+}
+
+impl Rendered for OrgReport {
+    fn to_markdown(&self) -> String {
+        OrgReport::to_markdown(self)
+    }
+    fn to_html(&self) -> String {
+        OrgReport::to_html(self)
+    }
+    fn to_json(&self) -> String {
+        OrgReport::to_json(self)
+    }
+}
+
+impl Rendered for ContributorProfile {
+    fn to_markdown(&self) -> String {
+        ContributorProfile::to_markdown(self)
+    }
+    fn to_html(&self) -> String {
+        ContributorProfile::to_html(self)
+    }
+    fn to_json(&self) -> String {
+        ContributorProfile::to_json(self)
+    }
+}
+
+impl Rendered for ActivityReport {
+    fn to_markdown(&self) -> String {
+        ActivityReport::to_markdown(self)
+    }
+    fn to_html(&self) -> String {
+        ActivityReport::to_html(self)
+    }
+    fn to_json(&self) -> String {
+        ActivityReport::to_json(self)
+    }
+}
+
+fn print_report(report: &impl Rendered, format: Format) {
+    match format {
+        Format::Markdown => print!("{}", report.to_markdown()),
+        Format::Html => print!("{}", report.to_html()),
+        Format::Json => println!("{}", report.to_json()),
+    }
+}
+
+// Markdown lists each hit as the line the bot shows, JSON has every field of every hit.
+fn print_search<T: Serialize>(
+    kind: &str,
+    query: &str,
+    hits: &[T],
+    line: fn(&T) -> String,
+    format: Format,
+) {
+    match format {
+        Format::Json => println!("{}", json!({ "query": query, "results": hits })),
+        Format::Markdown | Format::Html => {
+            println!("# {kind} matching `{query}`\n");
+            for hit in hits {
+                println!("- {}", line(hit).trim());
+            }
+        }
+    }
+}
@@ -0,0 +1,621 @@
+// The flows.network Discord bot: deployment hook, slash commands, report posting and
+// the scheduled reports.
+use crate::data_analyzers::digest_search_results;
+use crate::discord_functions::*;
+use crate::error::ResearchError;
+use crate::github_data_fetchers::{
+    search_discussions_integrated, search_issue, search_repository, search_users,
+};
+use crate::llm::llm_from_env;
+use crate::reports::{activity_report, org_report, RepoFilter, ReportEngine};
+use crate::schedule::{report_schedule, run_subscriptions, subscribe, subscriptions, unsubscribe};
+use crate::sinks::{sinks_from_env, DiscordSink};
+use crate::time_window::TimeWindow;
+use crate::transport::transport_from_env;
+use discord_flows::{
+    application_command_handler, message_handler,
+    http::Http,
+    model::{
+        // application::interaction::InteractionResponseType,
+        application_command::CommandDataOptionValue,
+        prelude::application::interaction::application_command::ApplicationCommandInteraction,
+        Message,
+    },
+    Bot, ProvidedBot,
+};
+use dotenv::dotenv;
+use flowsnet_platform_sdk::logger;
+use schedule_flows::{schedule_cron_job, schedule_handler};
+use serde_json::json;
+// Not used directly: `#[application_command_handler]` expands to code naming `Value`.
+use serde_json::Value;
+use std::env;
+use tokio::time::sleep;
+
+#[no_mangle]
+#[tokio::main(flavor = "current_thread")]
+pub async fn on_deploy() {
+    dotenv().ok();
+    logger::init();
+    let discord_token = env::var("discord_token").unwrap();
+    let channel_id = env::var("discord_channel_id").unwrap_or("channel_id not found".to_string());
+    let bot = ProvidedBot::new(&discord_token);
+    let commands_registered = env::var("COMMANDS_REGISTERED").unwrap_or("false".to_string());
+
+    if commands_registered == "false" {
+        register_commands(&discord_token).await;
+        env::set_var("COMMANDS_REGISTERED", "true");
+    }
+
+    schedule_cron_job(
+        report_schedule().as_str().to_string(),
+        String::from("scheduled reports"),
+    )
+    .await;
+
+    bot.listen_to_messages().await;
+
+    let channel_id = channel_id.parse::<u64>().unwrap();
+    bot.listen_to_application_commands_from_channel(channel_id)
+        .await;
+}
+
+#[message_handler]
+async fn handle(msg: Message) {
+    let discord_token = env::var("discord_token").unwrap();
+    let bot = ProvidedBot::new(&discord_token);
+    let client = bot.get_client();
+
+    if msg.author.bot {
+        std::process::exit(0);
+    }
+
+    _ = client
+        .send_message(
+            msg.channel_id.into(),
+            &json!({
+                "content": msg.content,
+            }),
+        )
+        .await;
+}
+
+#[application_command_handler]
+async fn handler(ac: ApplicationCommandInteraction) {
+    let github_token = env::var("github_token").unwrap_or("fake-token".to_string());
+    let token = env::var("discord_token").unwrap();
+    let _bot = ProvidedBot::new(&token);
+    let client = _bot.get_client();
+    client.set_application_id(ac.application_id.into());
+
+    let options = &ac.data.options;
+    _ = client
+        .create_interaction_response(
+            ac.id.into(),
+            &ac.token,
+            &(json!(
+                {
+                    "type": 4,
+                    "data": {
+                        "content": "🤖 ready."
+                    }
+                }
+            )),
+        )
+        .await;
+
+    match ac.data.name.as_str() {
+        "weekly_report" => _ = handle_weekly_report(client, ac, github_token).await,
+        "org_report" => handle_org_report(client, ac, github_token).await,
+        "subscribe" | "unsubscribe" => handle_subscription(client, ac).await,
+
+        "search" => handle_search(client, ac, github_token).await,
+        "contributor_profile" => handle_contributor_profile(client, ac, github_token).await,
+        "user_activity" => handle_user_activity(client, ac, github_token).await,
+        _ => {}
+    }
+}
+
+// Reports on every subscription, posted to `discord_channel_id` and the configured sinks.
+#[schedule_handler]
+async fn on_schedule(_body: Vec<u8>) {
+    dotenv().ok();
+    logger::init();
+    let github_token = env::var("github_token").unwrap_or("fake-token".to_string());
+    let github = transport_from_env(&github_token);
+    let llm = llm_from_env();
+
+    let mut sinks = sinks_from_env();
+    if let (Ok(discord_token), Some(channel_id)) = (
+        env::var("discord_token"),
+        env::var("discord_channel_id")
+            .ok()
+            .and_then(|id| id.parse::<u64>().ok()),
+    ) {
+        sinks.insert(0, Box::new(DiscordSink::new(&discord_token, channel_id)));
+    }
+
+    let delivered = run_subscriptions(github.as_ref(), llm.as_ref(), &sinks).await;
+    log::info!(
+        "delivered {} of {} scheduled reports",
+        delivered,
+        subscriptions().len()
+    );
+}
+
+async fn handle_subscription(client: Http, ac: ApplicationCommandInteraction) {
+    let options = &ac.data.options;
+    let (Some(owner), Some(repo)) = (
+        get_string_option(options, "owner"),
+        get_string_option(options, "repo"),
+    ) else {
+        _ = edit_original_wrapped(&client, &ac.token, "Please give an owner and a repo.").await;
+        return;
+    };
+    let user_name = get_string_option(options, "user_name");
+    let subject = match &user_name {
+        Some(user_name) => format!("{user_name} in `{owner}/{repo}`"),
+        None => format!("`{owner}/{repo}`"),
+    };
+    let schedule = report_schedule();
+
+    let msg = match ac.data.name.as_str() {
+        "subscribe" => match subscribe(&owner, &repo, user_name.as_deref()) {
+            true => format!(
+                "Subscribed to reports on {subject}, posted on the schedule `{}` (UTC).",
+                schedule.as_str()
+            ),
+            false => format!("There already is a subscription to reports on {subject}."),
+        },
+        _ => match unsubscribe(&owner, &repo, user_name.as_deref()) {
+            true => format!("Unsubscribed from reports on {subject}."),
+            false => format!("There is no subscription to reports on {subject}."),
+        },
+    };
+    _ = edit_original_wrapped(&client, &ac.token, &msg).await;
+}
+
+// `/search <users|repositories|issues|discussions> query [page] [digest]`
+async fn handle_search(client: Http, ac: ApplicationCommandInteraction, github_token: String) {
+    let github = transport_from_env(&github_token);
+    let github = github.as_ref();
+    let llm = llm_from_env();
+    let llm = llm.as_ref();
+
+    let Some(subcommand) = ac.data.options.first() else {
+        return;
+    };
+    let kind = subcommand.name.as_str();
+    let options = &subcommand.options;
+    let Some(query) = get_string_option(options, "query") else {
+        _ = edit_original_wrapped(&client, &ac.token, "Please give a search query.").await;
+        return;
+    };
+    let page = get_integer_option(options, "page").unwrap_or(1).max(1) as usize;
+    let digest = get_boolean_option(options, "digest").unwrap_or(false);
+
+    _ = edit_original_wrapped(
+        &client,
+        &ac.token,
+        &format!("searching GitHub {kind} matching `{query}`"),
+    )
+    .await;
+
+    // The search fetchers start with a heading line, the hits follow one per line.
+    let strip_heading = |text: String| {
+        text.split_once('\n')
+            .map(|(_, hits)| hits.to_string())
+            .unwrap_or_default()
+    };
+    let hits = match kind {
+        "users" => search_users(github, &query).await.map(strip_heading),
+        "repositories" => search_repository(github, &query).await.map(strip_heading),
+        "issues" => search_issue(github, &query).await.map(strip_heading),
+        "discussions" => search_discussions_integrated(github, llm, &query, &None)
+            .await
+            .map(|(_, discussions)| {
+                discussions
+                    .iter()
+                    .map(|gm| {
+                        format!(
+                            "**{}** <{}> by {} ({})\n{}\n\n",
+                            gm.tag_line.trim(),
+                            gm.source_url,
+                            gm.name,
+                            gm.date,
+                            gm.payload.trim()
+                        )
+                    })
+                    .collect::<String>()
+            }),
+        _ => return,
+    };
+    let hits = match hits {
+        Ok(hits) if !hits.trim().is_empty() => hits,
+        Ok(_) | Err(ResearchError::NoData(_)) => {
+            _ = edit_original_wrapped(
+                &client,
+                &ac.token,
+                &format!("No {kind} found matching `{query}`."),
+            )
+            .await;
+            return;
+        }
+        Err(e) => {
+            log::error!("failed to search {} matching {}: {}", kind, query, e);
+            _ = edit_original_wrapped(&client, &ac.token, &research_error_message(&e, "", &query))
+                .await;
+            return;
+        }
+    };
+
+    let pages = search_pages(&format!("**GitHub {kind} matching `{query}`**"), &hits);
+    let msg = match pages.get(page - 1) {
+        Some(content) => content.clone(),
+        None => format!(
+            "There are only {} pages of {kind} matching `{query}`.",
+            pages.len()
+        ),
+    };
+    _ = edit_original_wrapped(&client, &ac.token, &msg).await;
+
+    if digest {
+        let msg = match digest_search_results(llm, kind, &query, &hits).await {
+            Ok(digest) => format!("**Digest**\n{digest}"),
+            Err(e) => research_error_message(&e, "", &query),
+        };
+        for chunk in split_message(&msg, MESSAGE_LIMIT) {
+            _ = send_followup(&client, &ac.token, &json!({ "content": chunk })).await;
+        }
+    }
+}
+
+async fn handle_contributor_profile(
+    client: Http,
+    ac: ApplicationCommandInteraction,
+    github_token: String,
+) {
+    let github = transport_from_env(&github_token);
+    let github = github.as_ref();
+    let llm = llm_from_env();
+    let llm = llm.as_ref();
+    let options = &ac.data.options;
+    let (Some(user_name), Some(owner), Some(repo)) = (
+        get_string_option(options, "user_name"),
+        get_string_option(options, "owner"),
+        get_string_option(options, "repo"),
+    ) else {
+        _ = edit_original_wrapped(
+            &client,
+            &ac.token,
+            "Please give a user, an owner and a repo.",
+        )
+        .await;
+        return;
+    };
+    let days = get_integer_option(options, "days").unwrap_or(30);
+
+    _ = edit_original_wrapped(
+        &client,
+        &ac.token,
+        &format!("exploring how {user_name} could fit `{owner}/{repo}`"),
+    )
+    .await;
+
+    let engine = ReportEngine::new(github, llm, &owner, &repo)
+        .with_user(Some(user_name.clone()))
+        .with_days(days);
+    match engine.contributor_profile().await {
+        Ok(profile) => {
+            _ = edit_original_chunked(&client, &ac.token, &profile.to_markdown()).await;
+        }
+        Err(e) => {
+            log::error!("failed to profile {}: {}", user_name, e);
+            let msg = match e {
+                ResearchError::NotFound(_) => {
+                    format!("{user_name} isn't a GitHub user, please check the name.")
+                }
+                e => research_error_message(&e, &owner, &repo),
+            };
+            _ = edit_original_wrapped(&client, &ac.token, &msg).await;
+        }
+    }
+}
+
+async fn handle_user_activity(
+    client: Http,
+    ac: ApplicationCommandInteraction,
+    github_token: String,
+) {
+    let github = transport_from_env(&github_token);
+    let github = github.as_ref();
+    let llm = llm_from_env();
+    let llm = llm.as_ref();
+    let options = &ac.data.options;
+    let Some(user_name) = get_string_option(options, "user_name") else {
+        _ = edit_original_wrapped(&client, &ac.token, "Please give a GitHub user.").await;
+        return;
+    };
+    // Without a repo, release tags can't be used for `since` and `until`. `days` is 30
+    // by default here, unless a `period` is given instead.
+    let period = get_string_option(options, "period");
+    let days = get_integer_option(options, "days");
+    let window = match TimeWindow::resolve(
+        github,
+        "",
+        "",
+        days.or(period.is_none().then_some(30)),
+        None,
+        None,
+        period.as_deref(),
+    )
+    .await
+    {
+        Ok(window) => window,
+        Err(e) => {
+            _ = edit_original_wrapped(&client, &ac.token, &research_error_message(&e, "", ""))
+                .await;
+            return;
+        }
+    };
+
+    _ = edit_original_wrapped(
+        &client,
+        &ac.token,
+        &format!(
+            "exploring {user_name}'s contributions across GitHub {}",
+            window.describe()
+        ),
+    )
+    .await;
+
+    match activity_report(github, llm, &user_name, window).await {
+        Ok(report) => {
+            _ = edit_original_chunked(&client, &ac.token, &report.to_markdown()).await;
+        }
+        Err(e) => {
+            log::error!("failed to report on the activity of {}: {}", user_name, e);
+            let msg = match e {
+                ResearchError::NotFound(_) => {
+                    format!("{user_name} isn't a GitHub user, please check the name.")
+                }
+                e => research_error_message(&e, "", ""),
+            };
+            _ = edit_original_wrapped(&client, &ac.token, &msg).await;
+        }
+    }
+}
+
+async fn handle_org_report(client: Http, ac: ApplicationCommandInteraction, github_token: String) {
+    let github = transport_from_env(&github_token);
+    let github = github.as_ref();
+    let llm = llm_from_env();
+    let llm = llm.as_ref();
+    let options = &ac.data.options;
+    let Some(owner) = get_string_option(options, "owner") else {
+        _ = edit_original_wrapped(&client, &ac.token, "Please give an organization.").await;
+        return;
+    };
+    let filter = RepoFilter {
+        topic: get_string_option(options, "topic"),
+        include_archived: get_boolean_option(options, "include_archived").unwrap_or(false),
+        repos: get_string_option(options, "repos")
+            .map(|repos| {
+                repos
+                    .split(',')
+                    .map(|repo| repo.trim().to_string())
+                    .filter(|repo| !repo.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    let user_name = get_string_option(options, "user_name");
+    let window = match TimeWindow::resolve(
+        github,
+        "",
+        "",
+        get_integer_option(options, "days"),
+        None,
+        None,
+        get_string_option(options, "period").as_deref(),
+    )
+    .await
+    {
+        Ok(window) => window,
+        Err(e) => {
+            _ = edit_original_wrapped(&client, &ac.token, &research_error_message(&e, &owner, "*"))
+                .await;
+            return;
+        }
+    };
+
+    _ = edit_original_wrapped(
+        &client,
+        &ac.token,
+        &format!(
+            "exploring the repositories of `{owner}` {}, one after another",
+            window.describe()
+        ),
+    )
+    .await;
+
+    match org_report(github, llm, &owner, &filter, user_name, window).await {
+        Ok(report) => {
+            _ = edit_original_chunked(&client, &ac.token, &report.to_markdown()).await;
+        }
+        Err(e) => {
+            log::error!("failed to report on {}: {}", owner, e);
+            let msg = match e {
+                ResearchError::NotFound(_) => {
+                    format!("{owner} isn't a GitHub organization or user, please check the name.")
+                }
+                ResearchError::NoData(_) => {
+                    format!("No repositories of {owner} match, please check the filters.")
+                }
+                e => research_error_message(&e, &owner, "*"),
+            };
+            _ = edit_original_wrapped(&client, &ac.token, &msg).await;
+        }
+    }
+}
+
+async fn handle_weekly_report(
+    client: Http,
+    ac: ApplicationCommandInteraction,
+    github_token: String,
+) {
+    let github = transport_from_env(&github_token);
+    let github = github.as_ref();
+    let llm = llm_from_env();
+    let llm = llm.as_ref();
+    let options = &ac.data.options;
+    let owner = match options
+        .first()
+        .expect("Expected owner option")
+        .resolved
+        .as_ref()
+        .expect("Expected owner object")
+    {
+        CommandDataOptionValue::String(s) => s,
+        _ => panic!("Expected string for owner"),
+    };
+    let repo = match options
+        .get(1)
+        .expect("Expected repo option")
+        .resolved
+        .as_ref()
+        .expect("Expected repo object")
+    {
+        CommandDataOptionValue::String(s) => s,
+        _ => panic!("Expected string for repo"),
+    };
+    let user_name = get_string_option(options, "user_name");
+    let window = match TimeWindow::resolve(
+        github,
+        owner,
+        repo,
+        get_integer_option(options, "days"),
+        get_string_option(options, "since").as_deref(),
+        get_string_option(options, "until").as_deref(),
+        get_string_option(options, "period").as_deref(),
+    )
+    .await
+    {
+        Ok(window) => window,
+        Err(e) => {
+            log::error!("failed to resolve the time window: {}", e);
+            _ = edit_original_wrapped(&client, &ac.token, &research_error_message(&e, owner, repo))
+                .await;
+
+            std::process::exit(0);
+        }
+    };
+
+    let engine = ReportEngine::new(github, llm, owner, repo)
+        .with_user(user_name.clone())
+        .with_window(window);
+    let mut report = engine.new_report();
+
+    match engine.profile().await {
+        Err(e) => {
+            log::error!("failed to get the profile of {}/{}: {}", owner, repo, e);
+            _ = edit_original_wrapped(&client, &ac.token, &research_error_message(&e, owner, repo))
+                .await;
+
+            std::process::exit(0);
+        }
+        Ok(gm) => report.profile = Some(gm),
+    }
+
+    let addressee_str = match &user_name {
+        Some(user_name) => format!("{user_name}'s"),
+        None => String::from("key community participants'"),
+    };
+    let msg_content = match &user_name {
+        Some(_) if engine.is_code_contributor().await => String::new(),
+        Some(user_name) => format!("{user_name} hasn't contributed code to {owner}/{repo}. Bot will try to find out {user_name}'s other contributions; `/user_activity` shows their work across GitHub."),
+        None => format!(
+            "You didn't input a user's name. Bot will then create a report on the progress of {owner}/{repo} {}.",
+            window.describe()
+        ),
+    };
+    if !msg_content.is_empty() {
+        _ = edit_original_wrapped(&client, &ac.token, &msg_content).await;
+    }
+    sleep(tokio::time::Duration::from_secs(2)).await;
+    _ = edit_original_wrapped(
+        &client,
+        &ac.token,
+        &format!("exploring {addressee_str} GitHub contributions to `{owner}/{repo}` project"),
+    )
+    .await;
+
+    // Sections are gathered concurrently; each one reports its findings as soon as it is done.
+    let progress = |text: String| {
+        let (client, token) = (&client, &ac.token);
+        async move {
+            _ = edit_original_wrapped(client, token, &text).await;
+        }
+    };
+    (
+        report.commits,
+        report.issues,
+        (report.pull_requests, report.reviews),
+        report.discussions,
+    ) = futures::join!(
+        async {
+            let section = engine.commits().await;
+            progress(format!(
+                "found {} commits:\n{}",
+                section.found.len(),
+                section.found.join("\n")
+            ))
+            .await;
+            section
+        },
+        async {
+            let section = engine.issues().await;
+            progress(format!(
+                "found {} issues:\n{}",
+                section.found.len(),
+                section.found.join("\n")
+            ))
+            .await;
+            section
+        },
+        async {
+            let (section, reviews) = engine.pull_requests_and_reviews().await;
+            progress(format!(
+                "found {} pull requests:\n{}",
+                section.found.len(),
+                section.found.join("\n")
+            ))
+            .await;
+            progress(format!(
+                "found review activity from {} reviewers",
+                reviews.len()
+            ))
+            .await;
+            (section, reviews)
+        },
+        async {
+            let section = engine.discussions().await;
+            progress(format!(
+                "{} discussions were referenced in analysis:\n {}",
+                section.found.len(),
+                section.found.join("\n")
+            ))
+            .await;
+            section
+        }
+    );
+
+    report.rate_limited = engine.rate_limited();
+    // The sections are still worth posting when the language model can't relate them.
+    match engine.synthesize(&report).await {
+        Ok(synthesis) => report.synthesis = Some(synthesis),
+        Err(e) if !report.is_empty() => log::error!("failed to synthesize the report: {}", e),
+        Err(_) => {}
+    }
+    _ = post_report(&client, &ac.token, &report).await;
+}
@@ -1,38 +1,41 @@
+use crate::error::{ResearchError, ResearchResult};
 use crate::github_data_fetchers::*;
+use crate::llm::LlmBackend;
 use crate::octocrab_compat::{Comment, Issue};
+use crate::transport::GitHubTransport;
 use crate::utils::*;
 use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
 use log;
-use openai_flows::{
-    self,
-    chat::{self, ChatOptions},
-    OpenAIFlows,
-};
 use serde::Deserialize;
 
+// The `process_*` steps stop analyzing a section once it has more items than this.
+pub const MAX_ITEMS_PER_SECTION: usize = 16;
+
 pub async fn is_valid_owner_repo_integrated(
-    github_token: &str,
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
     owner: &str,
     repo: &str,
-) -> Option<GitMemory> {
+) -> ResearchResult<GitMemory> {
     #[derive(Deserialize)]
     struct CommunityProfile {
-        health_percentage: u16,
         description: Option<String>,
-        readme: Option<String>,
         updated_at: Option<DateTime<Utc>>,
     }
-    let openai = OpenAIFlows::new();
 
     let community_profile_url = format!(
-        "https://api.github.com/repos/{}/{}/community/profile",
-        owner, repo
+        "{}/repos/{}/{}/community/profile",
+        github.base_url(),
+        owner,
+        repo
     );
 
     let mut description = String::new();
     let mut date = Utc::now().date_naive();
-    match github_http_fetch(&github_token, &community_profile_url).await {
-        Some(res) => match serde_json::from_slice::<CommunityProfile>(&res) {
+    let mut fetch_error = None;
+    match github_http_fetch(github, &community_profile_url).await {
+        Ok(res) => match serde_json::from_slice::<CommunityProfile>(&res) {
             Ok(profile) => {
                 description = profile
                     .description
@@ -47,25 +50,36 @@ pub async fn is_valid_owner_repo_integrated(
             }
             Err(e) => log::error!("Error parsing Community Profile: {:?}", e),
         },
-        None => log::error!(
-            "Error fetching Community Profile: {:?}",
-            community_profile_url
-        ),
+        Err(e) => {
+            log::error!(
+                "Error fetching Community Profile: {:?}",
+                community_profile_url
+            );
+            fetch_error = Some(e);
+        }
     }
 
     let mut payload = String::new();
-    match get_readme(github_token, owner, repo).await {
-        Some(content) => {
+    match get_readme(github, owner, repo).await {
+        Ok(content) => {
             let content = squeeze_fit_post_texts(&content, 12_000, 0.6);
-            match analyze_readme(&content).await {
-                Some(summary) => payload = summary,
-                None => log::error!("Error parsing README.md: {}/{}", owner, repo),
+            match analyze_readme(llm, &content).await {
+                Ok(summary) => payload = summary,
+                Err(e) => {
+                    log::error!("Error parsing README.md: {}/{}", owner, repo);
+                    fetch_error.get_or_insert(e);
+                }
             }
         }
-        None => log::error!("Error fetching README.md: {}/{}", owner, repo),
+        Err(e) => {
+            log::error!("Error fetching README.md: {}/{}", owner, repo);
+            fetch_error.get_or_insert(e);
+        }
     };
     if description.is_empty() && payload.is_empty() {
-        return None;
+        // The community profile error says best why the repo is unusable, e.g. a 404.
+        return Err(fetch_error
+            .unwrap_or_else(|| ResearchError::NoData(format!("profile of {owner}/{repo}"))));
     }
 
     if description.is_empty() {
@@ -74,34 +88,56 @@ pub async fn is_valid_owner_repo_integrated(
         payload = description.clone();
     }
 
-    Some(GitMemory {
+    Ok(GitMemory {
         memory_type: MemoryType::Meta,
         name: format!("{}/{}", owner, repo),
         tag_line: description,
         source_url: community_profile_url,
-        payload: payload,
-        date: date,
+        payload,
+        date,
     })
 }
 
+// Summarizes up to `concurrency` issues at once; results keep the input order.
 pub async fn process_issues(
-    github_token: &str,
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
     inp_vec: Vec<Issue>,
     target_person: Option<String>,
-) -> Option<(String, usize, Vec<GitMemory>)> {
+    concurrency: usize,
+) -> ResearchResult<(String, usize, Vec<GitMemory>)> {
     let mut issues_summaries = String::new();
     let mut git_memory_vec = vec![];
+    let mut last_error = None;
+
+    let mut results = stream::iter(&inp_vec)
+        .map(|issue| {
+            let target_person = target_person.clone();
+            async move {
+                // Don't queue more fetches once GitHub has started refusing them.
+                if github.was_rate_limited() {
+                    return Err(ResearchError::RateLimited(issue.url.to_string()));
+                }
+                analyze_issue_integrated(github, llm, issue, target_person).await
+            }
+        })
+        .buffered(concurrency.max(1));
 
     for issue in &inp_vec {
-        match analyze_issue_integrated(github_token, issue, target_person.clone()).await {
-            None => {
-                log::error!("Error analyzing issue: {:?}", issue.url.to_string());
+        let result = match results.next().await {
+            Some(result) => result,
+            None => break,
+        };
+        match result {
+            Err(e) => {
+                log::error!("Error analyzing issue {:?}: {}", issue.url.to_string(), e);
+                last_error = Some(e);
                 continue;
             }
-            Some((summary, gm)) => {
+            Ok((summary, gm)) => {
                 issues_summaries.push_str(&format!("{} {}\n", gm.date, summary));
                 git_memory_vec.push(gm);
-                if git_memory_vec.len() > 16 {
+                if git_memory_vec.len() > MAX_ITEMS_PER_SECTION {
                     break;
                 }
             }
@@ -111,48 +147,202 @@ pub async fn process_issues(
     let count = git_memory_vec.len();
     if count == 0 {
         log::error!("No issues processed");
-        return None;
+        return Err(last_error.unwrap_or(ResearchError::NoData("issues".to_string())));
+    }
+    Ok((issues_summaries, count, git_memory_vec))
+}
+
+pub async fn process_pull_requests(
+    llm: &dyn LlmBackend,
+    inp_vec: Vec<PullRequest>,
+    target_person: Option<String>,
+    concurrency: usize,
+) -> ResearchResult<(String, usize, Vec<GitMemory>)> {
+    let mut pull_requests_summaries = String::new();
+    let mut git_memory_vec = vec![];
+    let mut last_error = None;
+
+    let mut results = stream::iter(&inp_vec)
+        .map(|pull_request| analyze_pull_request(llm, pull_request, target_person.clone()))
+        .buffered(concurrency.max(1));
+
+    for pull_request in &inp_vec {
+        let result = match results.next().await {
+            Some(result) => result,
+            None => break,
+        };
+        match result {
+            Err(e) => {
+                log::error!("Error analyzing pull request {:?}: {}", pull_request.url, e);
+                last_error = Some(e);
+                continue;
+            }
+            Ok((summary, gm)) => {
+                pull_requests_summaries.push_str(&format!("{} {}\n", gm.date, summary));
+                git_memory_vec.push(gm);
+                if git_memory_vec.len() > MAX_ITEMS_PER_SECTION {
+                    break;
+                }
+            }
+        }
+    }
+
+    let count = git_memory_vec.len();
+    if count == 0 {
+        log::error!("No pull requests processed");
+        return Err(last_error.unwrap_or(ResearchError::NoData("pull requests".to_string())));
     }
-    Some((issues_summaries, count, git_memory_vec))
+    Ok((pull_requests_summaries, count, git_memory_vec))
 }
-pub async fn analyze_readme(content: &str) -> Option<String> {
-    let openai = OpenAIFlows::new();
+
+pub async fn analyze_pull_request(
+    llm: &dyn LlmBackend,
+    pull_request: &PullRequest,
+    target_person: Option<String>,
+) -> ResearchResult<(String, GitMemory)> {
+    let pr_number = pull_request.number;
+    let pr_title = pull_request.title.to_string();
+    let pr_author = &pull_request.author;
+    let pr_url = &pull_request.url;
+
+    let pr_body = squeeze_fit_remove_quoted(&pull_request.body, "```", 500, 0.6);
+    let outcome = match (&pull_request.merged_at, &pull_request.closed_at) {
+        (Some(date), _) => format!("merged on {}", date.date_naive()),
+        (None, Some(date)) => format!("closed without merging on {}", date.date_naive()),
+        (None, None) => "still open".to_string(),
+    };
+    let linked_issues = match pull_request.linked_issues.is_empty() {
+        true => String::new(),
+        false => format!(
+            " It closes issues: {}.",
+            pull_request.linked_issues.join(", ")
+        ),
+    };
+
+    let mut all_text_from_pr = format!(
+        "User '{}' opened a pull request titled '{}', {}, changing {} files (+{} -{}), with the following description: '{}'.{}",
+        pr_author,
+        pr_title,
+        outcome,
+        pull_request.changed_files,
+        pull_request.additions,
+        pull_request.deletions,
+        pr_body,
+        linked_issues
+    );
+
+    for review in &pull_request.reviews {
+        let review_body = squeeze_fit_remove_quoted(&review.body, "```", 300, 0.6);
+        all_text_from_pr.push_str(&format!(
+            " {} reviewed ({}): {}",
+            review.reviewer, review.state, review_body
+        ));
+        for comment in &review.comments {
+            let comment_body = squeeze_fit_remove_quoted(comment, "```", 200, 0.6);
+            all_text_from_pr.push_str(&format!(" {} commented: {}", review.reviewer, comment_body));
+        }
+    }
+    let all_text_from_pr = squeeze_fit_remove_quoted(&all_text_from_pr, "```", 9000, 0.4);
+    let target_str = target_person
+        .clone()
+        .unwrap_or("key participants".to_string());
 
     let sys_prompt_1 = &format!(
-        "Your task is to objectively analyze a GitHub profile and the README of their project. Focus on extracting factual information about the features of the project, and its stated objectives. Avoid making judgments or inferring subjective value."
+        "Given the information that user '{pr_author}' opened a pull request titled '{pr_title}', your task is to analyze the proposed change and its review. Identify the purpose of the change, its scope, how the review went, and the significant contributions of the author and the reviewers."
     );
 
-    let co = ChatOptions {
-        model: chat::ChatModel::GPT35Turbo16K,
-        system_prompt: Some(sys_prompt_1),
-        restart: true,
-        temperature: Some(0.7),
-        max_tokens: Some(256),
-        ..Default::default()
+    let max_tokens = match all_text_from_pr.len() > 12000 {
+        true => 192,
+        false => 128,
     };
+    let usr_prompt_1 = &format!(
+        "Analyze the GitHub pull request content: {all_text_from_pr}. Provide a concise analysis touching upon: The problem the pull request addresses. The main changes and their size. The key points raised in review and the outcome. Emphasize the role and significance of '{target_str}' as author or reviewer. Aim for a succinct, analytical summary that stays under 128 tokens."
+    );
+
+    // The reviews and description above were trimmed; the key covers all of them.
+    let source_text = serde_json::to_string(pull_request).unwrap_or_default();
+    match cached_complete(
+        llm,
+        pr_url,
+        &source_text,
+        &format!("pr_{pr_number}"),
+        sys_prompt_1,
+        usr_prompt_1,
+        max_tokens,
+    )
+    .await
+    {
+        Ok(r) => {
+            let mut out = format!("{pr_url} ");
+            out.push_str(&r);
+            let name = target_person.unwrap_or(pr_author.to_string());
+            let gm = GitMemory {
+                memory_type: MemoryType::PullRequest,
+                name,
+                tag_line: pr_title,
+                source_url: pr_url.to_string(),
+                payload: r,
+                date: pull_request
+                    .merged_at
+                    .unwrap_or(pull_request.created_at)
+                    .date_naive(),
+            };
+
+            Ok((out, gm))
+        }
+        Err(e) => {
+            log::error!("Error generating pull request summary #{}", pr_number);
+            Err(e)
+        }
+    }
+}
+
+pub fn summarize_review_activity(activity: &[ReviewerActivity]) -> String {
+    let mut out = String::from("REVIEWS \n");
+    for reviewer in activity {
+        let turnaround_str = match reviewer.average_turnaround_hours() {
+            Some(hours) => format!(", average turnaround {:.1} hours", hours),
+            None => String::new(),
+        };
+        out.push_str(&format!(
+            "{} reviewed {} pull requests: {} approvals, {} change requests, {} comment reviews, {} review comments{}\n",
+            reviewer.login,
+            reviewer.pull_requests.len(),
+            reviewer.approvals,
+            reviewer.change_requests,
+            reviewer.commented_reviews,
+            reviewer.review_comments,
+            turnaround_str
+        ));
+    }
+    out
+}
+
+pub async fn analyze_readme(llm: &dyn LlmBackend, content: &str) -> ResearchResult<String> {
+    let sys_prompt_1 = "Your task is to objectively analyze a GitHub profile and the README of their project. Focus on extracting factual information about the features of the project, and its stated objectives. Avoid making judgments or inferring subjective value.";
+
     let usr_prompt_1 = &format!(
         "Based on the profile and README provided: {content}, extract a concise summary detailing this project's factual significance in its domain, their areas of expertise, and the main features and goals of the project. Ensure the insights are objective and under 110 tokens."
     );
 
-    match openai
-        .chat_completion(&format!("profile-99"), usr_prompt_1, &co)
+    match llm
+        .complete("profile-99", sys_prompt_1, usr_prompt_1, 256)
         .await
     {
-        Ok(r) => Some(r.choice),
-        Err(e) => {
-            log::error!("Error summarizing meta data: {}", e);
-            None
+        Some(r) => Ok(r),
+        None => {
+            log::error!("Error summarizing meta data");
+            Err(ResearchError::Llm("profile-99".to_string()))
         }
     }
 }
 
 pub async fn analyze_issue_integrated(
-    github_token: &str,
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
     issue: &Issue,
     target_person: Option<String>,
-) -> Option<(String, GitMemory)> {
-    let openai = OpenAIFlows::new();
-
+) -> ResearchResult<(String, GitMemory)> {
     let issue_creator_name = &issue.user.login;
     let issue_title = issue.title.to_string();
     let issue_number = issue.number;
@@ -176,13 +366,15 @@ pub async fn analyze_issue_integrated(
         "User '{}', opened an issue titled '{}', labeled '{}', with the following post: '{}'.",
         issue_creator_name, issue_title, labels, issue_body
     );
+    // The untrimmed post and comments, for the summary cache key.
+    let mut source_text = format!("{labels}\n{}\n", issue.body.as_deref().unwrap_or(""));
 
     let mut current_page = 1;
     loop {
         let url_str = format!("{}/comments?&page={}", issue_url, current_page);
 
-        match github_http_fetch(&github_token, &url_str).await {
-            Some(res) => match serde_json::from_slice::<Vec<Comment>>(res.as_slice()) {
+        match github_http_fetch(github, &url_str).await {
+            Ok(res) => match serde_json::from_slice::<Vec<Comment>>(res.as_slice()) {
                 Err(_e) => {
                     log::error!(
                         "Error parsing Vec<Comment> at page {}: {:?}",
@@ -196,6 +388,11 @@ pub async fn analyze_issue_integrated(
                         break;
                     }
                     for comment in &comments_obj {
+                        source_text.push_str(&format!(
+                            "{}: {}\n",
+                            comment.user.login,
+                            comment.body.as_deref().unwrap_or("")
+                        ));
                         let comment_body = match &comment.body {
                             Some(body) => squeeze_fit_remove_quoted(body, "```", 300, 0.6),
                             None => "".to_string(),
@@ -207,7 +404,7 @@ pub async fn analyze_issue_integrated(
                     }
                 }
             },
-            None => {
+            Err(_) => {
                 break;
             }
         }
@@ -223,82 +420,65 @@ pub async fn analyze_issue_integrated(
         "Given the information that user '{issue_creator_name}' opened an issue titled '{issue_title}', your task is to deeply analyze the content of the issue posts. Distill the crux of the issue, the potential solutions suggested, and evaluate the significant contributions of the participants in resolving or progressing the discussion."
     );
 
-    let co = match all_text_from_issue.len() > 12000 {
-        true => ChatOptions {
-            model: chat::ChatModel::GPT35Turbo16K,
-            system_prompt: Some(sys_prompt_1),
-            restart: true,
-            temperature: Some(0.7),
-            max_tokens: Some(192),
-            ..Default::default()
-        },
-        false => ChatOptions {
-            model: chat::ChatModel::GPT35Turbo,
-            system_prompt: Some(sys_prompt_1),
-            restart: true,
-            temperature: Some(0.7),
-            max_tokens: Some(128),
-            ..Default::default()
-        },
+    let max_tokens = match all_text_from_issue.len() > 12000 {
+        true => 192,
+        false => 128,
     };
     let usr_prompt_1 = &format!(
         "Analyze the GitHub issue content: {all_text_from_issue}. Provide a concise analysis touching upon: The central problem discussed in the issue. The main solutions proposed or agreed upon. Emphasize the role and significance of '{target_str}' in contributing towards the resolution or progression of the discussion. Aim for a succinct, analytical summary that stays under 128 tokens."
     );
 
-    match openai
-        .chat_completion(&format!("issue_{issue_number}"), usr_prompt_1, &co)
-        .await
+    match cached_complete(
+        llm,
+        &issue_url,
+        &source_text,
+        &format!("issue_{issue_number}"),
+        sys_prompt_1,
+        usr_prompt_1,
+        max_tokens,
+    )
+    .await
     {
         Ok(r) => {
             let mut out = format!("{issue_url} ");
-            out.push_str(&r.choice);
+            out.push_str(&r);
             let name = target_person
                 .unwrap_or(issue_creator_name.to_string())
                 .to_string();
             let gm = GitMemory {
                 memory_type: MemoryType::Issue,
-                name: name,
+                name,
                 tag_line: issue_title,
-                source_url: source_url,
-                payload: r.choice,
+                source_url,
+                payload: r,
                 date: issue_date,
             };
 
-            Some((out, gm))
+            Ok((out, gm))
         }
-        Err(_e) => {
-            log::error!("Error generating issue summary #{}: {}", issue_number, _e);
-            None
+        Err(e) => {
+            log::error!("Error generating issue summary #{}", issue_number);
+            Err(e)
         }
     }
 }
 
 pub async fn analyze_commit_integrated(
-    github_token: &str,
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
     user_name: &str,
     tag_line: &str,
     url: &str,
-) -> Option<String> {
-    let openai = OpenAIFlows::new();
-
+) -> ResearchResult<String> {
     let commit_patch_str = format!("{url}.patch");
-    let uri = http_req::uri::Uri::try_from(commit_patch_str.as_str())
-        .expect(&format!("Error generating URI from {:?}", commit_patch_str));
-    let mut writer = Vec::new();
-    match http_req::request::Request::new(&uri)
-        .method(http_req::request::Method::GET)
-        .header("User-Agent", "flows-network connector")
-        .header("Content-Type", "plain/text")
-        .header("Authorization", &format!("Bearer {github_token}"))
-        .send(&mut writer)
-    {
+    match github.get_patch(&commit_patch_str).await {
         Ok(res) => {
-            if !res.status_code().is_success() {
-                log::error!("Github http error {:?}", res.status_code());
-                return None;
+            if !res.is_success() {
+                log::error!("Github http error {:?}", res.status);
+                return Err(ResearchError::from_response(&commit_patch_str, &res));
             };
 
-            let text = String::from_utf8_lossy(writer.as_slice());
+            let text = String::from_utf8_lossy(res.body.as_slice());
 
             let mut stripped_texts = String::with_capacity(text.len());
             let mut inside_diff_block = false;
@@ -319,13 +499,12 @@ pub async fn analyze_commit_integrated(
                     continue;
                 }
 
-                if inside_diff_block {
-                    if line
+                if inside_diff_block
+                    && line
                         .chars()
                         .any(|ch| ch == '[' || ch == ']' || ch == '{' || ch == '}')
-                    {
-                        continue;
-                    }
+                {
+                    continue;
                 }
 
                 stripped_texts.push_str(line);
@@ -339,185 +518,218 @@ pub async fn analyze_commit_integrated(
                 "Given a commit patch from the user {user_name}, you are to analyze its content. Focus on the core essence of the changes without delving into granular technical specifics. Particularly, identify the purpose of the changes, the files impacted, and the broader implications for the project. Remember to strike a balance between brevity and capturing the essential details."
             );
 
-            let co = match stripped_texts.len() > 12000 {
-                true => ChatOptions {
-                    model: chat::ChatModel::GPT35Turbo16K,
-                    system_prompt: Some(sys_prompt_1),
-                    restart: true,
-                    temperature: Some(0.7),
-                    max_tokens: Some(192),
-                    ..Default::default()
-                },
-                false => ChatOptions {
-                    model: chat::ChatModel::GPT35Turbo,
-                    system_prompt: Some(sys_prompt_1),
-                    restart: true,
-                    temperature: Some(0.7),
-                    max_tokens: Some(128),
-                    ..Default::default()
-                },
+            let max_tokens = match stripped_texts.len() > 12000 {
+                true => 192,
+                false => 128,
             };
             let usr_prompt_1 = &format!(
                 "Analyze the commit patch: {stripped_texts}, and its description: {tag_line}. Summarize the main changes, emphasizing the intent behind the modifications and their implications for the project. Ensure clarity, but avoid granular technical details. Distinguish between core code and other types of changes. Conclude with a brief evaluation of {user_name}'s contributions in this commit and its potential impact on the project. Keep your response concise and under 110 tokens."
             );
 
-            let sha_serial = match url.rsplitn(2, "/").nth(0) {
+            let sha_serial = match url.rsplit('/').next() {
                 Some(s) => s.chars().take(5).collect::<String>(),
                 None => "0000".to_string(),
             };
-            match openai
-                .chat_completion(&format!("commit-{sha_serial}"), usr_prompt_1, &co)
-                .await
+            match cached_complete(
+                llm,
+                url,
+                &text,
+                &format!("commit-{sha_serial}"),
+                sys_prompt_1,
+                usr_prompt_1,
+                max_tokens,
+            )
+            .await
             {
                 Ok(r) => {
                     let mut out = format!("{} ", url);
-                    out.push_str(&r.choice);
-                    Some(out)
+                    out.push_str(&r);
+                    Ok(out)
                 }
-                Err(_e) => {
-                    log::error!("Error generating issue summary #{}: {}", sha_serial, _e);
-                    None
+                Err(e) => {
+                    log::error!("Error generating issue summary #{}", sha_serial);
+                    Err(e)
                 }
             }
         }
         Err(_e) => {
             log::error!("Error getting response from Github: {:?}", _e);
-            None
+            Err(_e.into())
         }
     }
 }
 
-pub async fn process_commits(github_token: &str, inp_vec: &mut Vec<GitMemory>) -> Option<String> {
+// Summarizes up to `concurrency` commits at once, stopping after 20 successes.
+pub async fn process_commits(
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
+    inp_vec: &mut [GitMemory],
+    concurrency: usize,
+) -> ResearchResult<String> {
     let mut commits_summaries = String::new();
+    let mut last_error = None;
 
     let max_entries = 20; // Maximum entries to process
-    let mut processed_count = 0; // Number of processed entries
-
-    for commit_obj in inp_vec.iter_mut() {
-        if processed_count >= max_entries {
-            break;
-        }
-
-        match analyze_commit_integrated(
-            github_token,
-            &commit_obj.name,
-            &commit_obj.tag_line,
-            &commit_obj.source_url,
-        )
-        .await
-        {
-            Some(summary) => {
-                commit_obj.payload = summary;
-
-                if commits_summaries.len() <= 45_000 {
-                    commits_summaries
-                        .push_str(&format!("{} {}\n", commit_obj.date, commit_obj.payload));
+    let mut summaries = Vec::new();
+
+    let mut results = stream::iter(inp_vec.iter().enumerate())
+        .map(|(index, commit_obj)| async move {
+            // Don't queue more fetches once GitHub has started refusing them.
+            if github.was_rate_limited() {
+                return (
+                    index,
+                    Err(ResearchError::RateLimited(commit_obj.source_url.clone())),
+                );
+            }
+            let result = analyze_commit_integrated(
+                github,
+                llm,
+                &commit_obj.name,
+                &commit_obj.tag_line,
+                &commit_obj.source_url,
+            )
+            .await;
+            (index, result)
+        })
+        .buffered(concurrency.max(1));
+
+    while let Some((index, result)) = results.next().await {
+        match result {
+            Ok(summary) => {
+                summaries.push((index, summary));
+                if summaries.len() >= max_entries {
+                    break;
                 }
-
-                processed_count += 1;
             }
-            None => {
+            Err(e) => {
                 log::error!(
-                    "Error analyzing commit {:?} for user {}",
-                    commit_obj.source_url,
-                    commit_obj.name
+                    "Error analyzing commit {:?} for user {}: {}",
+                    inp_vec[index].source_url,
+                    inp_vec[index].name,
+                    e
                 );
+                last_error = Some(e);
             }
         }
     }
+    drop(results);
+
+    let processed_count = summaries.len();
+    for (index, summary) in summaries {
+        let commit_obj = &mut inp_vec[index];
+        commit_obj.payload = summary;
+
+        if commits_summaries.len() <= 45_000 {
+            commits_summaries.push_str(&format!("{} {}\n", commit_obj.date, commit_obj.payload));
+        }
+    }
 
     if processed_count == 0 {
         log::error!("No commits processed");
-        return None;
+        return Err(last_error.unwrap_or(ResearchError::NoData("commits".to_string())));
     }
 
-    Some(commits_summaries)
+    Ok(commits_summaries)
 }
 
 pub async fn correlate_commits_issues(
+    llm: &dyn LlmBackend,
     _commits_summary: &str,
     _issues_summary: &str,
-) -> Option<String> {
+) -> ResearchResult<String> {
     let (commits_summary, issues_summary) =
         squeeze_fit_commits_issues(_commits_summary, _issues_summary, 0.6);
 
-    let sys_prompt_1 = &format!(
-        "Your task is to identify the 1-3 most impactful contributions by a specific user, based on the given commit logs and issue records. Pay close attention to any sequential relationships between issues and commits, and consider how they reflect the user's growth and evolution within the project. Use this data to evaluate the user's overall influence on the project's development. Provide a concise summary in bullet-point format."
-    );
+    let sys_prompt_1 = "Your task is to identify the 1-3 most impactful contributions by a specific user, based on the given commit logs and issue records. Pay close attention to any sequential relationships between issues and commits, and consider how they reflect the user's growth and evolution within the project. Use this data to evaluate the user's overall influence on the project's development. Provide a concise summary in bullet-point format.";
 
     let usr_prompt_1 = &format!(
         "Given the commit logs: {commits_summary} and issue records: {issues_summary}, identify the most significant contributions made by the user. Look for patterns and sequences of events that indicate the user's growth and how they approached problem-solving. Consider major code changes, and initiatives that had substantial impact on the project. Additionally, note any instances where the resolution of an issue led to a specific commit."
     );
 
-    let usr_prompt_2 = &format!(
-        "Based on the contributions identified, create a concise bullet-point summary. Highlight the user's key contributions and their influence on the project. Pay attention to their growth over time, and how their responses to issues evolved. Make sure to reference any interconnected events between issues and commits. Avoid replicating phrases from the source data and focus on providing a unique and insightful narrative. Please ensure your answer stayed below 256 tokens."
-    );
+    let usr_prompt_2 = "Based on the contributions identified, create a concise bullet-point summary. Highlight the user's key contributions and their influence on the project. Pay attention to their growth over time, and how their responses to issues evolved. Make sure to reference any interconnected events between issues and commits. Avoid replicating phrases from the source data and focus on providing a unique and insightful narrative. Please ensure your answer stayed below 256 tokens.";
 
     chain_of_chat(
+        llm,
         sys_prompt_1,
         usr_prompt_1,
         "correlate-99",
         512,
         usr_prompt_2,
         256,
-        "correlate_commits_issues",
     )
     .await
 }
 
-pub async fn correlate_commits_issues_discussions(
-    _profile_data: Option<&str>,
-    _commits_summary: Option<&str>,
-    _issues_summary: Option<&str>,
-    _discussions_summary: Option<&str>,
-    target_person: Option<&str>,
-) -> Option<String> {
-    let total_space = 16000; // 16k tokens
+// The section summaries `correlate_commits_issues_discussions` relates to each other;
+// sections left as None don't take a share of the prompt.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ActivitySummaries<'a> {
+    pub profile: Option<&'a str>,
+    pub commits: Option<&'a str>,
+    pub issues: Option<&'a str>,
+    pub pull_requests: Option<&'a str>,
+    pub reviews: Option<&'a str>,
+    pub discussions: Option<&'a str>,
+}
 
-    let _total_ratio = 11.0; // 1 + 4 + 4 + 2
-    let profile_ratio = 1.0;
-    let commit_ratio = 4.0;
-    let issue_ratio = 4.0;
-    let discussion_ratio = 2.0;
+// Shares of the prompt each section of `correlate_commits_issues_discussions` gets,
+// out of `TOTAL_RATIO` when all of them are present.
+pub(crate) const PROFILE_RATIO: f32 = 1.0;
+pub(crate) const COMMIT_RATIO: f32 = 4.0;
+pub(crate) const ISSUE_RATIO: f32 = 4.0;
+pub(crate) const PULL_REQUEST_RATIO: f32 = 4.0;
+pub(crate) const REVIEW_RATIO: f32 = 1.0;
+pub(crate) const DISCUSSION_RATIO: f32 = 2.0;
+pub(crate) const TOTAL_RATIO: f32 = 16.0;
+
+const CORRELATE_GEN_LEN_1: u16 = 512;
+const CORRELATE_GEN_LEN_2: u16 = 256;
+// Room for the fixed instruction text of both turns, which is about 400 tokens.
+const CORRELATE_INSTRUCTION_TOKENS: usize = 512;
+
+// Characters of section text a `ratio` out of `total_ratio` gets in the correlation prompt.
+// The second turn resends the first with its answer, so both generations and the
+// instructions are taken off the budget before it is shared out, at ~3 characters a token.
+pub(crate) fn correlation_section_chars(
+    llm: &dyn LlmBackend,
+    ratio: f32,
+    total_ratio: f32,
+) -> usize {
+    let reserved =
+        CORRELATE_GEN_LEN_1 as usize + CORRELATE_GEN_LEN_2 as usize + CORRELATE_INSTRUCTION_TOKENS;
+    let space = llm.token_budget().saturating_sub(reserved);
+    ((space as f32) * (ratio / total_ratio)) as usize * 3
+}
 
+pub async fn correlate_commits_issues_discussions(
+    llm: &dyn LlmBackend,
+    summaries: &ActivitySummaries<'_>,
+    target_person: Option<&str>,
+) -> ResearchResult<String> {
     let available_ratios = [
-        _profile_data.map(|_| profile_ratio),
-        _commits_summary.map(|_| commit_ratio),
-        _issues_summary.map(|_| issue_ratio),
-        _discussions_summary.map(|_| discussion_ratio),
+        summaries.profile.map(|_| PROFILE_RATIO),
+        summaries.commits.map(|_| COMMIT_RATIO),
+        summaries.issues.map(|_| ISSUE_RATIO),
+        summaries.pull_requests.map(|_| PULL_REQUEST_RATIO),
+        summaries.reviews.map(|_| REVIEW_RATIO),
+        summaries.discussions.map(|_| DISCUSSION_RATIO),
     ];
 
     let total_available_ratio: f32 = available_ratios.iter().filter_map(|&x| x).sum();
 
-    let compute_space =
-        |ratio: f32| -> usize { ((total_space as f32) * (ratio / total_available_ratio)) as usize };
-
-    let profile_space = _profile_data.map_or(0, |_| compute_space(profile_ratio));
-    let commit_space = _commits_summary.map_or(0, |_| compute_space(commit_ratio));
-    let issue_space = _issues_summary.map_or(0, |_| compute_space(issue_ratio));
-    let discussion_space = _discussions_summary.map_or(0, |_| compute_space(discussion_ratio));
-
-    let trim_to_allocated_space =
-        |source: &str, space: usize| -> String { source.chars().take(space * 3).collect() };
-
-    let profile_str = _profile_data.map_or("".to_string(), |x| {
-        format!(
-            "profile data: {}",
-            trim_to_allocated_space(x, profile_space)
-        )
-    });
-    let commits_str = _commits_summary.map_or("".to_string(), |x| {
-        format!("commit logs: {}", trim_to_allocated_space(x, commit_space))
-    });
-    let issues_str = _issues_summary.map_or("".to_string(), |x| {
-        format!("issue post: {}", trim_to_allocated_space(x, issue_space))
-    });
-    let discussions_str = _discussions_summary.map_or("".to_string(), |x| {
-        format!(
-            "discussion posts: {}",
-            trim_to_allocated_space(x, discussion_space)
-        )
-    });
+    let section = |label: &str, summary: Option<&str>, ratio: f32| -> String {
+        summary.map_or(String::new(), |x| {
+            let chars = correlation_section_chars(llm, ratio, total_available_ratio);
+            format!("{label}: {}", x.chars().take(chars).collect::<String>())
+        })
+    };
+
+    let profile_str = section("profile data", summaries.profile, PROFILE_RATIO);
+    let commits_str = section("commit logs", summaries.commits, COMMIT_RATIO);
+    let issues_str = section("issue post", summaries.issues, ISSUE_RATIO);
+    let pull_requests_str = section("pull requests", summaries.pull_requests, PULL_REQUEST_RATIO);
+    let reviews_str = section("code reviews", summaries.reviews, REVIEW_RATIO);
+    let discussions_str = section("discussion posts", summaries.discussions, DISCUSSION_RATIO);
 
     let target_str = match target_person {
         Some(person) => format!("{}'s", person),
@@ -525,44 +737,43 @@ pub async fn correlate_commits_issues_discussions(
     };
 
     let sys_prompt_1 =
-        "Analyze the GitHub activity data and profile data over the week to detect both key impactful contributions and connections between commits, issues, and discussions. Highlight specific code changes, resolutions, and improvements. Furthermore, trace evidence of commits addressing specific issues, discussions leading to commits, or issues spurred by discussions. The aim is to map out both the impactful technical advancements and the developmental narrative of the project.";
+        "Analyze the GitHub activity data and profile data over the reporting period to detect both key impactful contributions and connections between commits, issues, pull requests, and discussions. Highlight specific code changes, reviews, resolutions, and improvements. Furthermore, trace evidence of commits or pull requests addressing specific issues, discussions leading to commits, or issues spurred by discussions. The aim is to map out both the impactful technical advancements and the developmental narrative of the project.";
 
     let usr_prompt_1 = &format!(
-        "From {profile_str}, {commits_str}, {issues_str}, and {discussions_str}, detail {target_str}'s significant technical contributions. Enumerate individual tasks, code enhancements, and bug resolutions, emphasizing impactful contributions. Concurrently, identify connections: commits that appear to resolve specific issues, discussions that may have catalyzed certain commits, or issues influenced by preceding discussions. Extract tangible instances showcasing both impact and interconnections within the week."
+        "From {profile_str}, {commits_str}, {issues_str}, {pull_requests_str}, {reviews_str}, and {discussions_str}, detail {target_str}'s significant technical contributions. Enumerate individual tasks, code enhancements, bug resolutions, and code review work, emphasizing impactful contributions. Concurrently, identify connections: commits or pull requests that appear to resolve specific issues, discussions that may have catalyzed certain commits, or issues influenced by preceding discussions. Extract tangible instances showcasing both impact and interconnections within the reporting period."
     );
 
     let usr_prompt_2 = &format!(
-        "Merge the identified impactful technical contributions and their interconnections into a coherent summary for {target_str} over the week. Describe how these contributions align with the project's technical objectives. Pinpoint recurring technical patterns or trends and shed light on the synergy between individual efforts and their collective progression. Detail both the weight of each contribution and their interconnectedness in shaping the project. Limit to 256 tokens."
+        "Merge the identified impactful technical contributions and their interconnections into a coherent summary for {target_str} over the reporting period. Describe how these contributions align with the project's technical objectives. Pinpoint recurring technical patterns or trends and shed light on the synergy between individual efforts and their collective progression. Detail both the weight of each contribution and their interconnectedness in shaping the project. Limit to 256 tokens."
     );
 
     chain_of_chat(
+        llm,
         sys_prompt_1,
         usr_prompt_1,
-        "correlate-99",
-        512,
+        "correlate-activity-99",
+        CORRELATE_GEN_LEN_1,
         usr_prompt_2,
-        256,
-        "correlate_commits_issues_discussions",
+        CORRELATE_GEN_LEN_2,
     )
     .await
 }
 
 pub async fn correlate_user_and_home_project(
+    llm: &dyn LlmBackend,
     home_repo_data: &str,
     user_profile: &str,
     issues_data: &str,
     repos_data: &str,
     discussion_data: &str,
-) -> Option<String> {
+) -> ResearchResult<String> {
     let home_repo_data = home_repo_data.chars().take(6000).collect::<String>();
     let user_profile = user_profile.chars().take(4000).collect::<String>();
     let issues_data = issues_data.chars().take(9000).collect::<String>();
     let repos_data = repos_data.chars().take(6000).collect::<String>();
     let discussion_data = discussion_data.chars().take(4000).collect::<String>();
 
-    let sys_prompt_1 = &format!(
-        "First, let's analyze and understand the provided Github data in a step-by-step manner. Begin by evaluating the user's activity based on their most active repositories, languages used, issues they're involved in, and discussions they've participated in. Concurrently, grasp the characteristics and requirements of the home project. Your aim is to identify overlaps or connections between the user's skills or activities and the home project's needs."
-    );
+    let sys_prompt_1 = "First, let's analyze and understand the provided Github data in a step-by-step manner. Begin by evaluating the user's activity based on their most active repositories, languages used, issues they're involved in, and discussions they've participated in. Concurrently, grasp the characteristics and requirements of the home project. Your aim is to identify overlaps or connections between the user's skills or activities and the home project's needs.";
 
     let usr_prompt_1 = &format!(
         "Using a structured approach, analyze the given data: User Profile: {} Active Repositories: {} Issues Involved: {} Discussions Participated: {} Home project's characteristics: {} Identify patterns in the user's activity and spot potential synergies with the home project. Pay special attention to the programming languages they use, especially if they align with the home project's requirements. Derive insights from their interactions and the data provided.",
@@ -573,17 +784,103 @@ pub async fn correlate_user_and_home_project(
         home_repo_data
     );
 
-    let usr_prompt_2 = &format!(
-        "Now, using the insights from your step-by-step analysis, craft a concise bullet-point summary that underscores: - The user's main areas of expertise and interest. - The relevance of their preferred languages or technologies to the home project. - Their potential contributions to the home project, based on their skills and interactions. Ensure the summary is clear, insightful, and remains under 256 tokens. Emphasize any evident alignments between the user's skills and the project's needs."
-    );
+    let usr_prompt_2 = "Now, using the insights from your step-by-step analysis, craft a concise bullet-point summary that underscores: - The user's main areas of expertise and interest. - The relevance of their preferred languages or technologies to the home project. - Their potential contributions to the home project, based on their skills and interactions. Ensure the summary is clear, insightful, and remains under 256 tokens. Emphasize any evident alignments between the user's skills and the project's needs.";
     chain_of_chat(
+        llm,
         sys_prompt_1,
         usr_prompt_1,
         "correlate-user-home",
         512,
         usr_prompt_2,
         256,
-        "correlate-user-home-summary",
     )
     .await
 }
+
+// A short overview of search hits, `kind` being e.g. `issues` and `hits` the text
+// returned by the search fetchers.
+pub async fn digest_search_results(
+    llm: &dyn LlmBackend,
+    kind: &str,
+    query: &str,
+    hits: &str,
+) -> ResearchResult<String> {
+    let hits = squeeze_fit_post_texts(hits, 6000, 0.8);
+
+    let sys_prompt_1 = "Your task is to give an overview of GitHub search results. Group related results, point out the most relevant or active ones and recurring themes, and stay factual.";
+
+    let usr_prompt_1 = &format!(
+        "These are the GitHub {kind} matching the search `{query}`: {hits}. Write a concise bullet-point digest of what was found, naming the most notable results. Keep it under 256 tokens."
+    );
+
+    match llm
+        .complete("search-digest-99", sys_prompt_1, usr_prompt_1, 384)
+        .await
+    {
+        Some(r) => Ok(r),
+        None => {
+            log::error!("Error digesting the {} matching {}", kind, query);
+            Err(ResearchError::Llm("search-digest-99".to_string()))
+        }
+    }
+}
+
+// What a user has been doing across GitHub, from `ContributionHistory::to_text`.
+pub async fn summarize_user_activity(
+    llm: &dyn LlmBackend,
+    user_name: &str,
+    activity: &str,
+) -> ResearchResult<String> {
+    let activity = squeeze_fit_post_texts(activity, 9000, 0.8);
+
+    let sys_prompt_1 = "Your task is to summarize a GitHub user's recent activity across projects, based on their commit counts, issues, pull requests and reviews grouped by repository. Stay factual and don't judge the person.";
+
+    let usr_prompt_1 = &format!(
+        "Here are {user_name}'s contributions: {activity}. Write a concise bullet-point summary of where {user_name} spent their effort, what kind of work they did in each of the main projects, and any notable pull requests or issues. Keep it under 256 tokens."
+    );
+
+    match llm
+        .complete("user-activity-99", sys_prompt_1, usr_prompt_1, 384)
+        .await
+    {
+        Some(r) => Ok(r),
+        None => {
+            log::error!("Error summarizing the activity of {}", user_name);
+            Err(ResearchError::Llm("user-activity-99".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::OpenAICompatibleBackend;
+
+    #[test]
+    fn correlation_prompt_leaves_room_for_both_turns() {
+        let llm = OpenAICompatibleBackend::new("http://localhost:8080/v1", "model");
+        let ratios = [
+            PROFILE_RATIO,
+            COMMIT_RATIO,
+            ISSUE_RATIO,
+            PULL_REQUEST_RATIO,
+            REVIEW_RATIO,
+            DISCUSSION_RATIO,
+        ];
+        assert_eq!(ratios.iter().sum::<f32>(), TOTAL_RATIO);
+
+        let section_tokens = ratios
+            .iter()
+            .map(|&ratio| correlation_section_chars(&llm, ratio, TOTAL_RATIO) / 3)
+            .sum::<usize>();
+        let generated = CORRELATE_GEN_LEN_1 as usize + CORRELATE_GEN_LEN_2 as usize;
+        assert!(section_tokens + generated + CORRELATE_INSTRUCTION_TOKENS <= llm.token_budget());
+
+        // A budget smaller than what the turns need leaves the sections nothing.
+        let tiny = llm.with_context_tokens(1_000);
+        assert_eq!(
+            correlation_section_chars(&tiny, COMMIT_RATIO, TOTAL_RATIO),
+            0
+        );
+    }
+}
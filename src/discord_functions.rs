@@ -1,8 +1,30 @@
+// Discord output. Splitting messages and laying reports out as embeds is plain code that
+// builds for every target; registering commands and posting needs the `flows` client.
+use crate::error::ResearchError;
+use crate::github_data_fetchers::{GitMemory, ReviewerActivity};
+use crate::reports::{Report, ReportSection, RATE_LIMITED_NOTE, SUMMARY_UNAVAILABLE_NOTE};
+#[cfg(feature = "flows")]
 use discord_flows::http::Http;
+#[cfg(feature = "flows")]
 use discord_flows::http::HttpBuilder;
-use serde_json;
+#[cfg(feature = "flows")]
+use discord_flows::model::application_command::{CommandDataOption, CommandDataOptionValue};
+use serde_json::{self, json, Value};
+#[cfg(feature = "flows")]
 use std::env;
 
+// Discord's limits, counted in characters.
+pub const MESSAGE_LIMIT: usize = 2000;
+const EMBED_TITLE_LIMIT: usize = 256;
+const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+const EMBED_FIELD_NAME_LIMIT: usize = 256;
+const EMBED_FIELD_VALUE_LIMIT: usize = 1024;
+const EMBED_FIELDS_PER_EMBED: usize = 25;
+const EMBEDS_PER_MESSAGE: usize = 10;
+const EMBED_TOTAL_LIMIT: usize = 6000;
+const EMBED_COLOR: u32 = 0x2f81f7;
+
+#[cfg(feature = "flows")]
 pub async fn register_commands(discord_token: &str) -> bool {
     let bot_id = env::var("bot_id").unwrap_or("1143678250661466124".to_string());
     let guild_id = env::var("discord_server").unwrap_or("1091003237827608647".to_string());
@@ -28,13 +50,233 @@ pub async fn register_commands(discord_token: &str) -> bool {
                 "description": "The username for report generation",
                 "type": 3,
                 "required": false
+            },
+            {
+                "name": "days",
+                "description": "Number of days to cover, 7 by default",
+                "type": 4, // type 4 indicates an INTEGER
+                "required": false
+            },
+            {
+                "name": "since",
+                "description": "Start of the report, a YYYY-MM-DD date or a release tag",
+                "type": 3,
+                "required": false
+            },
+            {
+                "name": "until",
+                "description": "End of the report, a YYYY-MM-DD date or a release tag",
+                "type": 3,
+                "required": false
+            },
+            {
+                "name": "period",
+                "description": "Report on the last full calendar month or quarter",
+                "type": 3,
+                "required": false,
+                "choices": [
+                    { "name": "month", "value": "month" },
+                    { "name": "quarter", "value": "quarter" }
+                ]
+            }
+        ]
+    });
+
+    let command_org_report = serde_json::json!({
+        "name": "org_report",
+        "description": "Generate a report across the repositories of an organization",
+        "options": [
+            {
+                "name": "owner",
+                "description": "The organization or user owning the repositories",
+                "type": 3,
+                "required": true
+            },
+            {
+                "name": "topic",
+                "description": "Only the repositories with this topic",
+                "type": 3,
+                "required": false
+            },
+            {
+                "name": "repos",
+                "description": "Comma-separated repository names, instead of all of the owner's",
+                "type": 3,
+                "required": false
+            },
+            {
+                "name": "include_archived",
+                "description": "Also report on archived repositories",
+                "type": 5,
+                "required": false
+            },
+            {
+                "name": "user_name",
+                "description": "The username for report generation",
+                "type": 3,
+                "required": false
+            },
+            {
+                "name": "days",
+                "description": "Number of days to cover, 7 by default",
+                "type": 4,
+                "required": false
+            },
+            {
+                "name": "period",
+                "description": "Report on the last full calendar month or quarter",
+                "type": 3,
+                "required": false,
+                "choices": [
+                    { "name": "month", "value": "month" },
+                    { "name": "quarter", "value": "quarter" }
+                ]
+            }
+        ]
+    });
+
+    // Both take the repo and optional user of the scheduled report.
+    let subscription_options = serde_json::json!([
+        {
+            "name": "owner",
+            "description": "The owner of the repository",
+            "type": 3,
+            "required": true
+        },
+        {
+            "name": "repo",
+            "description": "The repository name",
+            "type": 3,
+            "required": true
+        },
+        {
+            "name": "user_name",
+            "description": "Report on this user's contributions only",
+            "type": 3,
+            "required": false
+        }
+    ]);
+    let command_subscribe = serde_json::json!({
+        "name": "subscribe",
+        "description": "Post a report on a repository on the bot's schedule",
+        "options": subscription_options,
+    });
+    let command_unsubscribe = serde_json::json!({
+        "name": "unsubscribe",
+        "description": "Stop the scheduled reports on a repository",
+        "options": subscription_options,
+    });
+
+    let search_options = serde_json::json!([
+        {
+            "name": "query",
+            "description": "GitHub search syntax, e.g. `repo:owner/repo label:bug`",
+            "type": 3,
+            "required": true
+        },
+        {
+            "name": "page",
+            "description": "Which page of results to show, 1 by default",
+            "type": 4,
+            "required": false,
+            "min_value": 1
+        },
+        {
+            "name": "digest",
+            "description": "Add an overview of the results written by the language model",
+            "type": 5, // type 5 indicates a BOOLEAN
+            "required": false
+        }
+    ]);
+    let search_subcommand = |name: &str, description: &str| {
+        serde_json::json!({
+            "name": name,
+            "description": description,
+            "type": 1, // type 1 indicates a SUB_COMMAND
+            "options": search_options,
+        })
+    };
+    let command_search = serde_json::json!({
+        "name": "search",
+        "description": "Search GitHub",
+        "options": [
+            search_subcommand("users", "Search GitHub users"),
+            search_subcommand("repositories", "Search GitHub repositories"),
+            search_subcommand("issues", "Search GitHub issues and pull requests"),
+            search_subcommand("discussions", "Search GitHub discussions, with a summary of each"),
+        ]
+    });
+
+    let command_contributor_profile = serde_json::json!({
+        "name": "contributor_profile",
+        "description": "Analyze how a GitHub user could fit a project",
+        "options": [
+            {
+                "name": "user_name",
+                "description": "The GitHub user to profile",
+                "type": 3,
+                "required": true
+            },
+            {
+                "name": "owner",
+                "description": "The owner of the repository",
+                "type": 3,
+                "required": true
+            },
+            {
+                "name": "repo",
+                "description": "The repository name",
+                "type": 3,
+                "required": true
+            },
+            {
+                "name": "days",
+                "description": "Number of days of the user's activity to look at, 30 by default",
+                "type": 4,
+                "required": false
             }
+        ]
+    });
 
+    let command_user_activity = serde_json::json!({
+        "name": "user_activity",
+        "description": "Report what a GitHub user has been doing across all public repositories",
+        "options": [
+            {
+                "name": "user_name",
+                "description": "The GitHub user",
+                "type": 3,
+                "required": true
+            },
+            {
+                "name": "days",
+                "description": "Number of days to cover, 30 by default",
+                "type": 4,
+                "required": false
+            },
+            {
+                "name": "period",
+                "description": "Report on the last full calendar month or quarter",
+                "type": 3,
+                "required": false,
+                "choices": [
+                    { "name": "month", "value": "month" },
+                    { "name": "quarter", "value": "quarter" }
+                ]
+            }
         ]
     });
 
     let guild_id = guild_id.parse::<u64>().unwrap_or(1091003237827608647);
-    let commands = serde_json::json!([command_weekly_report]);
+    let commands = serde_json::json!([
+        command_weekly_report,
+        command_org_report,
+        command_subscribe,
+        command_unsubscribe,
+        command_search,
+        command_contributor_profile,
+        command_user_activity
+    ]);
     let http_client = HttpBuilder::new(discord_token)
         .application_id(bot_id.parse().unwrap())
         .build();
@@ -54,13 +296,49 @@ pub async fn register_commands(discord_token: &str) -> bool {
     }
 }
 
+// Replaces the interaction's original message. Content over Discord's limit is cut at a
+// line or word boundary; use `edit_original_chunked` to keep all of it.
+#[cfg(feature = "flows")]
 pub async fn edit_original_wrapped(
     client: &Http,
     token: &str,
     content: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = match content.chars().count() > MESSAGE_LIMIT {
+        true => {
+            log::warn!("message of {} characters cut to fit Discord", content.len());
+            let head = split_message(content, MESSAGE_LIMIT - 1).remove(0);
+            format!("{head}…")
+        }
+        false => content.to_string(),
+    };
+    edit_original_json(client, token, &json!({ "content": content })).await
+}
+
+// Puts the first chunk of `content` in the original message and the rest in followups.
+#[cfg(feature = "flows")]
+pub async fn edit_original_chunked(
+    client: &Http,
+    token: &str,
+    content: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut chunks = split_message(content, MESSAGE_LIMIT).into_iter();
+    let first = chunks.next().unwrap_or_default();
+    edit_original_json(client, token, &json!({ "content": first })).await?;
+    for chunk in chunks {
+        send_followup(client, token, &json!({ "content": chunk })).await?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "flows")]
+async fn edit_original_json(
+    client: &Http,
+    token: &str,
+    message: &Value,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match client
-        .edit_original_interaction_response(token, &serde_json::json!({ "content": content }))
+        .edit_original_interaction_response(token, message)
         .await
     {
         Ok(_) => Ok(()),
@@ -70,3 +348,496 @@ pub async fn edit_original_wrapped(
         }
     }
 }
+
+#[cfg(feature = "flows")]
+pub async fn send_followup(
+    client: &Http,
+    token: &str,
+    message: &Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match client.create_followup_message(token, message).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            log::error!("error sending followup message: {:?}", e);
+            Err(Box::new(e))
+        }
+    }
+}
+
+// Posts the report as embeds, one per section, spread over as many messages as Discord's
+// limits require. Falls back to plain text chunks when the embeds are rejected.
+#[cfg(feature = "flows")]
+pub async fn post_report(
+    client: &Http,
+    token: &str,
+    report: &Report,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut batches = pack_embeds(report_embeds(report)).into_iter();
+    let first = batches.next().unwrap_or_default();
+    let posted =
+        match edit_original_json(client, token, &json!({ "content": "", "embeds": first })).await {
+            Ok(()) => {
+                let mut res = Ok(());
+                for batch in batches {
+                    res = send_followup(client, token, &json!({ "embeds": batch })).await;
+                    if res.is_err() {
+                        break;
+                    }
+                }
+                res
+            }
+            Err(e) => Err(e),
+        };
+    match posted {
+        Ok(()) => Ok(()),
+        Err(_) => edit_original_chunked(client, token, &report.to_text()).await,
+    }
+}
+
+// Splits `text` into pieces of at most `limit` characters, preferring paragraph breaks,
+// then line breaks, then spaces, so links and words stay whole.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+
+    while rest.chars().count() > limit {
+        let head_end = rest
+            .char_indices()
+            .nth(limit)
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        let head = &rest[..head_end];
+        let cut = head
+            .rfind("\n\n")
+            .or_else(|| head.rfind('\n'))
+            .or_else(|| head.rfind(' '))
+            .filter(|&i| i >= head_end / 2)
+            .unwrap_or(head_end);
+        chunks.push(rest[..cut].trim_end().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+pub(crate) fn truncate_chars(text: &str, limit: usize) -> String {
+    match text.chars().count() > limit {
+        true => text.chars().take(limit - 1).collect::<String>() + "…",
+        false => text.to_string(),
+    }
+}
+
+fn embed_len(embed: &Value) -> usize {
+    let text_len = |v: &Value| v.as_str().map(|s| s.chars().count()).unwrap_or(0);
+    let fields = embed["fields"].as_array().map(|fields| {
+        fields
+            .iter()
+            .map(|f| text_len(&f["name"]) + text_len(&f["value"]))
+            .sum::<usize>()
+    });
+    text_len(&embed["title"])
+        + text_len(&embed["description"])
+        + text_len(&embed["footer"]["text"])
+        + fields.unwrap_or(0)
+}
+
+fn embed(title: &str, description: &str, fields: Vec<Value>) -> Value {
+    let mut embed = json!({
+        "title": truncate_chars(title, EMBED_TITLE_LIMIT),
+        "color": EMBED_COLOR,
+        "fields": fields,
+    });
+    if !description.trim().is_empty() {
+        embed["description"] = json!(truncate_chars(description.trim(), EMBED_DESCRIPTION_LIMIT));
+    }
+    embed
+}
+
+fn field(name: &str, value: &str) -> Value {
+    json!({
+        "name": truncate_chars(name, EMBED_FIELD_NAME_LIMIT),
+        "value": truncate_chars(value, EMBED_FIELD_VALUE_LIMIT),
+        "inline": false,
+    })
+}
+
+fn memory_field(gm: &GitMemory) -> Value {
+    let name = match gm.tag_line.lines().next().map(str::trim) {
+        Some(line) if !line.is_empty() => line.to_string(),
+        _ => gm.source_url.clone(),
+    };
+    let author = match gm.name.is_empty() {
+        true => String::new(),
+        false => format!(" by {}", gm.name),
+    };
+    field(
+        &name,
+        &format!(
+            "{}{} ({})\n{}",
+            gm.source_url,
+            author,
+            gm.date,
+            gm.payload.trim()
+        ),
+    )
+}
+
+// Analyzed items get a field each; URLs that were found but not analyzed share one.
+fn section_embed(title: &str, section: &ReportSection) -> Value {
+    if section.found.is_empty() {
+        return embed(&format!("{title} (0 found)"), "None found.", vec![]);
+    }
+
+    let mut fields = Vec::new();
+    let mut budget = EMBED_TOTAL_LIMIT - EMBED_TITLE_LIMIT - EMBED_FIELD_VALUE_LIMIT;
+    for gm in &section.items {
+        let field = memory_field(gm);
+        let len = embed_len(&json!({ "fields": [&field] }));
+        if fields.len() + 1 >= EMBED_FIELDS_PER_EMBED || len > budget {
+            break;
+        }
+        budget -= len;
+        fields.push(field);
+    }
+
+    let shown = section
+        .items
+        .iter()
+        .take(fields.len())
+        .map(|gm| &gm.source_url);
+    let shown = shown.collect::<Vec<_>>();
+    let others = section
+        .found
+        .iter()
+        .filter(|url| !shown.contains(url))
+        .map(|url| url.as_str())
+        .collect::<Vec<_>>();
+    if !others.is_empty() {
+        let list = split_message(&others.join("\n"), EMBED_FIELD_VALUE_LIMIT).remove(0);
+        let listed = list.lines().count();
+        let name = match listed < others.len() {
+            true => format!("Also found ({} of {} listed)", listed, others.len()),
+            false => "Also found".to_string(),
+        };
+        fields.push(field(&name, &list));
+    }
+
+    embed(
+        &format!("{title} ({} found)", section.found.len()),
+        "",
+        fields,
+    )
+}
+
+fn reviews_embed(reviews: &[ReviewerActivity]) -> Value {
+    let fields = reviews
+        .iter()
+        .take(EMBED_FIELDS_PER_EMBED)
+        .map(|r| {
+            let turnaround = r
+                .average_turnaround_hours()
+                .map(|h| format!(", average turnaround {:.1} hours", h))
+                .unwrap_or_default();
+            field(
+                &r.login,
+                &format!(
+                    "{} pull requests: {} approvals, {} change requests, {} comment reviews, {} review comments{}",
+                    r.pull_requests.len(),
+                    r.approvals,
+                    r.change_requests,
+                    r.commented_reviews,
+                    r.review_comments,
+                    turnaround
+                ),
+            )
+        })
+        .collect();
+    embed("Reviews", "", fields)
+}
+
+pub fn report_embeds(report: &Report) -> Vec<Value> {
+    let mut summary_fields = vec![];
+    if let Some(profile) = &report.profile {
+        summary_fields.push(field("About", &profile.payload));
+    }
+    let summary = match (&report.synthesis, report.is_empty()) {
+        (_, true) => report.to_text(),
+        (Some(synthesis), false) => synthesis.clone(),
+        (None, false) => SUMMARY_UNAVAILABLE_NOTE.to_string(),
+    };
+    let mut embeds = vec![embed(&report.title(), &summary, summary_fields)];
+
+    if !report.is_empty() {
+        embeds.push(section_embed("Commits", &report.commits));
+        embeds.push(section_embed("Issues", &report.issues));
+        embeds.push(section_embed("Pull requests", &report.pull_requests));
+        if !report.reviews.is_empty() {
+            embeds.push(reviews_embed(&report.reviews));
+        }
+        if !report.discussions.items.is_empty() {
+            embeds.push(section_embed("Discussions", &report.discussions));
+        }
+    }
+
+    if report.rate_limited {
+        if let Some(last) = embeds.last_mut() {
+            last["footer"] = json!({ "text": RATE_LIMITED_NOTE });
+        }
+    }
+    embeds
+}
+
+// Groups embeds into messages within Discord's per-message count and size limits.
+pub fn pack_embeds(embeds: Vec<Value>) -> Vec<Vec<Value>> {
+    let mut batches: Vec<Vec<Value>> = vec![];
+    let mut batch_len = 0;
+    for embed in embeds {
+        let len = embed_len(&embed);
+        match batches.last_mut() {
+            Some(batch)
+                if batch.len() < EMBEDS_PER_MESSAGE && batch_len + len <= EMBED_TOTAL_LIMIT =>
+            {
+                batch_len += len;
+                batch.push(embed);
+            }
+            _ => {
+                batch_len = len;
+                batches.push(vec![embed]);
+            }
+        }
+    }
+    batches
+}
+
+// Numbered pages of search hits, each fitting one Discord message under `heading`.
+pub fn search_pages(heading: &str, hits: &str) -> Vec<String> {
+    let limit = MESSAGE_LIMIT
+        .saturating_sub(heading.chars().count() + 32)
+        .max(500);
+    let pages = split_message(hits.trim(), limit);
+    let n_pages = pages.len();
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, page)| format!("{heading} (page {} of {n_pages})\n{page}", i + 1))
+        .collect()
+}
+
+#[cfg(feature = "flows")]
+pub fn get_string_option(options: &[CommandDataOption], name: &str) -> Option<String> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| match opt.resolved.as_ref() {
+            Some(CommandDataOptionValue::String(s)) => Some(s.to_string()),
+            _ => None,
+        })
+}
+
+#[cfg(feature = "flows")]
+pub fn get_integer_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| match opt.resolved.as_ref() {
+            Some(CommandDataOptionValue::Integer(n)) => Some(*n),
+            _ => None,
+        })
+}
+
+#[cfg(feature = "flows")]
+pub fn get_boolean_option(options: &[CommandDataOption], name: &str) -> Option<bool> {
+    options
+        .iter()
+        .find(|opt| opt.name == name)
+        .and_then(|opt| match opt.resolved.as_ref() {
+            Some(CommandDataOptionValue::Boolean(b)) => Some(*b),
+            _ => None,
+        })
+}
+
+// What the user sees in Discord when a step fails; logs keep the full error.
+pub fn research_error_message(error: &ResearchError, owner: &str, repo: &str) -> String {
+    match error {
+        ResearchError::NotFound(_) => {
+            "You've entered invalid owner/repo, or the target is private. Please try again."
+                .to_string()
+        }
+        ResearchError::Unauthorized(_) => format!(
+            "The bot's GitHub token was rejected or can't access {owner}/{repo}. Please ask the bot's operator to check it."
+        ),
+        ResearchError::RateLimited(_) => {
+            "The bot has hit GitHub's rate limit. Please try again in a few minutes.".to_string()
+        }
+        ResearchError::Http { status, .. } => format!(
+            "GitHub answered with an unexpected error ({status}) for {owner}/{repo}. Please try again later."
+        ),
+        ResearchError::Transport(_) => {
+            "The bot couldn't reach GitHub. Please try again later.".to_string()
+        }
+        ResearchError::GraphQl(msg) => format!("GitHub rejected the bot's query: {msg}"),
+        ResearchError::Parse(_) => {
+            "GitHub returned data the bot couldn't read. Please try again later.".to_string()
+        }
+        ResearchError::Llm(_) => {
+            "The language model failed to write a summary. Please try again later.".to_string()
+        }
+        ResearchError::NoData(_) => format!("No useful data found for {owner}/{repo}."),
+        ResearchError::InvalidInput(what) => format!("You've entered an invalid {what}. Use YYYY-MM-DD dates or existing release tags for `since` and `until`, and check the other options."),
+        ResearchError::Other(e) => format!("Something went wrong: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reports::sample_report;
+
+    fn char_counts(chunks: &[String]) -> Vec<usize> {
+        chunks.iter().map(|c| c.chars().count()).collect()
+    }
+
+    #[test]
+    fn split_message_keeps_multibyte_characters_whole() {
+        let text = "é".repeat(4500);
+        let chunks = split_message(&text, MESSAGE_LIMIT);
+        assert_eq!(char_counts(&chunks), [2000, 2000, 500]);
+        assert_eq!(chunks.concat(), text);
+
+        let text = "🦀🦀🦀 ".repeat(1000);
+        let chunks = split_message(&text, MESSAGE_LIMIT);
+        assert!(char_counts(&chunks).iter().all(|n| *n <= MESSAGE_LIMIT));
+        // Cut at spaces, so the words are all there.
+        assert_eq!(chunks.join(" "), text.trim());
+    }
+
+    #[test]
+    fn split_message_prefers_paragraphs_then_lines() {
+        let text = format!(
+            "{}\n\n{}\n{}",
+            "a".repeat(1200),
+            "b".repeat(500),
+            "c".repeat(500)
+        );
+        let chunks = split_message(&text, MESSAGE_LIMIT);
+        assert_eq!(chunks, [text[..1200].to_string(), text[1202..].to_string()]);
+
+        let text = format!("{}\n{}", "a".repeat(1500), "b".repeat(1500));
+        let chunks = split_message(&text, MESSAGE_LIMIT);
+        assert_eq!(char_counts(&chunks), [1500, 1500]);
+    }
+
+    #[test]
+    fn split_message_cuts_long_lines_without_losing_text() {
+        let text = "x".repeat(4100);
+        let chunks = split_message(&text, MESSAGE_LIMIT);
+        assert_eq!(char_counts(&chunks), [2000, 2000, 100]);
+        assert_eq!(split_message("", MESSAGE_LIMIT), [""]);
+    }
+
+    fn batch_len(batch: &[Value]) -> usize {
+        batch.iter().map(embed_len).sum()
+    }
+
+    #[test]
+    fn pack_embeds_keeps_to_the_message_limits() {
+        let small = (0..23)
+            .map(|i| embed(&format!("{i}"), "", vec![]))
+            .collect();
+        let sizes = pack_embeds(small).iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(sizes, [10, 10, 3]);
+
+        let large = (0..5)
+            .map(|i| embed(&format!("{i}"), &"ü".repeat(2500), vec![]))
+            .collect();
+        let batches = pack_embeds(large);
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+        assert!(batches
+            .iter()
+            .all(|batch| batch_len(batch) <= EMBED_TOTAL_LIMIT));
+    }
+
+    #[test]
+    fn report_embeds_fit_discord_limits() {
+        let mut report = sample_report();
+        let template = report.commits.items.remove(0);
+        report.commits.found.clear();
+        for i in 0..40 {
+            let url = format!("https://github.com/o/r/commit/{i:040}");
+            report.commits.found.push(url.clone());
+            report.commits.items.push(GitMemory {
+                tag_line: "ä".repeat(300),
+                source_url: url,
+                payload: "ö".repeat(900),
+                name: template.name.clone(),
+                memory_type: crate::github_data_fetchers::MemoryType::Commit,
+                date: template.date,
+            });
+        }
+        report.synthesis = Some("summary ".repeat(700));
+        report.rate_limited = true;
+
+        let embeds = report_embeds(&report);
+        for embed in &embeds {
+            assert!(
+                embed_len(embed) <= EMBED_TOTAL_LIMIT,
+                "{}",
+                embed_len(embed)
+            );
+            let chars = |v: &Value| v.as_str().unwrap_or_default().chars().count();
+            assert!(chars(&embed["title"]) <= EMBED_TITLE_LIMIT);
+            assert!(chars(&embed["description"]) <= EMBED_DESCRIPTION_LIMIT);
+            let fields = embed["fields"].as_array().unwrap();
+            assert!(fields.len() <= EMBED_FIELDS_PER_EMBED);
+            for field in fields {
+                assert!(chars(&field["name"]) <= EMBED_FIELD_NAME_LIMIT);
+                assert!(chars(&field["value"]) <= EMBED_FIELD_VALUE_LIMIT);
+            }
+        }
+
+        // The commits that didn't fit as fields are still listed.
+        let commits = &embeds[1];
+        assert_eq!(commits["title"], "Commits (40 found)");
+        let fields = commits["fields"].as_array().unwrap();
+        assert!(fields.last().unwrap()["name"]
+            .as_str()
+            .unwrap()
+            .starts_with("Also found"));
+        assert_eq!(embeds.last().unwrap()["footer"]["text"], RATE_LIMITED_NOTE);
+
+        let batches = pack_embeds(embeds);
+        assert!(batches.iter().all(
+            |batch| batch.len() <= EMBEDS_PER_MESSAGE && batch_len(batch) <= EMBED_TOTAL_LIMIT
+        ));
+    }
+
+    #[test]
+    fn report_without_synthesis_keeps_its_sections() {
+        let mut report = sample_report();
+        report.synthesis = None;
+
+        let text = report.to_text();
+        assert!(text.contains("found 2 commits:\nhttps://github.com/o/r/commit/a1"));
+        assert!(text.contains("found 1 issues:\nhttps://github.com/o/r/issues/41"));
+        assert!(text.ends_with(SUMMARY_UNAVAILABLE_NOTE));
+        assert!(!text.contains("no report generated"));
+
+        let embeds = report_embeds(&report);
+        assert_eq!(embeds[0]["description"], SUMMARY_UNAVAILABLE_NOTE);
+        let titles = embeds[1..]
+            .iter()
+            .map(|embed| embed["title"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            [
+                "Commits (2 found)",
+                "Issues (1 found)",
+                "Pull requests (0 found)",
+                "Reviews"
+            ]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::transport::GitHubResponse;
+use std::fmt;
+
+pub type ResearchResult<T> = Result<T, ResearchError>;
+
+#[derive(Debug)]
+pub enum ResearchError {
+    // 404, or a private repo the token can't see.
+    NotFound(String),
+    // 401, or a 403 that isn't rate limiting.
+    Unauthorized(String),
+    RateLimited(String),
+    // Any other non-success status.
+    Http { status: u16, url: String },
+    // The request never got a response.
+    Transport(String),
+    GraphQl(String),
+    Parse(String),
+    Llm(String),
+    // The request worked but there was nothing to work with.
+    NoData(String),
+    // Bad command options, e.g. an unknown release tag.
+    InvalidInput(String),
+    Other(anyhow::Error),
+}
+
+impl ResearchError {
+    pub fn from_response(url: &str, res: &GitHubResponse) -> Self {
+        let remaining = res.header("X-RateLimit-Remaining").map(|v| v.trim());
+        match res.status {
+            404 | 410 => ResearchError::NotFound(url.to_string()),
+            429 => ResearchError::RateLimited(url.to_string()),
+            403 if remaining == Some("0") || res.header("Retry-After").is_some() => {
+                ResearchError::RateLimited(url.to_string())
+            }
+            401 | 403 => ResearchError::Unauthorized(url.to_string()),
+            status => ResearchError::Http {
+                status,
+                url: url.to_string(),
+            },
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ResearchError::NotFound(_))
+    }
+}
+
+impl fmt::Display for ResearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResearchError::NotFound(what) => write!(f, "not found: {what}"),
+            ResearchError::Unauthorized(url) => write!(f, "unauthorized: {url}"),
+            ResearchError::RateLimited(url) => write!(f, "rate limited: {url}"),
+            ResearchError::Http { status, url } => write!(f, "http error {status}: {url}"),
+            ResearchError::Transport(msg) => write!(f, "transport error: {msg}"),
+            ResearchError::GraphQl(msg) => write!(f, "GraphQL error: {msg}"),
+            ResearchError::Parse(msg) => write!(f, "parse error: {msg}"),
+            ResearchError::Llm(msg) => write!(f, "LLM error: {msg}"),
+            ResearchError::NoData(what) => write!(f, "no data: {what}"),
+            ResearchError::InvalidInput(what) => write!(f, "invalid input: {what}"),
+            ResearchError::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ResearchError {}
+
+impl From<serde_json::Error> for ResearchError {
+    fn from(e: serde_json::Error) -> Self {
+        ResearchError::Parse(e.to_string())
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for ResearchError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        ResearchError::Transport(e.to_string())
+    }
+}
+
+impl From<anyhow::Error> for ResearchError {
+    fn from(e: anyhow::Error) -> Self {
+        ResearchError::Other(e)
+    }
+}
@@ -1,16 +1,15 @@
-use crate::octocrab_compat::{Comment, Issue, Repository, User};
+use crate::error::{ResearchError, ResearchResult};
+use crate::llm::LlmBackend;
+use crate::octocrab_compat::{Comment, Issue, RateLimit, Repository, User};
+use crate::store::{get, set};
+use crate::time_window::{format_iso, TimeWindow};
+use crate::transport::GitHubTransport;
 use crate::utils::*;
-use chrono::{DateTime, Duration, NaiveDate, Utc};
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
 use derivative::Derivative;
-use http_req::response::Response;
-use openai_flows::{
-    self,
-    chat::{self, ChatOptions},
-    OpenAIFlows,
-};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use store_flows::{get, set};
 
 #[derive(Derivative, Serialize, Deserialize, Debug)]
 pub struct GitMemory {
@@ -29,22 +28,26 @@ pub struct GitMemory {
 pub enum MemoryType {
     Commit,
     Issue,
+    PullRequest,
     Discussion,
     Meta,
 }
-pub async fn get_user_profile(github_token: &str, user: &str) -> Option<User> {
-    let user_profile_url = format!("https://api.github.com/users/{user}");
+pub async fn get_user_profile(github: &dyn GitHubTransport, user: &str) -> ResearchResult<User> {
+    let user_profile_url = format!("{}/users/{user}", github.base_url());
 
-    match github_http_fetch(&github_token, &user_profile_url).await {
-        Some(res) => serde_json::from_slice::<User>(res.as_slice()).ok(),
+    match github_http_fetch(github, &user_profile_url).await {
+        Ok(res) => Ok(serde_json::from_slice::<User>(res.as_slice())?),
 
-        None => {
+        Err(e) => {
             log::error!("Github user not found.");
-            None
+            Err(e)
         }
     }
 }
-pub async fn get_user_data_by_login(github_token: &str, login: &str) -> Option<String> {
+pub async fn get_user_data_by_login(
+    github: &dyn GitHubTransport,
+    login: &str,
+) -> ResearchResult<String> {
     #[derive(Debug, Deserialize)]
     struct User {
         name: Option<String>,
@@ -66,19 +69,14 @@ pub async fn get_user_data_by_login(github_token: &str, login: &str) -> Option<S
         repository_owner: Option<User>,
     }
 
-    #[derive(Debug, Deserialize)]
-    struct UserRoot {
-        data: Option<RepositoryOwner>,
-    }
-
-    let base_url = "https://api.github.com/graphql";
+    let base_url = github.graphql_url();
     let mut out = String::from("USER_profile: \n");
 
-    let query = format!(
+    let query = GraphQlQuery::new(
         r#"
-        query {{
-            repositoryOwner(login: "{login}") {{
-                ... on User {{
+        query ($login: String!) {
+            repositoryOwner(login: $login) {
+                ... on User {
                     name
                     login
                     url
@@ -88,115 +86,137 @@ pub async fn get_user_data_by_login(github_token: &str, login: &str) -> Option<S
                     location
                     createdAt
                     email
-                }}
-            }}
-        }}
-        "#
-    );
-
-    match github_http_post(&github_token, base_url, &&query).await {
-        None => {
-            log::info!("Failed to send the request to get UserRoot: {}", base_url);
-            return None;
-        }
-        Some(res) => match serde_json::from_slice::<UserRoot>(res.as_slice()) {
-            Err(e) => {
-                log::error!("Failed to parse the response for UserRoot: {}", e);
-                return None;
+                }
             }
-            Ok(results) => {
-                if let Some(repository_owner) = &results.data {
-                    if let Some(user) = &repository_owner.repository_owner {
-                        let login_str = match &user.login {
-                            Some(login) => format!("Login: {},", login),
-                            None => {
-                                return None;
-                            }
-                        };
-
-                        let name_str = match &user.name {
-                            Some(name) => format!("Name: {},", name),
-                            None => String::new(),
-                        };
-
-                        let url_str = match &user.url {
-                            Some(url) => format!("Url: {},", url),
-                            None => String::new(),
-                        };
-
-                        let twitter_str = match &user.twitter_username {
-                            Some(twitter) => format!("Twitter: {},", twitter),
-                            None => String::new(),
-                        };
-
-                        let bio_str = match &user.bio {
-                            Some(bio) if bio.is_empty() => String::new(),
-                            Some(bio) => format!("Bio: {},", bio),
-                            None => String::new(),
-                        };
-
-                        let company_str = match &user.company {
-                            Some(company) => format!("Company: {},", company),
-                            None => String::new(),
-                        };
-
-                        let location_str = match &user.location {
-                            Some(location) => format!("Location: {},", location),
-                            None => String::new(),
-                        };
+        }
+        "#,
+    )
+    .variable("login", login);
 
-                        let date_str = match &user.created_at {
-                            Some(date) => {
-                                format!("Created At: {},", date.date_naive().to_string())
-                            }
-                            None => String::new(),
-                        };
+    match github_graphql::<RepositoryOwner>(github, &query).await {
+        Err(e) => {
+            log::info!("Failed to get UserRoot from {}: {}", base_url, e);
+            return Err(e);
+        }
+        Ok(results) => {
+            // Unknown logins come back as a null owner, organizations as an owner
+            // without the `User` fields.
+            let repository_owner = &results.data;
+            if let Some(user) = &repository_owner.repository_owner {
+                let login_str = match &user.login {
+                    Some(login) => format!("Login: {},", login),
+                    None => {
+                        return Err(ResearchError::NotFound(format!("user {login}")));
+                    }
+                };
+
+                let name_str = match &user.name {
+                    Some(name) => format!("Name: {},", name),
+                    None => String::new(),
+                };
+
+                let url_str = match &user.url {
+                    Some(url) => format!("Url: {},", url),
+                    None => String::new(),
+                };
+
+                let twitter_str = match &user.twitter_username {
+                    Some(twitter) => format!("Twitter: {},", twitter),
+                    None => String::new(),
+                };
+
+                let bio_str = match &user.bio {
+                    Some(bio) if bio.is_empty() => String::new(),
+                    Some(bio) => format!("Bio: {},", bio),
+                    None => String::new(),
+                };
+
+                let company_str = match &user.company {
+                    Some(company) => format!("Company: {},", company),
+                    None => String::new(),
+                };
+
+                let location_str = match &user.location {
+                    Some(location) => format!("Location: {},", location),
+                    None => String::new(),
+                };
+
+                let date_str = match &user.created_at {
+                    Some(date) => {
+                        format!("Created At: {},", date.date_naive())
+                    }
+                    None => String::new(),
+                };
 
-                        let email_str = match &user.email {
-                            Some(email) => format!("Email: {}", email),
-                            None => String::new(),
-                        };
+                let email_str = match &user.email {
+                    Some(email) => format!("Email: {}", email),
+                    None => String::new(),
+                };
 
-                        out.push_str(
+                out.push_str(
                                 &format!(
                                     "{name_str} {login_str} {url_str} {twitter_str} {bio_str} {company_str} {location_str} {date_str} {email_str}\n"
                                 )
                             );
-                    }
-                }
+            } else {
+                return Err(ResearchError::NotFound(format!("user {login}")));
             }
-        },
+        }
     }
 
-    Some(out)
+    Ok(out)
 }
 pub async fn get_community_profile_data(
-    github_token: &str,
+    github: &dyn GitHubTransport,
     owner: &str,
     repo: &str,
-) -> Option<String> {
+) -> ResearchResult<String> {
     #[derive(Deserialize, Debug)]
     struct CommunityProfile {
         description: String,
         // documentation: Option<String>,
     }
 
-    let community_profile_url =
-        format!("https://api.github.com/repos/{owner}/{repo}/community/profile");
+    let community_profile_url = format!(
+        "{}/repos/{owner}/{repo}/community/profile",
+        github.base_url()
+    );
 
-    match github_http_fetch(&github_token, &community_profile_url).await {
-        Some(res) => match serde_json::from_slice::<CommunityProfile>(&res) {
-            Ok(profile) => {
-                return Some(format!("Description: {}", profile.description));
+    match github_http_fetch(github, &community_profile_url).await {
+        Ok(res) => match serde_json::from_slice::<CommunityProfile>(&res) {
+            Ok(profile) => Ok(format!("Description: {}", profile.description)),
+            Err(e) => {
+                log::error!("Error parsing Community Profile: {:?}", e);
+                Err(e.into())
             }
-            Err(e) => log::error!("Error parsing Community Profile: {:?}", e),
         },
-        None => log::error!("Community profile not found for {}/{}.", owner, repo),
+        Err(e) => {
+            log::error!("Community profile not found for {}/{}.", owner, repo);
+            Err(e)
+        }
     }
-    None
 }
+// The repo's languages, most used first.
+pub async fn get_repo_languages(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+) -> ResearchResult<Vec<String>> {
+    let url = format!("{}/repos/{owner}/{repo}/languages", github.base_url());
+
+    let res = github_http_fetch(github, &url).await?;
+    let mut languages = serde_json::from_slice::<std::collections::HashMap<String, u64>>(&res)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    languages.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    Ok(languages
+        .into_iter()
+        .map(|(language, _)| language)
+        .collect())
+}
+
 pub async fn is_code_contributor(
-    github_token: &str,
+    github: &dyn GitHubTransport,
     owner: &str,
     repo: &str,
     user_name: &str,
@@ -212,75 +232,68 @@ pub async fn is_code_contributor(
         .and_then(|val| serde_json::from_value::<std::collections::HashSet<String>>(val).ok())
     {
         Some(set) => set.contains(user_name),
-        None => match get_contributors(github_token, owner, repo).await {
-            Some(contributors) => {
+        None => match get_contributors(github, owner, repo).await {
+            Ok(contributors) => {
                 set(
                     key,
                     serde_json::to_value(contributors.clone()).unwrap_or_default(),
                     None,
                 );
-                return contributors.contains(&user_name.to_owned());
+                contributors.contains(&user_name.to_owned())
             }
-            None => {
-                log::error!("Github contributors not found.");
-                return false;
+            Err(e) => {
+                log::error!("Github contributors not found: {}", e);
+                false
             }
         },
     }
 }
 
-pub async fn get_contributors(github_token: &str, owner: &str, repo: &str) -> Option<Vec<String>> {
+pub async fn get_contributors(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+) -> ResearchResult<Vec<String>> {
     #[derive(Debug, Deserialize)]
     struct GithubUser {
         login: String,
     }
 
     let url = format!(
-        "https://api.github.com/repos/{}/{}/contributors",
-        owner, repo
+        "{}/repos/{}/{}/contributors",
+        github.base_url(),
+        owner,
+        repo
     );
     let mut contributors = Vec::new();
 
     let mut current_url = url.to_owned();
     loop {
-        let response_result: Result<(Response, Vec<u8>), Box<dyn std::error::Error>> =
-            github_fetch_with_header(&github_token, &current_url);
-        match response_result {
+        match github_fetch_with_header(github, &current_url).await {
             Err(e) => {
                 log::error!(
                     "Error getting response for request to get contributors: {:?}",
                     e
                 );
-                return None;
+                return Err(e);
             }
-            Ok((res, body)) => {
-                let status = res.status_code(); // Check the status code
-                if !status.is_success() {
-                    log::error!(
-                        "Request to get contributors, unexpected status code: {:?}",
-                        status
-                    );
-                    return None;
-                }
-
+            Ok(res) => {
                 let new_contributors: Vec<GithubUser> =
-                    match serde_json::from_slice(body.as_slice()) {
+                    match serde_json::from_slice(res.body.as_slice()) {
                         Ok(contributors) => contributors,
                         Err(err) => {
                             log::error!("Error parsing contributors: {:?}", err);
-                            return None;
+                            return Err(err.into());
                         }
                     };
 
                 contributors.extend(new_contributors.into_iter().map(|user| user.login));
 
                 // Handle pagination
-                let res_headers = res.headers();
-                let link_header = res_headers.get("Link");
+                let link_header = res.header("Link");
                 match link_header {
                     Some(header) => {
                         let next_link_temp: Option<String> = header
-                            .as_str()
                             .split(',')
                             .filter_map(|link| {
                                 if link.contains("rel=\"next\"") {
@@ -309,77 +322,132 @@ pub async fn get_contributors(github_token: &str, owner: &str, repo: &str) -> Op
         }
     }
 
-    Some(contributors)
+    Ok(contributors)
 }
 
-pub async fn get_readme(github_token: &str, owner: &str, repo: &str) -> Option<String> {
+pub async fn get_readme(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+) -> ResearchResult<String> {
     #[derive(Deserialize, Debug)]
     struct GithubReadme {
         content: Option<String>,
     }
 
-    let readme_url = format!("https://api.github.com/repos/{owner}/{repo}/readme");
+    let readme_url = format!("{}/repos/{owner}/{repo}/readme", github.base_url());
 
-    match github_http_fetch(&github_token, &readme_url).await {
-        Some(res) => match serde_json::from_slice::<GithubReadme>(&res) {
+    match github_http_fetch(github, &readme_url).await {
+        Ok(res) => match serde_json::from_slice::<GithubReadme>(&res) {
             Ok(readme) => {
                 if let Some(c) = readme.content {
                     let cleaned_content = c.replace("\n", "");
-                    match base64::decode(&cleaned_content) {
+                    match base64::engine::general_purpose::STANDARD.decode(&cleaned_content) {
                         Ok(decoded_content) => match String::from_utf8(decoded_content) {
-                            Ok(out) => {
-                                return Some(format!("Readme: {}", out));
-                            }
+                            Ok(out) => Ok(format!("Readme: {}", out)),
                             Err(e) => {
                                 log::error!("Failed to convert cleaned readme to String: {:?}", e);
-                                return None;
+                                Err(ResearchError::Parse(e.to_string()))
                             }
                         },
                         Err(e) => {
                             log::error!("Error decoding base64 content: {:?}", e);
-                            None
+                            Err(ResearchError::Parse(e.to_string()))
                         }
                     }
                 } else {
                     log::error!("Content field in readme is null.");
-                    None
+                    Err(ResearchError::NoData(format!("readme of {owner}/{repo}")))
                 }
             }
             Err(e) => {
                 log::error!("Error parsing Readme: {:?}", e);
-                None
+                Err(e.into())
             }
         },
-        None => {
+        Err(e) => {
             log::error!("Github readme not found.");
-            None
+            Err(e)
+        }
+    }
+}
+
+// Doesn't count against the limits it reports.
+pub async fn get_rate_limit(github: &dyn GitHubTransport) -> ResearchResult<RateLimit> {
+    let rate_limit_url = format!("{}/rate_limit", github.base_url());
+
+    match github_http_fetch(github, &rate_limit_url).await {
+        Ok(res) => match serde_json::from_slice::<RateLimit>(&res) {
+            Ok(rate_limit) => Ok(rate_limit),
+            Err(e) => {
+                log::error!("Error parsing RateLimit: {:?}", e);
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            log::error!("Github rate limit not available.");
+            Err(e)
+        }
+    }
+}
+
+pub async fn get_release_date(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+    tag: &str,
+) -> ResearchResult<DateTime<Utc>> {
+    #[derive(Deserialize, Debug)]
+    struct Release {
+        created_at: Option<DateTime<Utc>>,
+        published_at: Option<DateTime<Utc>>,
+    }
+
+    let release_url = format!(
+        "{}/repos/{owner}/{repo}/releases/tags/{}",
+        github.base_url(),
+        urlencoding::encode(tag)
+    );
+
+    match github_http_fetch(github, &release_url).await {
+        Ok(res) => match serde_json::from_slice::<Release>(&res) {
+            Ok(release) => release
+                .published_at
+                .or(release.created_at)
+                .ok_or_else(|| ResearchError::NoData(format!("date of release {tag}"))),
+            Err(e) => {
+                log::error!("Error parsing Release: {:?}", e);
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            log::error!("Release {} not found for {}/{}.", tag, owner, repo);
+            Err(e)
         }
     }
 }
 
 pub async fn get_issues_in_range(
-    github_token: &str,
+    github: &dyn GitHubTransport,
     owner: &str,
     repo: &str,
     user_name: Option<String>,
-    range: u16,
-) -> Option<(usize, Vec<Issue>)> {
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> ResearchResult<(usize, Vec<Issue>)> {
     #[derive(Debug, Deserialize)]
     struct Page<T> {
         pub items: Vec<T>,
         pub total_count: Option<u64>,
     }
 
-    let now = Utc::now();
-    let n_days_ago = (now - Duration::days(range as i64))
-        .format("%Y-%m-%dT%H:%M:%SZ")
-        .to_string();
+    let range_str = TimeWindow::new(since, until).search_range();
 
     let user_str = user_name
         .map(|u| format!("involves:{}", u))
         .unwrap_or_default();
 
-    let query = format!("repo:{owner}/{repo} is:issue {user_str} updated:>{n_days_ago}");
+    let query = format!("repo:{owner}/{repo} is:issue {user_str} updated:{range_str}");
     let encoded_query = urlencoding::encode(&query);
 
     let mut issue_vec = vec![];
@@ -388,14 +456,19 @@ pub async fn get_issues_in_range(
 
     loop {
         let url_str = format!(
-            "https://api.github.com/search/issues?q={}&sort=updated&order=desc&page={}",
-            encoded_query, current_page
+            "{}/search/issues?q={}&sort=updated&order=desc&page={}",
+            github.base_url(),
+            encoded_query,
+            current_page
         );
 
-        match github_http_fetch(&github_token, &url_str).await {
-            Some(res) => match serde_json::from_slice::<Page<Issue>>(res.as_slice()) {
+        match github_http_fetch(github, &url_str).await {
+            Ok(res) => match serde_json::from_slice::<Page<Issue>>(res.as_slice()) {
                 Err(e) => {
                     log::error!("error: {:?}", e);
+                    if issue_vec.is_empty() {
+                        return Err(e.into());
+                    }
                     break;
                 }
                 Ok(issue_page) => {
@@ -415,16 +488,19 @@ pub async fn get_issues_in_range(
                     }
                 }
             },
-            None => {
-                break;
-            }
+            // Earlier pages are kept when a later one fails, e.g. on rate limits.
+            Err(e) if issue_vec.is_empty() => return Err(e),
+            Err(_) => break,
         }
     }
     let count = issue_vec.len();
-    Some((count, issue_vec))
+    Ok((count, issue_vec))
 }
 
-pub async fn get_issue_texts(github_token: &str, issue: &Issue) -> Option<String> {
+pub async fn get_issue_texts(
+    github: &dyn GitHubTransport,
+    issue: &Issue,
+) -> ResearchResult<String> {
     let issue_creator_name = &issue.user.login;
     let issue_title = &issue.title;
     let issue_body = match &issue.body {
@@ -449,8 +525,8 @@ pub async fn get_issue_texts(github_token: &str, issue: &Issue) -> Option<String
     loop {
         let url_str = format!("{}/comments?&page={}", issue_url, current_page);
 
-        match github_http_fetch(&github_token, &url_str).await {
-            Some(res) => match serde_json::from_slice::<Vec<Comment>>(res.as_slice()) {
+        match github_http_fetch(github, &url_str).await {
+            Ok(res) => match serde_json::from_slice::<Vec<Comment>>(res.as_slice()) {
                 Err(_e) => {
                     log::error!(
                         "Error parsing Vec<Comment> at page {}: {:?}",
@@ -477,7 +553,7 @@ pub async fn get_issue_texts(github_token: &str, issue: &Issue) -> Option<String
                     }
                 }
             },
-            None => {
+            Err(_) => {
                 break;
             }
         }
@@ -485,16 +561,17 @@ pub async fn get_issue_texts(github_token: &str, issue: &Issue) -> Option<String
         current_page += 1;
     }
 
-    Some(all_text_from_issue)
+    Ok(all_text_from_issue)
 }
 
 pub async fn get_commits_in_range(
-    github_token: &str,
+    github: &dyn GitHubTransport,
     owner: &str,
     repo: &str,
     user_name: Option<String>,
-    range: u16,
-) -> Option<(usize, Vec<GitMemory>)> {
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> ResearchResult<(usize, Vec<GitMemory>)> {
     #[derive(Debug, Deserialize, Serialize)]
     struct User {
         login: String,
@@ -522,27 +599,36 @@ pub async fn get_commits_in_range(
     }
 
     let author_str = match user_name {
-        Some(user_name) => format!("author={}", user_name),
+        Some(user_name) => format!("author={}&", user_name),
         None => "".to_string(),
     };
 
-    let base_commit_url =
-        format!("https://api.github.com/repos/{owner}/{repo}/commits?{author_str}");
+    let base_commit_url = format!(
+        "{}/repos/{owner}/{repo}/commits?{author_str}since={}&until={}",
+        github.base_url(),
+        format_iso(&since),
+        format_iso(&until)
+    );
 
     let mut git_memory_vec = vec![];
-    let now = Utc::now();
-    let n_days_ago = (now - Duration::days(range as i64)).date_naive();
+    let window = TimeWindow::new(since, until);
     let mut current_page = 1;
     loop {
         let commits_query_url = format!("{base_commit_url}&page={}", current_page);
-        match github_http_fetch(&github_token, &commits_query_url).await {
-            None => {
+        match github_http_fetch(github, &commits_query_url).await {
+            Err(e) => {
                 log::error!("Error fetching commits");
+                if current_page == 1 {
+                    return Err(e);
+                }
                 break;
             }
-            Some(res) => match serde_json::from_slice::<Vec<GithubCommit>>(res.as_slice()) {
+            Ok(res) => match serde_json::from_slice::<Vec<GithubCommit>>(res.as_slice()) {
                 Err(e) => {
                     log::error!("Error parsing commits: {:?}", e);
+                    if current_page == 1 {
+                        return Err(e.into());
+                    }
                     break;
                 }
                 Ok(commits) => {
@@ -552,7 +638,7 @@ pub async fn get_commits_in_range(
 
                     for commit in commits {
                         if let Some(commit_date) = &commit.commit.author.date {
-                            if commit_date.date_naive() > n_days_ago {
+                            if window.contains(commit_date) {
                                 if let Some(author) = &commit.author {
                                     git_memory_vec.push(GitMemory {
                                         memory_type: MemoryType::Commit,
@@ -574,189 +660,1540 @@ pub async fn get_commits_in_range(
     }
 
     let count = git_memory_vec.len();
-    Some((count, git_memory_vec))
+    Ok((count, git_memory_vec))
 }
 
-pub async fn get_user_repos_in_language(
-    github_token: &str,
-    user: &str,
-    language: &str,
-) -> Option<Vec<Repository>> {
-    #[derive(Debug, Deserialize)]
-    struct Page<T> {
-        pub items: Vec<T>,
-        pub total_count: Option<u64>,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PullRequestReview {
+    pub reviewer: String,
+    pub state: String,
+    pub body: String,
+    pub comments: Vec<String>,
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub author: String,
+    pub body: String,
+    pub state: String,
+    pub created_at: DateTime<Utc>,
+    pub merged_at: Option<DateTime<Utc>>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub additions: u64,
+    pub deletions: u64,
+    pub changed_files: u64,
+    pub reviewers: Vec<String>,
+    pub reviews: Vec<PullRequestReview>,
+    pub linked_issues: Vec<String>,
+}
+
+impl PullRequest {
+    // Opened, merged or closed within the window.
+    pub fn is_active_within(&self, window: &TimeWindow) -> bool {
+        window.contains(&self.created_at)
+            || self.merged_at.is_some_and(|d| window.contains(&d))
+            || self.closed_at.is_some_and(|d| window.contains(&d))
     }
+}
 
-    let query = format!("user:{} language:{} sort:stars", user, language);
-    let encoded_query = urlencoding::encode(&query);
+// Caps the reviews fetched per pull request and the comments fetched per review.
+const MAX_REVIEWS_PER_PULL_REQUEST: usize = 300;
+const MAX_COMMENTS_PER_REVIEW: usize = 300;
 
-    let mut out: Vec<Repository> = vec![];
-    let mut total_pages = None;
-    let mut current_page = 1;
+#[derive(Debug, Deserialize)]
+struct ReviewAuthor {
+    login: Option<String>,
+}
 
-    loop {
-        let url_str = format!(
-            "https://api.github.com/search/repositories?q={}&page={}",
-            encoded_query, current_page
-        );
+#[derive(Debug, Deserialize)]
+struct ReviewNode {
+    id: Option<String>,
+    author: Option<ReviewAuthor>,
+    state: Option<String>,
+    body: Option<String>,
+    #[serde(rename = "submittedAt")]
+    submitted_at: Option<DateTime<Utc>>,
+    comments: Option<Connection<ReviewCommentNode>>,
+}
 
-        match github_http_fetch(&github_token, &url_str).await {
-            Some(res) => match serde_json::from_slice::<Page<Repository>>(res.as_slice()) {
-                Err(_e) => {
-                    log::error!("Error parsing Page<Repository>: {:?}", _e);
-                    break;
-                }
-                Ok(repo_page) => {
-                    if total_pages.is_none() {
-                        if let Some(count) = repo_page.total_count {
-                            total_pages = Some(((count as f64) / 30.0).ceil() as usize);
+#[derive(Debug, Deserialize)]
+struct ReviewCommentNode {
+    body: Option<String>,
+}
+
+const PULL_REQUEST_REVIEWS_QUERY: &str = r#"
+    query ($id: ID!, $cursor: String) {
+        node(id: $id) {
+            ... on PullRequest {
+                reviews(first: 50, after: $cursor) {
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                    nodes {
+                        id
+                        author {
+                            login
+                        }
+                        state
+                        body
+                        submittedAt
+                        comments(first: 10) {
+                            pageInfo {
+                                endCursor
+                                hasNextPage
+                            }
+                            nodes {
+                                body
+                            }
                         }
                     }
+                }
+            }
+        }
+    }
+"#;
 
-                    for repo in repo_page.items {
-                        out.push(repo);
+const REVIEW_COMMENTS_QUERY: &str = r#"
+    query ($id: ID!, $cursor: String) {
+        node(id: $id) {
+            ... on PullRequestReview {
+                comments(first: 100, after: $cursor) {
+                    pageInfo {
+                        endCursor
+                        hasNextPage
                     }
-
-                    current_page += 1;
-                    if current_page > total_pages.unwrap_or(usize::MAX) {
-                        break;
+                    nodes {
+                        body
                     }
                 }
-            },
-            None => {
-                break;
             }
         }
     }
+"#;
+
+// Follows the remaining pages of the reviews of the pull request `pull_request_id`, and
+// of the comments of each review.
+async fn collect_reviews(
+    github: &dyn GitHubTransport,
+    pull_request_id: Option<&str>,
+    first_page: Connection<ReviewNode>,
+) -> Vec<PullRequestReview> {
+    let nodes = match pull_request_id {
+        Some(id) => {
+            follow_connection(
+                github,
+                PULL_REQUEST_REVIEWS_QUERY,
+                id,
+                first_page,
+                MAX_REVIEWS_PER_PULL_REQUEST,
+            )
+            .await
+        }
+        None => first_page.nodes.into_iter().flatten().flatten().collect(),
+    };
 
-    if out.is_empty() {
-        None
-    } else {
-        Some(out)
-    }
+    let mut reviews = vec![];
+    for review in nodes {
+        let comments = match (review.id, review.comments) {
+            (Some(id), Some(first_page)) => {
+                follow_connection(
+                    github,
+                    REVIEW_COMMENTS_QUERY,
+                    &id,
+                    first_page,
+                    MAX_COMMENTS_PER_REVIEW,
+                )
+                .await
+            }
+            (None, Some(first_page)) => first_page.nodes.into_iter().flatten().flatten().collect(),
+            (_, None) => vec![],
+        };
+        reviews.push(PullRequestReview {
+            reviewer: review.author.and_then(|a| a.login).unwrap_or_default(),
+            state: review.state.unwrap_or_default(),
+            body: review.body.unwrap_or_default(),
+            comments: comments.into_iter().filter_map(|c| c.body).collect(),
+            submitted_at: review.submitted_at,
+        });
+    }
+    reviews
 }
 
-pub async fn get_user_repos_gql(
-    github_token: &str,
-    user_name: &str,
-    language: &str,
-) -> Option<String> {
-    #[derive(Debug, Deserialize)]
-    struct Root {
-        data: Data,
-    }
-
+// Pull requests updated within the window, involving `user_name` if given, with all
+// their reviews.
+async fn search_pull_requests(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+    user_name: Option<&str>,
+    window: &TimeWindow,
+) -> ResearchResult<Vec<PullRequest>> {
     #[derive(Debug, Deserialize)]
     struct Data {
-        search: Search,
+        search: Option<Search>,
     }
 
     #[derive(Debug, Deserialize)]
     struct Search {
-        nodes: Vec<Node>,
-    }
-
-    #[derive(Debug, Deserialize)]
-    pub struct Node {
-        pub name: String,
-        #[serde(rename = "defaultBranchRef")]
-        default_branch_ref: BranchRef,
-        stargazers: Stargazers,
-        pub description: Option<String>,
-    }
-    #[derive(Debug, Deserialize)]
-    struct BranchRef {
-        target: Target,
+        nodes: Option<Vec<Option<Node>>>,
+        #[serde(rename = "pageInfo")]
+        page_info: Option<PageCursor>,
     }
 
     #[derive(Debug, Deserialize)]
-    struct Target {
-        history: History,
+    struct Node {
+        id: Option<String>,
+        number: Option<u64>,
+        title: Option<String>,
+        url: Option<String>,
+        body: Option<String>,
+        state: Option<String>,
+        author: Option<ReviewAuthor>,
+        #[serde(rename = "createdAt")]
+        created_at: Option<DateTime<Utc>>,
+        #[serde(rename = "mergedAt")]
+        merged_at: Option<DateTime<Utc>>,
+        #[serde(rename = "closedAt")]
+        closed_at: Option<DateTime<Utc>>,
+        additions: Option<u64>,
+        deletions: Option<u64>,
+        #[serde(rename = "changedFiles")]
+        changed_files: Option<u64>,
+        reviews: Option<Connection<ReviewNode>>,
+        #[serde(rename = "closingIssuesReferences")]
+        closing_issues_references: Option<LinkedIssues>,
     }
 
     #[derive(Debug, Deserialize)]
-    struct History {
-        #[serde(rename = "totalCount")]
-        total_count: i32,
+    struct LinkedIssues {
+        nodes: Option<Vec<Option<LinkedIssue>>>,
     }
 
     #[derive(Debug, Deserialize)]
-    struct Stargazers {
-        #[serde(rename = "totalCount")]
-        total_count: i32,
+    struct LinkedIssue {
+        url: Option<String>,
     }
 
-    let query = format!(
-        r#"
-    query {{
-        search(query: "user:{} language:{}", type: REPOSITORY, first: 100) {{
-            nodes {{
-                ... on Repository {{
-                    name
-                    defaultBranchRef {{
-                        target {{
-                            ... on Commit {{
-                                history(first: 0) {{
-                                    totalCount
-                                }}
-                            }}
-                        }}
-                    }}
-                    description
-                    stargazers {{
-                        totalCount
-                    }}
-                }}
-            }}
-        }}
-    }}
-    "#,
-        user_name, language
+    let user_str = user_name
+        .map(|u| format!("involves:{}", u))
+        .unwrap_or_default();
+    let search_query = format!(
+        "repo:{owner}/{repo} is:pr {user_str} updated:{}",
+        window.search_range()
     );
 
-    let base_url = "https://api.github.com/graphql";
-    let mut out = format!("Repos in {language}:\n");
-    match github_http_post(&github_token, base_url, &query).await {
-        None => log::error!("Failed to send the request to {}", base_url.to_string()),
-        Some(response) => match serde_json::from_slice::<Root>(response.as_slice()) {
-            Err(e) => log::error!("Failed to parse the response: {}", e),
-            Ok(repos) => {
-                let mut repos_sorted: Vec<&Node> = repos.data.search.nodes.iter().collect();
-                repos_sorted
-                    .sort_by(|a, b| b.stargazers.total_count.cmp(&a.stargazers.total_count));
-
-                for repo in repos_sorted {
-                    let name_str = format!("Repo: {}", repo.name);
-
-                    let description_str = match &repo.description {
-                        Some(description) => format!("Description: {},", description),
-                        None => String::new(),
+    let base_url = github.graphql_url();
+    let mut pr_vec = vec![];
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let query = GraphQlQuery::new(
+            r#"
+            query ($searchQuery: String!, $cursor: String) {
+                rateLimit {
+                    cost
+                    remaining
+                    resetAt
+                }
+                search(query: $searchQuery, type: ISSUE, first: 50, after: $cursor) {
+                    nodes {
+                        ... on PullRequest {
+                            id
+                            number
+                            title
+                            url
+                            body
+                            state
+                            author {
+                                login
+                            }
+                            createdAt
+                            mergedAt
+                            closedAt
+                            additions
+                            deletions
+                            changedFiles
+                            reviews(first: 20) {
+                                pageInfo {
+                                    endCursor
+                                    hasNextPage
+                                }
+                                nodes {
+                                    id
+                                    author {
+                                        login
+                                    }
+                                    state
+                                    body
+                                    submittedAt
+                                    comments(first: 10) {
+                                        pageInfo {
+                                            endCursor
+                                            hasNextPage
+                                        }
+                                        nodes {
+                                            body
+                                        }
+                                    }
+                                }
+                            }
+                            closingIssuesReferences(first: 10) {
+                                nodes {
+                                    url
+                                }
+                            }
+                        }
+                    }
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                }
+            }
+            "#,
+        )
+        .variable("searchQuery", &search_query)
+        .variable("cursor", &cursor);
+
+        match github_graphql::<Data>(github, &query).await {
+            Err(e) => {
+                log::error!("Failed to get pull requests from {}: {}", base_url, e);
+                if cursor.is_none() {
+                    return Err(e);
+                }
+                break;
+            }
+            Ok(results) => {
+                let search = match results.data.search {
+                    Some(search) => search,
+                    None => break,
+                };
+
+                for node in search.nodes.unwrap_or_default().into_iter().flatten() {
+                    let created_at = match node.created_at {
+                        Some(date) => date,
+                        None => continue,
                     };
 
-                    let stars_str = match repo.stargazers.total_count {
-                        0 => String::new(),
-                        count => format!("Stars: {count}"),
+                    let reviews = match node.reviews {
+                        Some(first_page) => {
+                            collect_reviews(github, node.id.as_deref(), first_page).await
+                        }
+                        None => vec![],
                     };
 
-                    let commits_str = format!(
-                        "Commits: {}",
-                        repo.default_branch_ref.target.history.total_count
-                    );
+                    let mut reviewers = reviews
+                        .iter()
+                        .map(|r| r.reviewer.clone())
+                        .filter(|r| !r.is_empty())
+                        .collect::<Vec<String>>();
+                    reviewers.sort();
+                    reviewers.dedup();
+
+                    let linked_issues = node
+                        .closing_issues_references
+                        .and_then(|l| l.nodes)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|i| i.url)
+                        .collect();
+
+                    pr_vec.push(PullRequest {
+                        number: node.number.unwrap_or_default(),
+                        title: node.title.unwrap_or_default(),
+                        url: node.url.unwrap_or_default(),
+                        author: node.author.and_then(|a| a.login).unwrap_or_default(),
+                        body: node.body.unwrap_or_default(),
+                        state: node.state.unwrap_or_default(),
+                        created_at,
+                        merged_at: node.merged_at,
+                        closed_at: node.closed_at,
+                        additions: node.additions.unwrap_or_default(),
+                        deletions: node.deletions.unwrap_or_default(),
+                        changed_files: node.changed_files.unwrap_or_default(),
+                        reviewers,
+                        reviews,
+                        linked_issues,
+                    });
+                }
 
-                    let temp = format!("{name_str} {description_str} {stars_str} {commits_str}\n");
+                match search.page_info.and_then(|p| p.next_cursor()) {
+                    Some(end_cursor) => cursor = Some(end_cursor),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    Ok(pr_vec)
+}
+
+// Pull requests opened, merged or closed within `since..until`.
+pub async fn get_pull_requests_in_range(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+    user_name: Option<String>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> ResearchResult<(usize, Vec<PullRequest>)> {
+    let window = TimeWindow::new(since, until);
+    let pr_vec = search_pull_requests(github, owner, repo, user_name.as_deref(), &window)
+        .await?
+        .into_iter()
+        .filter(|pr| pr.is_active_within(&window))
+        .collect::<Vec<PullRequest>>();
+    Ok((pr_vec.len(), pr_vec))
+}
+
+// The pull requests opened, merged or closed within `since..until` and the review
+// activity on every pull request updated then, from a single search.
+pub async fn get_pull_requests_and_reviews(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+    user_name: Option<String>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> ResearchResult<(Vec<PullRequest>, Vec<ReviewerActivity>)> {
+    let window = TimeWindow::new(since, until);
+    let mut pr_vec =
+        search_pull_requests(github, owner, repo, user_name.as_deref(), &window).await?;
+    let activity = review_activity(&pr_vec, &window, user_name.as_deref());
+    pr_vec.retain(|pr| pr.is_active_within(&window));
+    Ok((pr_vec, activity))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReviewerActivity {
+    pub login: String,
+    pub approvals: usize,
+    pub change_requests: usize,
+    pub commented_reviews: usize,
+    pub review_comments: usize,
+    pub pull_requests: Vec<String>,
+    // Hours between a pull request being opened and this reviewer's first review of it,
+    // for the pull requests they first reviewed within the window.
+    pub turnaround_hours: Vec<f64>,
+}
+
+impl ReviewerActivity {
+    pub fn reviews(&self) -> usize {
+        self.approvals + self.change_requests + self.commented_reviews
+    }
+
+    pub fn average_turnaround_hours(&self) -> Option<f64> {
+        match self.turnaround_hours.is_empty() {
+            true => None,
+            false => {
+                Some(self.turnaround_hours.iter().sum::<f64>() / self.turnaround_hours.len() as f64)
+            }
+        }
+    }
+}
+
+// Reviews, and the comments they carry, submitted within the window, grouped by reviewer
+// login and sorted by review count. Authors reviewing their own pull requests are left out.
+pub fn review_activity(
+    pull_requests: &[PullRequest],
+    window: &TimeWindow,
+    user_name: Option<&str>,
+) -> Vec<ReviewerActivity> {
+    let mut activity = std::collections::HashMap::<String, ReviewerActivity>::new();
+    for pr in pull_requests {
+        // Each reviewer's first review of the pull request, within the window or not.
+        let mut first_reviews = std::collections::HashMap::<&str, DateTime<Utc>>::new();
+        for review in &pr.reviews {
+            if let Some(date) = review.submitted_at {
+                first_reviews
+                    .entry(review.reviewer.as_str())
+                    .and_modify(|first| *first = (*first).min(date))
+                    .or_insert(date);
+            }
+        }
+
+        for review in &pr.reviews {
+            let submitted_at = match review.submitted_at {
+                Some(date) if window.contains(&date) => date,
+                _ => continue,
+            };
+            if review.reviewer.is_empty() || review.reviewer.eq_ignore_ascii_case(&pr.author) {
+                continue;
+            }
+            let entry =
+                activity
+                    .entry(review.reviewer.clone())
+                    .or_insert_with(|| ReviewerActivity {
+                        login: review.reviewer.clone(),
+                        ..Default::default()
+                    });
+            match review.state.as_str() {
+                "APPROVED" => entry.approvals += 1,
+                "CHANGES_REQUESTED" => entry.change_requests += 1,
+                "COMMENTED" => entry.commented_reviews += 1,
+                _ => continue,
+            }
+            entry.review_comments += review.comments.len();
+            if !entry.pull_requests.contains(&pr.url) {
+                entry.pull_requests.push(pr.url.clone());
+            }
+            if first_reviews.get(review.reviewer.as_str()) == Some(&submitted_at) {
+                first_reviews.remove(review.reviewer.as_str());
+                let hours = (submitted_at - pr.created_at).num_minutes() as f64 / 60.0;
+                entry.turnaround_hours.push(hours.max(0.0));
+            }
+        }
+    }
+
+    let mut out = activity
+        .into_values()
+        .filter(|a| user_name.is_none_or(|u| a.login.eq_ignore_ascii_case(u)))
+        .collect::<Vec<ReviewerActivity>>();
+    out.sort_by_key(|a| std::cmp::Reverse(a.reviews()));
+    out
+}
+
+// Review activity on the pull requests updated within `since..until`, see `review_activity`.
+pub async fn get_review_activity(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+    user_name: Option<String>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> ResearchResult<Vec<ReviewerActivity>> {
+    let window = TimeWindow::new(since, until);
+    let pr_vec = search_pull_requests(github, owner, repo, user_name.as_deref(), &window).await?;
+    Ok(review_activity(&pr_vec, &window, user_name.as_deref()))
+}
+
+pub async fn get_user_repos_in_language(
+    github: &dyn GitHubTransport,
+    user: &str,
+    language: &str,
+) -> ResearchResult<Vec<Repository>> {
+    #[derive(Debug, Deserialize)]
+    struct Page<T> {
+        pub items: Vec<T>,
+        pub total_count: Option<u64>,
+    }
+
+    let query = format!("user:{} language:{} sort:stars", user, language);
+    let encoded_query = urlencoding::encode(&query);
+
+    let mut out: Vec<Repository> = vec![];
+    let mut total_pages = None;
+    let mut current_page = 1;
+
+    loop {
+        let url_str = format!(
+            "{}/search/repositories?q={}&page={}",
+            github.base_url(),
+            encoded_query,
+            current_page
+        );
+
+        match github_http_fetch(github, &url_str).await {
+            Ok(res) => match serde_json::from_slice::<Page<Repository>>(res.as_slice()) {
+                Err(_e) => {
+                    log::error!("Error parsing Page<Repository>: {:?}", _e);
+                    if out.is_empty() {
+                        return Err(_e.into());
+                    }
+                    break;
+                }
+                Ok(repo_page) => {
+                    if total_pages.is_none() {
+                        if let Some(count) = repo_page.total_count {
+                            total_pages = Some(((count as f64) / 30.0).ceil() as usize);
+                        }
+                    }
+
+                    for repo in repo_page.items {
+                        out.push(repo);
+                    }
+
+                    current_page += 1;
+                    if current_page > total_pages.unwrap_or(usize::MAX) {
+                        break;
+                    }
+                }
+            },
+            Err(e) if out.is_empty() => return Err(e),
+            Err(_) => {
+                break;
+            }
+        }
+    }
+
+    if out.is_empty() {
+        Err(ResearchError::NoData(format!("{language} repos of {user}")))
+    } else {
+        Ok(out)
+    }
+}
+
+pub async fn get_user_repos_gql(
+    github: &dyn GitHubTransport,
+    user_name: &str,
+    language: &str,
+) -> ResearchResult<String> {
+    #[derive(Debug, Deserialize)]
+    struct Data {
+        search: Search,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Search {
+        nodes: Vec<Node>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Node {
+        pub name: String,
+        #[serde(rename = "defaultBranchRef")]
+        default_branch_ref: BranchRef,
+        stargazers: Stargazers,
+        pub description: Option<String>,
+    }
+    #[derive(Debug, Deserialize)]
+    struct BranchRef {
+        target: Target,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Target {
+        history: History,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct History {
+        #[serde(rename = "totalCount")]
+        total_count: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Stargazers {
+        #[serde(rename = "totalCount")]
+        total_count: i32,
+    }
+
+    let query = GraphQlQuery::new(
+        r#"
+    query ($searchQuery: String!) {
+        rateLimit {
+            cost
+            remaining
+            resetAt
+        }
+        search(query: $searchQuery, type: REPOSITORY, first: 100) {
+            nodes {
+                ... on Repository {
+                    name
+                    defaultBranchRef {
+                        target {
+                            ... on Commit {
+                                history(first: 0) {
+                                    totalCount
+                                }
+                            }
+                        }
+                    }
+                    description
+                    stargazers {
+                        totalCount
+                    }
+                }
+            }
+        }
+    }
+    "#,
+    )
+    .variable(
+        "searchQuery",
+        format!("user:{user_name} language:{language}"),
+    );
+
+    let base_url = github.graphql_url();
+    let mut out = format!("Repos in {language}:\n");
+    match github_graphql::<Data>(github, &query).await {
+        Err(e) => {
+            log::error!("Failed to get repositories from {}: {}", base_url, e);
+            return Err(e);
+        }
+        Ok(repos) => {
+            let mut repos_sorted: Vec<&Node> = repos.data.search.nodes.iter().collect();
+            repos_sorted.sort_by_key(|repo| std::cmp::Reverse(repo.stargazers.total_count));
+
+            for repo in repos_sorted {
+                let name_str = format!("Repo: {}", repo.name);
+
+                let description_str = match &repo.description {
+                    Some(description) => format!("Description: {},", description),
+                    None => String::new(),
+                };
+
+                let stars_str = match repo.stargazers.total_count {
+                    0 => String::new(),
+                    count => format!("Stars: {count}"),
+                };
+
+                let commits_str = format!(
+                    "Commits: {}",
+                    repo.default_branch_ref.target.history.total_count
+                );
+
+                let temp = format!("{name_str} {description_str} {stars_str} {commits_str}\n");
+
+                out.push_str(&temp);
+            }
+
+            log::info!("Found {} repositories", repos.data.search.nodes.len());
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PortfolioRepo {
+    pub name_with_owner: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub stars: u64,
+    // Owned by the user, as opposed to contributed to.
+    pub owned: bool,
+    // Bytes of code per language, largest first.
+    pub languages: Vec<(String, u64)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LanguageSkill {
+    pub language: String,
+    pub bytes: u64,
+    pub repos: usize,
+    // Between 0 and 1, the language's share of each repo averaged over all repos.
+    pub share: f64,
+}
+
+// A user's repositories with the languages they are written in, and those languages
+// ranked across them. Every repo weighs the same, so one huge project contributed to
+// doesn't drown out the user's own work.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LanguagePortfolio {
+    pub login: String,
+    pub repos: Vec<PortfolioRepo>,
+    pub skills: Vec<LanguageSkill>,
+}
+
+impl LanguagePortfolio {
+    fn from_repos(login: &str, repos: Vec<PortfolioRepo>) -> Self {
+        let mut by_language = std::collections::HashMap::<String, LanguageSkill>::new();
+        let mut n_repos = 0;
+        for repo in &repos {
+            let repo_bytes = repo.languages.iter().map(|(_, bytes)| bytes).sum::<u64>();
+            if repo_bytes == 0 {
+                continue;
+            }
+            n_repos += 1;
+            for (language, bytes) in &repo.languages {
+                let skill = by_language
+                    .entry(language.clone())
+                    .or_insert_with(|| LanguageSkill {
+                        language: language.clone(),
+                        ..Default::default()
+                    });
+                skill.bytes += bytes;
+                skill.repos += 1;
+                skill.share += *bytes as f64 / repo_bytes as f64;
+            }
+        }
+        let mut skills = by_language
+            .into_values()
+            .map(|mut skill| {
+                skill.share /= n_repos.max(1) as f64;
+                skill
+            })
+            .collect::<Vec<_>>();
+        skills.sort_by(|a, b| b.share.total_cmp(&a.share).then(b.bytes.cmp(&a.bytes)));
+        LanguagePortfolio {
+            login: login.to_string(),
+            repos,
+            skills,
+        }
+    }
+
+    pub fn top_languages(&self, n: usize) -> Vec<&str> {
+        self.skills
+            .iter()
+            .take(n)
+            .map(|s| s.language.as_str())
+            .collect()
+    }
+
+    // The ranked languages and the most starred repos, as text for prompts.
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("LANGUAGES \n");
+        for skill in &self.skills {
+            out.push_str(&format!(
+                "{}: {:.0}% of the average repo, in {} repos\n",
+                skill.language,
+                skill.share * 100.0,
+                skill.repos
+            ));
+        }
+        out.push_str("REPOSITORIES \n");
+        let mut repos = self.repos.iter().collect::<Vec<_>>();
+        repos.sort_by_key(|repo| std::cmp::Reverse(repo.stars));
+        for repo in repos.into_iter().take(30) {
+            let languages = repo
+                .languages
+                .iter()
+                .take(3)
+                .map(|(language, _)| language.as_str())
+                .collect::<Vec<_>>();
+            out.push_str(&format!(
+                "Repo: {} ({}), Languages: {}, Stars: {}, Description: {}\n",
+                repo.name_with_owner,
+                match repo.owned {
+                    true => "owned",
+                    false => "contributed to",
+                },
+                languages.join(", "),
+                repo.stars,
+                repo.description.as_deref().unwrap_or("")
+            ));
+        }
+        out
+    }
+}
+
+// Repos per kind, owned or contributed to, that go into a portfolio.
+const PORTFOLIO_MAX_REPOS: usize = 100;
+
+const USER_OWNED_REPOS_QUERY: &str = r#"
+    query ($login: String!, $cursor: String) {
+        user(login: $login) {
+            repos: repositories(first: 50, after: $cursor, ownerAffiliations: [OWNER], isFork: false, orderBy: {field: STARGAZERS, direction: DESC}) {
+                pageInfo {
+                    endCursor
+                    hasNextPage
+                }
+                nodes {
+                    nameWithOwner
+                    url
+                    description
+                    stargazerCount
+                    languages(first: 10, orderBy: {field: SIZE, direction: DESC}) {
+                        edges {
+                            size
+                            node {
+                                name
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    "#;
+
+// GitHub only keeps the repos contributed to in the last year.
+const USER_CONTRIBUTED_REPOS_QUERY: &str = r#"
+    query ($login: String!, $cursor: String) {
+        user(login: $login) {
+            repos: repositoriesContributedTo(first: 50, after: $cursor, includeUserRepositories: false, contributionTypes: [COMMIT, PULL_REQUEST, PULL_REQUEST_REVIEW]) {
+                pageInfo {
+                    endCursor
+                    hasNextPage
+                }
+                nodes {
+                    nameWithOwner
+                    url
+                    description
+                    stargazerCount
+                    languages(first: 10, orderBy: {field: SIZE, direction: DESC}) {
+                        edges {
+                            size
+                            node {
+                                name
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    "#;
+
+// Fetches the repos the user owns, forks left out, and those they committed to, opened
+// pull requests on or reviewed, and ranks the languages across them.
+pub async fn get_user_language_portfolio(
+    github: &dyn GitHubTransport,
+    login: &str,
+) -> ResearchResult<LanguagePortfolio> {
+    #[derive(Debug, Deserialize)]
+    struct Data {
+        user: Option<UserRepos>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct UserRepos {
+        repos: Option<Connection<Repo>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Repo {
+        #[serde(rename = "nameWithOwner")]
+        name_with_owner: String,
+        url: String,
+        description: Option<String>,
+        #[serde(rename = "stargazerCount")]
+        stargazer_count: Option<u64>,
+        languages: Option<Languages>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Languages {
+        edges: Option<Vec<Option<LanguageEdge>>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct LanguageEdge {
+        size: u64,
+        node: LanguageName,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct LanguageName {
+        name: String,
+    }
+
+    let base_url = github.graphql_url();
+    let mut repos = vec![];
+    for (query_str, owned) in [
+        (USER_OWNED_REPOS_QUERY, true),
+        (USER_CONTRIBUTED_REPOS_QUERY, false),
+    ] {
+        let mut cursor: Option<String> = None;
+        let mut n_repos = 0;
+        loop {
+            let query = GraphQlQuery::new(query_str)
+                .variable("login", login)
+                .variable("cursor", &cursor);
+
+            let connection = match github_graphql::<Data>(github, &query).await {
+                Err(e) => {
+                    log::error!(
+                        "Failed to get the repos of {} from {}: {}",
+                        login,
+                        base_url,
+                        e
+                    );
+                    if repos.is_empty() && owned {
+                        return Err(e);
+                    }
+                    break;
+                }
+                Ok(results) => match results.data.user {
+                    Some(user) => user.repos,
+                    None => return Err(ResearchError::NotFound(format!("user {login}"))),
+                },
+            };
+            let Some(connection) = connection else {
+                break;
+            };
+
+            for repo in connection.nodes.unwrap_or_default().into_iter().flatten() {
+                let languages = repo
+                    .languages
+                    .and_then(|l| l.edges)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(|edge| (edge.node.name, edge.size))
+                    .collect();
+                repos.push(PortfolioRepo {
+                    name_with_owner: repo.name_with_owner,
+                    url: repo.url,
+                    description: repo.description,
+                    stars: repo.stargazer_count.unwrap_or(0),
+                    owned,
+                    languages,
+                });
+                n_repos += 1;
+            }
+
+            if n_repos >= PORTFOLIO_MAX_REPOS {
+                break;
+            }
+            match connection.page_info.and_then(|p| p.next_cursor()) {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+    }
+
+    Ok(LanguagePortfolio::from_repos(login, repos))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OwnerRepo {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_archived: bool,
+    pub topics: Vec<String>,
+}
+
+// An org or user lists at most this many repos, most recently pushed first.
+const OWNER_MAX_REPOS: usize = 500;
+
+const OWNER_REPOS_QUERY: &str = r#"
+    query ($login: String!, $cursor: String) {
+        repositoryOwner(login: $login) {
+            repositories(first: 100, after: $cursor, isFork: false, orderBy: {field: PUSHED_AT, direction: DESC}) {
+                pageInfo {
+                    endCursor
+                    hasNextPage
+                }
+                nodes {
+                    name
+                    description
+                    isArchived
+                    repositoryTopics(first: 20) {
+                        nodes {
+                            topic {
+                                name
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    "#;
+
+// Lists the public repos of an org or user, forks left out. Fails when GitHub doesn't
+// know the owner.
+pub async fn get_owner_repos(
+    github: &dyn GitHubTransport,
+    owner: &str,
+) -> ResearchResult<Vec<OwnerRepo>> {
+    #[derive(Debug, Deserialize)]
+    struct Data {
+        #[serde(rename = "repositoryOwner")]
+        repository_owner: Option<Owner>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Owner {
+        repositories: Option<Connection<Repo>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Repo {
+        name: String,
+        description: Option<String>,
+        #[serde(rename = "isArchived")]
+        is_archived: Option<bool>,
+        #[serde(rename = "repositoryTopics")]
+        repository_topics: Option<Connection<TopicNode>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TopicNode {
+        topic: TopicName,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TopicName {
+        name: String,
+    }
+
+    let base_url = github.graphql_url();
+    let mut repos = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let query = GraphQlQuery::new(OWNER_REPOS_QUERY)
+            .variable("login", owner)
+            .variable("cursor", &cursor);
+
+        let connection = match github_graphql::<Data>(github, &query).await {
+            Err(e) => {
+                log::error!(
+                    "Failed to get the repos of {} from {}: {}",
+                    owner,
+                    base_url,
+                    e
+                );
+                if repos.is_empty() {
+                    return Err(e);
+                }
+                break;
+            }
+            Ok(results) => match results.data.repository_owner {
+                Some(repository_owner) => repository_owner.repositories,
+                None => return Err(ResearchError::NotFound(format!("owner {owner}"))),
+            },
+        };
+        let Some(connection) = connection else {
+            break;
+        };
+
+        for repo in connection.nodes.unwrap_or_default().into_iter().flatten() {
+            let topics = repo
+                .repository_topics
+                .and_then(|t| t.nodes)
+                .unwrap_or_default()
+                .into_iter()
+                .flatten()
+                .map(|node| node.topic.name)
+                .collect();
+            repos.push(OwnerRepo {
+                name: repo.name,
+                description: repo.description,
+                is_archived: repo.is_archived.unwrap_or(false),
+                topics,
+            });
+        }
+
+        if repos.len() >= OWNER_MAX_REPOS {
+            break;
+        }
+        match connection.page_info.and_then(|p| p.next_cursor()) {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    Ok(repos)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContributionItem {
+    pub title: String,
+    pub url: String,
+    // `OPEN`, `CLOSED` or `MERGED` for issues and pull requests, the review state
+    // (`APPROVED`, `CHANGES_REQUESTED`, `COMMENTED`, ...) for reviews.
+    pub state: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// What a user did in one repo. The counts are GitHub's totals; the item lists are cut at
+// 50 per repo and kind for every year of the window.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RepoContributions {
+    pub name_with_owner: String,
+    pub url: String,
+    pub commits: u64,
+    pub issue_count: u64,
+    pub issues: Vec<ContributionItem>,
+    pub pull_request_count: u64,
+    pub pull_requests: Vec<ContributionItem>,
+    pub review_count: u64,
+    pub reviews: Vec<ContributionItem>,
+}
+
+impl RepoContributions {
+    pub fn total(&self) -> u64 {
+        self.commits + self.issue_count + self.pull_request_count + self.review_count
+    }
+}
+
+// A user's contributions to public repos in a time window, busiest repo first.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ContributionHistory {
+    pub login: String,
+    pub window: Option<TimeWindow>,
+    pub repos: Vec<RepoContributions>,
+    // Contributions to private repos, which GitHub only counts.
+    pub restricted_contributions: u64,
+}
+
+impl ContributionHistory {
+    pub fn is_empty(&self) -> bool {
+        self.repos.is_empty()
+    }
+
+    // Per repo: the counts, then the issues, pull requests and reviews, as text for prompts.
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("CONTRIBUTIONS \n");
+        for repo in &self.repos {
+            out.push_str(&format!(
+                "Repo: {}, Commits: {}, Issues: {}, Pull requests: {}, Reviews: {}\n",
+                repo.name_with_owner,
+                repo.commits,
+                repo.issue_count,
+                repo.pull_request_count,
+                repo.review_count
+            ));
+            for (kind, items) in [
+                ("Issue", &repo.issues),
+                ("Pull request", &repo.pull_requests),
+                ("Review of", &repo.reviews),
+            ] {
+                for item in items {
+                    out.push_str(&format!(
+                        "    {kind}: {} ({}, {})\n",
+                        item.title,
+                        item.state.to_lowercase(),
+                        item.occurred_at.date_naive()
+                    ));
+                }
+            }
+        }
+        out
+    }
+}
+
+const CONTRIBUTIONS_QUERY: &str = r#"
+    query ($login: String!, $from: DateTime!, $to: DateTime!) {
+        user(login: $login) {
+            contributionsCollection(from: $from, to: $to) {
+                restrictedContributionsCount
+                commitContributionsByRepository(maxRepositories: 100) {
+                    repository {
+                        nameWithOwner
+                        url
+                    }
+                    contributions {
+                        totalCount
+                    }
+                }
+                issueContributionsByRepository(maxRepositories: 100) {
+                    repository {
+                        nameWithOwner
+                        url
+                    }
+                    contributions(first: 50) {
+                        totalCount
+                        nodes {
+                            occurredAt
+                            issue {
+                                title
+                                url
+                                state
+                            }
+                        }
+                    }
+                }
+                pullRequestContributionsByRepository(maxRepositories: 100) {
+                    repository {
+                        nameWithOwner
+                        url
+                    }
+                    contributions(first: 50) {
+                        totalCount
+                        nodes {
+                            occurredAt
+                            pullRequest {
+                                title
+                                url
+                                state
+                            }
+                        }
+                    }
+                }
+                pullRequestReviewContributionsByRepository(maxRepositories: 100) {
+                    repository {
+                        nameWithOwner
+                        url
+                    }
+                    contributions(first: 50) {
+                        totalCount
+                        nodes {
+                            occurredAt
+                            pullRequestReview {
+                                state
+                            }
+                            pullRequest {
+                                title
+                                url
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    "#;
+
+// The user's commit, issue, pull request and review contributions across all public repos
+// in `window`, grouped by repo. GitHub answers for at most a year at a time, so longer
+// windows are fetched a year after another.
+pub async fn get_contribution_history(
+    github: &dyn GitHubTransport,
+    login: &str,
+    window: &TimeWindow,
+) -> ResearchResult<ContributionHistory> {
+    #[derive(Debug, Deserialize)]
+    struct Data {
+        user: Option<UserContributions>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct UserContributions {
+        #[serde(rename = "contributionsCollection")]
+        collection: Collection,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Collection {
+        #[serde(rename = "restrictedContributionsCount")]
+        restricted_contributions_count: u64,
+        #[serde(rename = "commitContributionsByRepository")]
+        commits: Vec<ByRepo<serde::de::IgnoredAny>>,
+        #[serde(rename = "issueContributionsByRepository")]
+        issues: Vec<ByRepo<IssueContribution>>,
+        #[serde(rename = "pullRequestContributionsByRepository")]
+        pull_requests: Vec<ByRepo<PullRequestContribution>>,
+        #[serde(rename = "pullRequestReviewContributionsByRepository")]
+        reviews: Vec<ByRepo<ReviewContribution>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ByRepo<T> {
+        repository: RepoRef,
+        contributions: Contributions<T>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct RepoRef {
+        #[serde(rename = "nameWithOwner")]
+        name_with_owner: String,
+        url: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Contributions<T> {
+        #[serde(rename = "totalCount")]
+        total_count: u64,
+        nodes: Option<Vec<Option<T>>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        title: String,
+        url: String,
+        state: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct IssueContribution {
+        #[serde(rename = "occurredAt")]
+        occurred_at: DateTime<Utc>,
+        issue: Item,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct PullRequestContribution {
+        #[serde(rename = "occurredAt")]
+        occurred_at: DateTime<Utc>,
+        #[serde(rename = "pullRequest")]
+        pull_request: Item,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ReviewContribution {
+        #[serde(rename = "occurredAt")]
+        occurred_at: DateTime<Utc>,
+        #[serde(rename = "pullRequestReview")]
+        pull_request_review: Option<ReviewState>,
+        #[serde(rename = "pullRequest")]
+        pull_request: Item,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ReviewState {
+        state: String,
+    }
+
+    fn item(item: Item, state: Option<String>, occurred_at: DateTime<Utc>) -> ContributionItem {
+        ContributionItem {
+            title: item.title,
+            url: item.url,
+            state: state.or(item.state).unwrap_or_default(),
+            occurred_at,
+        }
+    }
+
+    fn repo_entry(
+        by_repo: &mut std::collections::HashMap<String, RepoContributions>,
+        repo: RepoRef,
+    ) -> &mut RepoContributions {
+        by_repo
+            .entry(repo.name_with_owner.clone())
+            .or_insert_with(|| RepoContributions {
+                name_with_owner: repo.name_with_owner,
+                url: repo.url,
+                ..Default::default()
+            })
+    }
+
+    let base_url = github.graphql_url();
+    let mut by_repo = std::collections::HashMap::<String, RepoContributions>::new();
+    let mut restricted_contributions = 0;
+    let mut since = window.since;
+    while since < window.until {
+        let until = std::cmp::min(since + chrono::Duration::days(365), window.until);
+        let query = GraphQlQuery::new(CONTRIBUTIONS_QUERY)
+            .variable("login", login)
+            .variable("from", format_iso(&since))
+            .variable("to", format_iso(&until));
+
+        let collection = match github_graphql::<Data>(github, &query).await {
+            Err(e) => {
+                log::error!(
+                    "Failed to get the contributions of {} from {}: {}",
+                    login,
+                    base_url,
+                    e
+                );
+                return Err(e);
+            }
+            Ok(results) => match results.data.user {
+                Some(user) => user.collection,
+                None => return Err(ResearchError::NotFound(format!("user {login}"))),
+            },
+        };
+        restricted_contributions += collection.restricted_contributions_count;
+
+        for by in collection.commits {
+            repo_entry(&mut by_repo, by.repository).commits += by.contributions.total_count;
+        }
+        for by in collection.issues {
+            let entry = repo_entry(&mut by_repo, by.repository);
+            entry.issue_count += by.contributions.total_count;
+            entry.issues.extend(
+                by.contributions
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(|c| item(c.issue, None, c.occurred_at)),
+            );
+        }
+        for by in collection.pull_requests {
+            let entry = repo_entry(&mut by_repo, by.repository);
+            entry.pull_request_count += by.contributions.total_count;
+            entry.pull_requests.extend(
+                by.contributions
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(|c| item(c.pull_request, None, c.occurred_at)),
+            );
+        }
+        for by in collection.reviews {
+            let entry = repo_entry(&mut by_repo, by.repository);
+            entry.review_count += by.contributions.total_count;
+            entry.reviews.extend(
+                by.contributions
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(|c| {
+                        let state = c.pull_request_review.map(|r| r.state);
+                        item(c.pull_request, state, c.occurred_at)
+                    }),
+            );
+        }
+        since = until;
+    }
 
-                    out.push_str(&temp);
-                }
+    let mut repos = by_repo.into_values().collect::<Vec<_>>();
+    repos.sort_by_key(|repo| std::cmp::Reverse(repo.total()));
+    Ok(ContributionHistory {
+        login: login.to_string(),
+        window: Some(*window),
+        repos,
+        restricted_contributions,
+    })
+}
+
+// One hit of `search_issue_hits`, long bodies are shortened only in the text form.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IssueHit {
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub created_at: NaiveDate,
+    pub author: Option<String>,
+    pub assignees: Vec<String>,
+    pub state: Option<String>,
+    pub body: Option<String>,
+    pub author_association: Option<String>,
+}
 
-                log::info!("Found {} repositories", repos.data.search.nodes.len());
+impl IssueHit {
+    pub fn to_line(&self) -> String {
+        let title_str = match &self.title {
+            Some(title) => format!("Title: {},", title),
+            None => String::new(),
+        };
+        let url_str = match &self.url {
+            Some(u) => format!("Url: {}", u),
+            None => String::new(),
+        };
+        let author_str = match &self.author {
+            Some(auth) => format!("Author: {},", auth),
+            None => String::new(),
+        };
+        let assignees_str = match self.assignees.is_empty() {
+            true => String::new(),
+            false => format!("Assignees: {},", self.assignees.join(", ")),
+        };
+        let state_str = match &self.state {
+            Some(s) => format!("State: {},", s),
+            None => String::new(),
+        };
+        let body_str = match &self.body {
+            Some(body_text) if body_text.len() > 180 => {
+                let truncated_body = body_text
+                    .chars()
+                    .take(100)
+                    .chain(body_text.chars().skip(body_text.chars().count() - 80))
+                    .collect::<String>();
+
+                format!("Body: {}", truncated_body)
             }
-        },
+            Some(body_text) => format!("Body: {},", body_text),
+            None => String::new(),
+        };
+        let assoc_str = match &self.author_association {
+            Some(association) => format!("Author Association: {}", association),
+            None => String::new(),
+        };
+        let date = self.created_at;
+
+        format!(
+            "{title_str} {url_str} Created At: {date} {author_str} {assignees_str}  {state_str} {body_str} {assoc_str}"
+        )
+    }
+}
+
+pub async fn search_issue(
+    github: &dyn GitHubTransport,
+    search_query: &str,
+) -> ResearchResult<String> {
+    let mut out = String::from("ISSUES \n");
+    for hit in search_issue_hits(github, search_query).await? {
+        out.push_str(&hit.to_line());
+        out.push('\n');
     }
-    Some(out)
+    Ok(out)
 }
 
-pub async fn search_issue(github_token: &str, search_query: &str) -> Option<String> {
+pub async fn search_issue_hits(
+    github: &dyn GitHubTransport,
+    search_query: &str,
+) -> ResearchResult<Vec<IssueHit>> {
     #[derive(Debug, Deserialize, Clone)]
     pub struct User {
         login: Option<String>,
@@ -775,7 +2212,6 @@ pub async fn search_issue(github_token: &str, search_query: &str) -> Option<Stri
     #[derive(Debug, Deserialize, Clone)]
     struct Issue {
         url: Option<String>,
-        number: Option<u64>,
         state: Option<String>,
         title: Option<String>,
         body: Option<String>,
@@ -785,8 +2221,6 @@ pub async fn search_issue(github_token: &str, search_query: &str) -> Option<Stri
         author_association: Option<String>,
         #[serde(rename = "createdAt")]
         created_at: Option<DateTime<Utc>>,
-        #[serde(rename = "updatedAt")]
-        updated_at: Option<DateTime<Utc>>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -814,205 +2248,205 @@ pub async fn search_issue(github_token: &str, search_query: &str) -> Option<Stri
         search: Option<SearchResult>,
     }
 
-    #[derive(Debug, Deserialize)]
-    struct IssueRoot {
-        data: Option<IssueSearch>,
-    }
-
-    let base_url = "https://api.github.com/graphql";
-    let mut out = String::from("ISSUES \n");
+    let base_url = github.graphql_url();
+    let mut hits = vec![];
 
-    let mut cursor = None;
+    let max_results = max_search_results();
+    let mut n_issues = 0;
+    let mut cursor: Option<String> = None;
 
     loop {
-        let query = format!(
+        let query = GraphQlQuery::new(
             r#"
-            query {{
-                search(query: "{search_query}", type: ISSUE, first: 100{after}) {{
-                    edges {{
-                        node {{
-                            ... on Issue {{
+            query ($searchQuery: String!, $cursor: String) {
+                rateLimit {
+                    cost
+                    remaining
+                    resetAt
+                }
+                search(query: $searchQuery, type: ISSUE, first: 100, after: $cursor) {
+                    edges {
+                        node {
+                            ... on Issue {
                                 url
                                 number
                                 state
                                 title
                                 body
-                                author {{
+                                author {
                                     login
-                                }}
-                                assignees(first: 100) {{
-                                    edges {{
-                                        node {{
+                                }
+                                assignees(first: 100) {
+                                    edges {
+                                        node {
                                             login
-                                        }}
-                                    }}
-                                }}
+                                        }
+                                    }
+                                }
                                 authorAssociation
                                 createdAt
                                 updatedAt
-                            }}
-                        }}
-                    }}
-                    pageInfo {{
+                            }
+                        }
+                    }
+                    pageInfo {
                         endCursor
                         hasNextPage
-                      }}
-                }}
-            }}
+                      }
+                }
+            }
             "#,
-            search_query = search_query,
-            after = cursor
-                .as_ref()
-                .map_or(String::new(), |c| format!(r#", after: "{}""#, c))
-        );
+        )
+        .variable("searchQuery", search_query)
+        .variable("cursor", &cursor);
 
-        match github_http_post(&github_token, base_url, &query).await {
-            None => {
-                log::error!("Failed to send the request: {}", base_url);
+        match github_graphql::<IssueSearch>(github, &query).await {
+            Err(e) => {
+                log::error!("Failed to get issues from {}: {}", base_url, e);
+                if cursor.is_none() {
+                    return Err(e);
+                }
                 break;
             }
-            Some(response) => match serde_json::from_slice::<IssueRoot>(response.as_slice()) {
-                Err(e) => {
-                    log::error!("Failed to parse the response: {}", e);
-                    break;
-                }
-                Ok(results) => {
-                    if let Some(search) = &results.data.as_ref().and_then(|d| d.search.as_ref()) {
-                        if let Some(edges) = &search.edges {
-                            for edge in edges.iter().filter_map(|e| e.as_ref()) {
-                                if let Some(issue) = &edge.node {
-                                    let date = match issue.created_at {
-                                        Some(date) => date.date_naive().to_string(),
-                                        None => {
-                                            continue;
-                                        }
-                                    };
-                                    let title_str = match &issue.title {
-                                        Some(title) => format!("Title: {},", title),
-                                        None => String::new(),
-                                    };
-                                    let url_str = match &issue.url {
-                                        Some(u) => format!("Url: {}", u),
-                                        None => String::new(),
-                                    };
-
-                                    let author_str =
-                                        match issue.clone().author.and_then(|a| a.login) {
-                                            Some(auth) => format!("Author: {},", auth),
-                                            None => String::new(),
-                                        };
-
-                                    let assignees_str = {
-                                        let assignee_names = issue
-                                            .assignees
-                                            .as_ref()
-                                            .and_then(|e| e.edges.as_ref())
-                                            .map_or(Vec::new(), |assignee_edges| {
-                                                assignee_edges
-                                                    .iter()
-                                                    .filter_map(|edge| {
-                                                        edge.as_ref().and_then(|actual_edge| {
-                                                            actual_edge.node.as_ref().and_then(
-                                                                |user| {
-                                                                    user.login.as_ref().map(
-                                                                        |login_str| {
-                                                                            login_str.as_str()
-                                                                        },
-                                                                    )
-                                                                },
-                                                            )
-                                                        })
-                                                    })
-                                                    .collect::<Vec<&str>>()
-                                            });
-
-                                        if !assignee_names.is_empty() {
-                                            format!("Assignees: {},", assignee_names.join(", "))
-                                        } else {
-                                            String::new()
-                                        }
-                                    };
-
-                                    let state_str = match &issue.state {
-                                        Some(s) => format!("State: {},", s),
-                                        None => String::new(),
-                                    };
-
-                                    let body_str = match &issue.body {
-                                        Some(body_text) if body_text.len() > 180 => {
-                                            let truncated_body = body_text
-                                                .chars()
-                                                .take(100)
-                                                .chain(
-                                                    body_text
-                                                        .chars()
-                                                        .skip(body_text.chars().count() - 80),
-                                                )
-                                                .collect::<String>();
-
-                                            format!("Body: {}", truncated_body)
-                                        }
-                                        Some(body_text) => format!("Body: {},", body_text),
-                                        None => String::new(),
-                                    };
-
-                                    let assoc_str = match &issue.author_association {
-                                        Some(association) => {
-                                            format!("Author Association: {}", association)
-                                        }
-                                        None => String::new(),
-                                    };
-
-                                    let temp = format!(
-                                            "{title_str} {url_str} Created At: {date} {author_str} {assignees_str}  {state_str} {body_str} {assoc_str}"
-                                        );
+            Ok(results) => {
+                if let Some(search) = &results.data.search.as_ref() {
+                    if let Some(edges) = &search.edges {
+                        for edge in edges.iter().filter_map(|e| e.as_ref()) {
+                            if let Some(issue) = &edge.node {
+                                let created_at = match issue.created_at {
+                                    Some(date) => date.date_naive(),
+                                    None => {
+                                        continue;
+                                    }
+                                };
+                                let assignees = issue
+                                    .assignees
+                                    .as_ref()
+                                    .and_then(|e| e.edges.as_ref())
+                                    .map_or(Vec::new(), |assignee_edges| {
+                                        assignee_edges
+                                            .iter()
+                                            .flatten()
+                                            .filter_map(|edge| edge.node.as_ref())
+                                            .filter_map(|user| user.login.clone())
+                                            .collect()
+                                    });
 
-                                    out.push_str(&temp);
-                                    out.push_str("\n");
-                                } else {
-                                    continue;
-                                }
+                                hits.push(IssueHit {
+                                    title: issue.title.clone(),
+                                    url: issue.url.clone(),
+                                    created_at,
+                                    author: issue.author.as_ref().and_then(|a| a.login.clone()),
+                                    assignees,
+                                    state: issue.state.clone(),
+                                    body: issue.body.clone(),
+                                    author_association: issue.author_association.clone(),
+                                });
+                                n_issues += 1;
+                            } else {
+                                continue;
                             }
                         }
+                    }
 
-                        if let Some(page_info) = &search.page_info {
-                            if let Some(has_next_page) = page_info.has_next_page {
-                                if has_next_page {
-                                    match &page_info.end_cursor {
-                                        Some(end_cursor) => {
-                                            cursor = Some(end_cursor.clone());
-                                            log::info!(
-                                                    "Fetched a page, moving to next page with cursor: {}",
-                                                    end_cursor
-                                                );
-                                            continue;
-                                        }
-                                        None => {
-                                            log::error!(
+                    if n_issues >= max_results {
+                        break;
+                    }
+                    if let Some(page_info) = &search.page_info {
+                        if let Some(has_next_page) = page_info.has_next_page {
+                            if has_next_page {
+                                match &page_info.end_cursor {
+                                    Some(end_cursor) => {
+                                        cursor = Some(end_cursor.clone());
+                                        log::info!(
+                                            "Fetched a page, moving to next page with cursor: {}",
+                                            end_cursor
+                                        );
+                                        continue;
+                                    }
+                                    None => {
+                                        log::error!(
                                                     "Warning: hasNextPage is true, but endCursor is None. This might result in missing data."
                                                 );
-                                            break;
-                                        }
+                                        break;
                                     }
                                 }
                             }
                         }
                     }
-                    break;
                 }
-            },
+                break;
+            }
         }
     }
 
-    Some(out)
+    Ok(hits)
 }
 
-pub async fn search_repository(github_token: &str, search_query: &str) -> Option<String> {
-    #[derive(Debug, Deserialize)]
-    struct Payload {
-        data: Option<Data>,
+// One hit of `search_repository_hits`, long descriptions are shortened only in the text form.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RepositoryHit {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub created_at: NaiveDate,
+    pub stars: Option<u32>,
+    pub forks: Option<u32>,
+}
+
+impl RepositoryHit {
+    pub fn to_line(&self) -> String {
+        let name_str = match &self.name {
+            Some(name) => format!("Name: {name},"),
+            None => String::new(),
+        };
+        let desc_str = match &self.description {
+            Some(desc) if desc.len() > 300 => {
+                let truncated_desc = desc
+                    .chars()
+                    .take(180)
+                    .chain(desc.chars().skip(desc.chars().count() - 120))
+                    .collect::<String>();
+
+                format!("Description: {truncated_desc}")
+            }
+            Some(desc) => format!("Description: {desc},"),
+            None => String::new(),
+        };
+        let url_str = match &self.url {
+            Some(url) => format!("Url: {url}"),
+            None => String::new(),
+        };
+        let stars_str = match &self.stars {
+            Some(stars) => format!("Stars: {stars},"),
+            None => String::new(),
+        };
+        let forks_str = match &self.forks {
+            Some(fork_count) => format!("Forks: {fork_count}"),
+            None => String::new(),
+        };
+        let date_str = self.created_at;
+
+        format!("{name_str} {desc_str} {url_str} Created At: {date_str} {stars_str} {forks_str}")
+    }
+}
+
+pub async fn search_repository(
+    github: &dyn GitHubTransport,
+    search_query: &str,
+) -> ResearchResult<String> {
+    let mut out = String::from("REPOSITORY \n");
+    for hit in search_repository_hits(github, search_query).await? {
+        out.push_str(&hit.to_line());
+        out.push('\n');
     }
+    Ok(out)
+}
 
+pub async fn search_repository_hits(
+    github: &dyn GitHubTransport,
+    search_query: &str,
+) -> ResearchResult<Vec<RepositoryHit>> {
     #[derive(Debug, Deserialize)]
     struct Data {
         search: Option<Search>,
@@ -1056,380 +2490,548 @@ pub async fn search_repository(github_token: &str, search_query: &str) -> Option
         has_next_page: Option<bool>,
     }
 
-    let base_url = "https://api.github.com/graphql";
-    let mut out = String::from("REPOSITORY \n");
+    let base_url = github.graphql_url();
+    let mut hits = vec![];
 
+    let max_results = max_search_results();
+    let mut n_repos = 0;
     let mut cursor: Option<String> = None;
 
     loop {
-        let query = format!(
+        let query = GraphQlQuery::new(
             r#"
-                query {{
-                    search(query: "{search_query}", type: REPOSITORY, first: 100{after}) {{
-                        edges {{
-                            node {{
-                                ... on Repository {{
+                query ($searchQuery: String!, $cursor: String) {
+                    rateLimit {
+                        cost
+                        remaining
+                        resetAt
+                    }
+                    search(query: $searchQuery, type: REPOSITORY, first: 100, after: $cursor) {
+                        edges {
+                            node {
+                                ... on Repository {
                                     name
                                     description
                                     url
                                     createdAt
-                                    stargazers {{
+                                    stargazers {
                                       totalCount
-                                    }}
+                                    }
                                     forkCount
-                                }}
-                            }}
-                        }}
-                        pageInfo {{
+                                }
+                            }
+                        }
+                        pageInfo {
                             endCursor
                             hasNextPage
-                        }}
-                    }}
-                }}
+                        }
+                    }
+                }
             "#,
-            search_query = search_query,
-            after = cursor
-                .as_ref()
-                .map_or(String::new(), |c| format!(r#", after: "{}""#, c))
-        );
+        )
+        .variable("searchQuery", search_query)
+        .variable("cursor", &cursor);
 
-        match github_http_post(&github_token, base_url, &query).await {
-            None => {
-                log::error!(
-                    "Failed to send the request to get RepositoryRoot: {}",
-                    base_url
-                );
-                return None;
-            }
-            Some(response) => match serde_json::from_slice::<Payload>(response.as_slice()) {
-                Err(e) => {
-                    log::error!("Failed to parse the response for RepositoryRoot: {}", e);
-                    return None;
+        match github_graphql::<Data>(github, &query).await {
+            Err(e) => {
+                log::error!("Failed to get repositories from {}: {}", base_url, e);
+                if cursor.is_none() {
+                    return Err(e);
                 }
-                Ok(payload) => {
-                    if let Some(data) = &payload.data {
-                        if let Some(search) = &data.search {
-                            if let Some(edges) = &search.edges {
-                                for edge_option in edges {
-                                    if let Some(edge) = edge_option {
-                                        if let Some(repo) = &edge.node {
-                                            let date_str = match &repo.created_at {
-                                                Some(date) => date.date_naive().to_string(),
-                                                None => {
-                                                    continue;
-                                                }
-                                            };
-
-                                            let name_str = match &repo.name {
-                                                Some(name) => format!("Name: {name},"),
-                                                None => String::new(),
-                                            };
-
-                                            let desc_str = match &repo.description {
-                                                Some(desc) if desc.len() > 300 => {
-                                                    let truncated_desc = desc
-                                                        .chars()
-                                                        .take(180)
-                                                        .chain(
-                                                            desc.chars()
-                                                                .skip(desc.chars().count() - 120),
-                                                        )
-                                                        .collect::<String>();
-
-                                                    format!("Description: {truncated_desc}")
-                                                }
-                                                Some(desc) => format!("Description: {desc},"),
-                                                None => String::new(),
-                                            };
-
-                                            let url_str = match &repo.url {
-                                                Some(url) => format!("Url: {url}"),
-                                                None => String::new(),
-                                            };
-
-                                            let stars_str = match &repo.stargazers {
-                                                Some(sg) => format!(
-                                                    "Stars: {},",
-                                                    sg.total_count.unwrap_or(0)
-                                                ),
-                                                None => String::new(),
-                                            };
-
-                                            let forks_str = match &repo.fork_count {
-                                                Some(fork_count) => format!("Forks: {fork_count}"),
-                                                None => String::new(),
-                                            };
-
-                                            out.push_str(
-                                                    &format!(
-                                                        "{name_str} {desc_str} {url_str} Created At: {date_str} {stars_str} {forks_str}\n"
-                                                    )
-                                                );
+                break;
+            }
+            Ok(payload) => {
+                match &payload.data.search {
+                    Some(search) => {
+                        if let Some(edges) = &search.edges {
+                            for edge in edges.iter().flatten() {
+                                if let Some(repo) = &edge.node {
+                                    let created_at = match &repo.created_at {
+                                        Some(date) => date.date_naive(),
+                                        None => {
+                                            continue;
                                         }
-                                    }
-                                }
-                            }
-                            if let Some(page_info) = &search.page_info {
-                                if page_info.has_next_page.unwrap_or(false) {
-                                    cursor = page_info.end_cursor.clone();
-                                } else {
-                                    break;
+                                    };
+
+                                    hits.push(RepositoryHit {
+                                        name: repo.name.clone(),
+                                        description: repo.description.clone(),
+                                        url: repo.url.clone(),
+                                        created_at,
+                                        stars: repo
+                                            .stargazers
+                                            .as_ref()
+                                            .map(|sg| sg.total_count.unwrap_or(0)),
+                                        forks: repo.fork_count,
+                                    });
+                                    n_repos += 1;
                                 }
                             }
                         }
+                        if n_repos >= max_results {
+                            break;
+                        }
+                        // A missing cursor would restart from the first page forever.
+                        match &search.page_info {
+                            Some(PageInfo {
+                                has_next_page: Some(true),
+                                end_cursor: Some(end_cursor),
+                            }) => cursor = Some(end_cursor.clone()),
+                            _ => break,
+                        }
                     }
+                    None => break,
                 }
-            },
+            }
         };
     }
 
-    Some(out)
+    Ok(hits)
 }
 
-pub async fn search_discussions_integrated(
-    github_token: &str,
-    search_query: &str,
-    target_person: &Option<String>,
-) -> Option<(String, Vec<GitMemory>)> {
-    #[derive(Debug, Deserialize)]
-    struct DiscussionRoot {
-        data: Option<Data>,
-    }
+// Caps every paginated search at `github_max_search_results` hits, 500 by default.
+fn max_search_results() -> usize {
+    std::env::var("github_max_search_results")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(500)
+}
 
-    #[derive(Debug, Deserialize)]
-    struct Data {
-        search: Option<Search>,
-    }
+// Caps comments plus replies fetched per discussion at `discussion_max_comments`, 300 by default.
+fn max_discussion_comments() -> usize {
+    std::env::var("discussion_max_comments")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(300)
+}
 
-    #[derive(Debug, Deserialize)]
-    struct Search {
-        edges: Option<Vec<Option<Edge>>>,
+#[derive(Debug, Deserialize)]
+struct PageCursor {
+    #[serde(rename = "endCursor")]
+    end_cursor: Option<String>,
+    #[serde(rename = "hasNextPage")]
+    has_next_page: Option<bool>,
+}
+
+impl PageCursor {
+    fn next_cursor(&self) -> Option<String> {
+        match self.has_next_page {
+            Some(true) => self.end_cursor.clone(),
+            _ => None,
+        }
     }
+}
 
-    #[derive(Debug, Deserialize)]
-    struct Edge {
-        node: Option<Discussion>,
+#[derive(Debug, Deserialize)]
+struct Connection<T> {
+    #[serde(rename = "pageInfo")]
+    page_info: Option<PageCursor>,
+    nodes: Option<Vec<Option<T>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeData<T> {
+    node: Option<NodeConnection<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeConnection<T> {
+    #[serde(alias = "comments", alias = "replies", alias = "reviews")]
+    connection: Option<Connection<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscussionAuthor {
+    login: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscussionComment {
+    id: Option<String>,
+    author: Option<DiscussionAuthor>,
+    body: Option<String>,
+    replies: Option<Connection<DiscussionReply>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscussionReply {
+    author: Option<DiscussionAuthor>,
+    body: Option<String>,
+}
+
+const DISCUSSION_COMMENTS_QUERY: &str = r#"
+    query ($id: ID!, $cursor: String) {
+        node(id: $id) {
+            ... on Discussion {
+                comments(first: 100, after: $cursor) {
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                    nodes {
+                        id
+                        author {
+                            login
+                        }
+                        body
+                        replies(first: 10) {
+                            pageInfo {
+                                endCursor
+                                hasNextPage
+                            }
+                            nodes {
+                                author {
+                                    login
+                                }
+                                body
+                            }
+                        }
+                    }
+                }
+            }
+        }
     }
+"#;
 
-    #[derive(Debug, Deserialize)]
-    struct Discussion {
-        title: Option<String>,
-        url: Option<String>,
-        html_url: Option<String>,
-        author: Option<Author>,
-        body: Option<String>,
-        comments: Option<Comments>,
-        #[serde(rename = "createdAt")]
-        created_at: DateTime<Utc>,
-        #[serde(rename = "upvoteCount")]
-        upvote_count: Option<u32>,
+const COMMENT_REPLIES_QUERY: &str = r#"
+    query ($id: ID!, $cursor: String) {
+        node(id: $id) {
+            ... on DiscussionComment {
+                replies(first: 100, after: $cursor) {
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                    nodes {
+                        author {
+                            login
+                        }
+                        body
+                    }
+                }
+            }
+        }
     }
+"#;
+
+// Collects `first_page` and every following page of a connection hanging off
+// the node `node_id`, stopping after `cap` items. A failing page keeps what was
+// already fetched.
+async fn follow_connection<T: serde::de::DeserializeOwned>(
+    github: &dyn GitHubTransport,
+    query: &'static str,
+    node_id: &str,
+    first_page: Connection<T>,
+    cap: usize,
+) -> Vec<T> {
+    let mut items = Vec::new();
+    let mut page = first_page;
 
-    #[derive(Debug, Deserialize)]
-    struct Comments {
-        edges: Option<Vec<Option<CommentEdge>>>,
+    loop {
+        items.extend(page.nodes.into_iter().flatten().flatten());
+        if items.len() >= cap {
+            items.truncate(cap);
+            break;
+        }
+        let cursor = match page.page_info.and_then(|p| p.next_cursor()) {
+            Some(cursor) => cursor,
+            None => break,
+        };
+        let next_query = GraphQlQuery::new(query)
+            .variable("id", node_id)
+            .variable("cursor", &cursor);
+        match github_graphql::<NodeData<T>>(github, &next_query).await {
+            Ok(res) => match res.data.node.and_then(|n| n.connection) {
+                Some(next) => page = next,
+                None => break,
+            },
+            Err(e) => {
+                log::error!("Failed to get page after {} of {}: {}", cursor, node_id, e);
+                break;
+            }
+        }
     }
+    items
+}
 
+pub async fn search_discussions_integrated(
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
+    search_query: &str,
+    target_person: &Option<String>,
+) -> ResearchResult<(String, Vec<GitMemory>)> {
     #[derive(Debug, Deserialize)]
-    struct CommentEdge {
-        node: Option<CommentNode>,
+    struct Data {
+        search: Option<Search>,
     }
 
     #[derive(Debug, Deserialize)]
-    struct CommentNode {
-        author: Option<Author>,
-        body: Option<String>,
+    struct Search {
+        nodes: Option<Vec<Option<Discussion>>>,
+        #[serde(rename = "pageInfo")]
+        page_info: Option<PageCursor>,
     }
 
     #[derive(Debug, Deserialize)]
-    struct Author {
-        login: Option<String>,
+    struct Discussion {
+        id: Option<String>,
+        title: Option<String>,
+        url: Option<String>,
+        author: Option<DiscussionAuthor>,
+        body: Option<String>,
+        comments: Option<Connection<DiscussionComment>>,
+        #[serde(rename = "createdAt")]
+        created_at: DateTime<Utc>,
+        #[serde(rename = "upvoteCount")]
+        upvote_count: Option<u32>,
     }
 
-    let openai = OpenAIFlows::new();
+    let base_url = github.graphql_url();
+    let max_results = max_search_results();
+    let max_comments = max_discussion_comments();
 
-    let base_url = "https://api.github.com/graphql";
-
-    let query = format!(
-        r#"
-        query {{
-            search(query: "{search_query}", type: DISCUSSION, first: 100) {{
-                edges {{
-                    node {{
-                        ... on Discussion {{
+    // Nested `first` arguments multiply against GitHub's node limit, so the
+    // search pages are smaller than elsewhere and the rest is fetched per node.
+    let mut discussions = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let query = GraphQlQuery::new(
+            r#"
+            query ($searchQuery: String!, $cursor: String) {
+                rateLimit {
+                    cost
+                    remaining
+                    resetAt
+                }
+                search(query: $searchQuery, type: DISCUSSION, first: 50, after: $cursor) {
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                    nodes {
+                        ... on Discussion {
+                            id
                             title
                             url
-                            html_url
                             body
-                            author {{
+                            author {
                                 login
-                            }}
+                            }
                             createdAt
                             upvoteCount
-                            comments (first: 100) {{
-                                edges {{
-                                    node {{
-                                        author {{
-                                            login
-                                        }}
-                                        body
-                                    }}
-                                }}
-                            }}
-                        }}
-                    }}
-                }}
-            }}
-        }}
-        "#,
-        search_query = search_query
-    );
-    let mut git_mem_vec = Vec::with_capacity(100);
-    let mut text_out = String::from("DISCUSSIONS \n");
+                            comments(first: 50) {
+                                pageInfo {
+                                    endCursor
+                                    hasNextPage
+                                }
+                                nodes {
+                                    id
+                                    author {
+                                        login
+                                    }
+                                    body
+                                    replies(first: 10) {
+                                        pageInfo {
+                                            endCursor
+                                            hasNextPage
+                                        }
+                                        nodes {
+                                            author {
+                                                login
+                                            }
+                                            body
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            "#,
+        )
+        .variable("searchQuery", search_query)
+        .variable("cursor", &cursor);
 
-    match github_http_post(&github_token, base_url, &query).await {
-        None => {
-            log::error!(
-                "Failed to send the request to get DiscussionRoot: {}",
-                base_url
-            );
-            return None;
-        }
-        Some(response) => match serde_json::from_slice::<DiscussionRoot>(&response) {
+        match github_graphql::<Data>(github, &query).await {
             Err(e) => {
-                log::error!("Failed to parse the response for DiscussionRoot: {}", e);
-                return None;
+                log::error!("Failed to get discussions from {}: {}", base_url, e);
+                if cursor.is_none() {
+                    return Err(e);
+                }
+                break;
             }
             Ok(results) => {
-                let empty_str = "".to_string();
-
-                if let Some(search) = results.data?.search {
-                    for edge_option in search.edges?.iter().filter_map(|e| e.as_ref()) {
-                        if let Some(discussion) = &edge_option.node {
-                            let date = discussion.created_at.date_naive();
-                            let title = discussion.title.as_ref().unwrap_or(&empty_str).to_string();
-                            let url = discussion.url.as_ref().unwrap_or(&empty_str).to_string();
-                            let source_url = discussion
-                                .html_url
-                                .as_ref()
-                                .unwrap_or(&empty_str)
-                                .to_string();
-                            let author_login = discussion
-                                .author
-                                .as_ref()
-                                .and_then(|a| a.login.as_ref())
-                                .unwrap_or(&empty_str)
-                                .to_string();
+                let search = match results.data.search {
+                    Some(search) => search,
+                    None => break,
+                };
+                discussions.extend(search.nodes.into_iter().flatten().flatten());
+                if discussions.len() >= max_results {
+                    discussions.truncate(max_results);
+                    break;
+                }
+                match search.page_info.and_then(|p| p.next_cursor()) {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+        }
+    }
 
-                            let upvotes_str = match discussion.upvote_count {
-                                Some(c) if c > 0 => format!("Upvotes: {}", c),
-                                _ => "".to_string(),
-                            };
-                            let body_text = match discussion.body.as_ref() {
-                                Some(text) => squeeze_fit_remove_quoted(&text, "```", 500, 0.6),
-                                None => "".to_string(),
-                            };
-                            let mut disuccsion_texts = format!(
-                                "Title: '{}' Url: '{}' Body: '{}' Created At: {} {} Author: {}\n",
-                                title, url, body_text, date, upvotes_str, author_login
-                            );
+    let mut git_mem_vec = Vec::with_capacity(discussions.len());
+    let mut text_out = String::from("DISCUSSIONS \n");
+    let empty_str = "".to_string();
+
+    for discussion in discussions {
+        let date = discussion.created_at.date_naive();
+        let title = discussion.title.as_ref().unwrap_or(&empty_str).to_string();
+        let url = discussion.url.as_ref().unwrap_or(&empty_str).to_string();
+        // A discussion's GraphQL `url` already points at the web page.
+        let source_url = url.clone();
+        let author_login = discussion
+            .author
+            .as_ref()
+            .and_then(|a| a.login.as_ref())
+            .unwrap_or(&empty_str)
+            .to_string();
+
+        let upvotes_str = match discussion.upvote_count {
+            Some(c) if c > 0 => format!("Upvotes: {}", c),
+            _ => "".to_string(),
+        };
+        let body_text = match discussion.body.as_ref() {
+            Some(text) => squeeze_fit_remove_quoted(text, "```", 500, 0.6),
+            None => "".to_string(),
+        };
+        let mut disuccsion_texts = format!(
+            "Title: '{}' Url: '{}' Body: '{}' Created At: {} {} Author: {}\n",
+            title, url, body_text, date, upvotes_str, author_login
+        );
+        // The untrimmed posts, for the summary cache key.
+        let mut source_text = format!("{}\n", discussion.body.as_deref().unwrap_or(""));
+
+        let comments = match (discussion.comments, &discussion.id) {
+            (Some(first_page), Some(id)) => {
+                follow_connection(
+                    github,
+                    DISCUSSION_COMMENTS_QUERY,
+                    id,
+                    first_page,
+                    max_comments,
+                )
+                .await
+            }
+            _ => vec![],
+        };
 
-                            if let Some(comments) = &discussion.comments {
-                                if let Some(ref edges) = comments.edges {
-                                    for comment_edge_option in
-                                        edges.iter().filter_map(|e| e.as_ref())
-                                    {
-                                        if let Some(comment) = &comment_edge_option.node {
-                                            let stripped_comment_text = squeeze_fit_remove_quoted(
-                                                &comment.body.as_ref().unwrap_or(&empty_str),
-                                                "```",
-                                                300,
-                                                0.6,
-                                            );
-                                            let comment_author = comment
-                                                .author
-                                                .as_ref()
-                                                .and_then(|a| a.login.as_ref())
-                                                .unwrap_or(&empty_str);
-                                            disuccsion_texts.push_str(
-                                                &(format!(
-                                                    "{comment_author} comments: '{stripped_comment_text}'\n")),
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                            let disuccsion_texts =
-                                squeeze_fit_post_texts(&disuccsion_texts, 12_000, 0.4);
-                            let target_str = match &target_person {
-                                Some(person) => format!("{}'s", person),
-                                None => "key participants'".to_string(),
-                            };
+        let mut n_posts = comments.len();
+        for comment in comments {
+            source_text.push_str(&format!("{}\n", comment.body.as_deref().unwrap_or("")));
+            let stripped_comment_text = squeeze_fit_remove_quoted(
+                comment.body.as_ref().unwrap_or(&empty_str),
+                "```",
+                300,
+                0.6,
+            );
+            let comment_author = comment
+                .author
+                .as_ref()
+                .and_then(|a| a.login.as_ref())
+                .unwrap_or(&empty_str);
+            disuccsion_texts
+                .push_str(&(format!("{comment_author} comments: '{stripped_comment_text}'\n")));
+
+            let replies = match (comment.replies, &comment.id) {
+                (Some(first_page), Some(id)) if n_posts < max_comments => {
+                    follow_connection(
+                        github,
+                        COMMENT_REPLIES_QUERY,
+                        id,
+                        first_page,
+                        max_comments - n_posts,
+                    )
+                    .await
+                }
+                _ => vec![],
+            };
+            n_posts += replies.len();
+            for reply in replies {
+                source_text.push_str(&format!("{}\n", reply.body.as_deref().unwrap_or("")));
+                let stripped_reply_text = squeeze_fit_remove_quoted(
+                    reply.body.as_ref().unwrap_or(&empty_str),
+                    "```",
+                    200,
+                    0.6,
+                );
+                let reply_author = reply
+                    .author
+                    .as_ref()
+                    .and_then(|a| a.login.as_ref())
+                    .unwrap_or(&empty_str);
+                disuccsion_texts
+                    .push_str(&(format!("    {reply_author} replied: '{stripped_reply_text}'\n")));
+            }
+        }
+        let disuccsion_texts = squeeze_fit_post_texts(&disuccsion_texts, 12_000, 0.4);
+        let target_str = match &target_person {
+            Some(person) => format!("{}'s", person),
+            None => "key participants'".to_string(),
+        };
 
-                            let sys_prompt_1 = &format!(
-                                    "Analyze the provided GitHub discussion. Identify the main topic, actions by participants, crucial viewpoints, solutions or consensus reached, and particularly highlight the contributions of specific individuals, especially '{target_str}'. Summarize without being verbose."
-                                );
+        let sys_prompt_1 = &format!(
+                    "Analyze the provided GitHub discussion. Identify the main topic, actions by participants, crucial viewpoints, solutions or consensus reached, and particularly highlight the contributions of specific individuals, especially '{target_str}'. Summarize without being verbose."
+                );
 
-                            let co = match disuccsion_texts.len() > 12000 {
-                                true => ChatOptions {
-                                    model: chat::ChatModel::GPT35Turbo16K,
-                                    system_prompt: Some(sys_prompt_1),
-                                    restart: true,
-                                    temperature: Some(0.7),
-                                    max_tokens: Some(256),
-                                    ..Default::default()
-                                },
-                                false => ChatOptions {
-                                    model: chat::ChatModel::GPT35Turbo,
-                                    system_prompt: Some(sys_prompt_1),
-                                    restart: true,
-                                    temperature: Some(0.7),
-                                    max_tokens: Some(192),
-                                    ..Default::default()
-                                },
-                            };
+        let max_tokens = match disuccsion_texts.len() > 12000 {
+            true => 256,
+            false => 192,
+        };
 
-                            let usr_prompt_1 = &format!(
-                                    "Analyze the content: {disuccsion_texts}. Briefly summarize the central topic, participants' actions, primary viewpoints, and outcomes. Emphasize the role of '{target_str}' in driving the discussion or reaching a resolution. Aim for a succinct summary that is rich in analysis and under 192 tokens."
-                                );
+        let usr_prompt_1 = &format!(
+                    "Analyze the content: {disuccsion_texts}. Briefly summarize the central topic, participants' actions, primary viewpoints, and outcomes. Emphasize the role of '{target_str}' in driving the discussion or reaching a resolution. Aim for a succinct summary that is rich in analysis and under 192 tokens."
+                );
 
-                            match openai
-                                .chat_completion("discussion99", usr_prompt_1, &co)
-                                .await
-                            {
-                                Ok(r) => {
-                                    text_out.push_str(&(format!("{} {}", url, r.choice)));
-                                    git_mem_vec.push(GitMemory {
-                                        memory_type: MemoryType::Discussion,
-                                        name: author_login,
-                                        tag_line: title,
-                                        source_url: source_url,
-                                        payload: r.choice,
-                                        date: date,
-                                    });
-                                }
+        match cached_complete(
+            llm,
+            &url,
+            &source_text,
+            "discussion99",
+            sys_prompt_1,
+            usr_prompt_1,
+            max_tokens,
+        )
+        .await
+        {
+            Ok(r) => {
+                text_out.push_str(&(format!("{} {}", url, r)));
+                git_mem_vec.push(GitMemory {
+                    memory_type: MemoryType::Discussion,
+                    name: author_login,
+                    tag_line: title,
+                    source_url,
+                    payload: r,
+                    date,
+                });
+            }
 
-                                Err(_e) => log::error!(
-                                    "Error generating discussion summary #{}: {}",
-                                    url,
-                                    _e
-                                ),
-                            }
-                        }
-                    }
-                }
+            Err(e) => {
+                log::error!("Error generating discussion summary #{}: {}", url, e)
             }
-        },
+        }
     }
 
     if git_mem_vec.is_empty() {
-        None
+        Err(ResearchError::NoData(format!(
+            "discussions for {search_query}"
+        )))
     } else {
-        Some((text_out, git_mem_vec))
+        Ok((text_out, git_mem_vec))
     }
 }
+
 /* pub async fn search_discussions_integrated_chain(
-    github_token: &str,
+    github: &dyn GitHubTransport,
     search_query: &str,
     target_person: &Option<String>,
 ) -> Option<(String, Vec<GitMemory>)> {
@@ -1487,11 +3089,16 @@ pub async fn search_discussions_integrated(
         login: Option<String>,
     }
 
-    let base_url = "https://api.github.com/graphql";
+    let base_url = github.graphql_url();
 
     let query = format!(
         r#"
         query {{
+            rateLimit {{
+                cost
+                remaining
+                resetAt
+            }}
             search(query: "{search_query}", type: DISCUSSION, first: 100) {{
                 edges {{
                     node {{
@@ -1525,7 +3132,7 @@ pub async fn search_discussions_integrated(
     let mut git_mem_vec = Vec::with_capacity(100);
     let mut text_out = String::from("DISCUSSIONS \n");
 
-    match github_http_post(&github_token, base_url, &query).await {
+    match github_http_post(github, &query).await {
         None => {
             log::error!(
                 "Failed to send the request to get DiscussionRoot: {}",
@@ -1651,7 +3258,64 @@ pub async fn search_discussions_integrated(
     }
 } */
 
-pub async fn search_users(github_token: &str, search_query: &str) -> Option<String> {
+// One hit of `search_users_hits`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UserHit {
+    pub name: Option<String>,
+    pub login: String,
+    pub url: Option<String>,
+    pub twitter_username: Option<String>,
+    pub bio: Option<String>,
+    pub company: Option<String>,
+    pub location: Option<String>,
+    pub created_at: Option<NaiveDate>,
+    pub email: Option<String>,
+}
+
+impl UserHit {
+    pub fn to_line(&self) -> String {
+        let labelled = |label: &str, value: &Option<String>| match value {
+            Some(value) => format!("{label}: {value},"),
+            None => String::new(),
+        };
+        let name_str = labelled("Name", &self.name);
+        let login_str = format!("Login: {},", self.login);
+        let url_str = labelled("Url", &self.url);
+        let twitter_str = labelled("Twitter", &self.twitter_username);
+        let bio_str = labelled("Bio", &self.bio);
+        let company_str = labelled("Company", &self.company);
+        let location_str = labelled("Location", &self.location);
+        let date_str = match &self.created_at {
+            Some(date) => format!("Created At: {},", date),
+            None => String::new(),
+        };
+        let email_str = match &self.email {
+            Some(email) => format!("Email: {}", email),
+            None => String::new(),
+        };
+
+        format!(
+            "{name_str} {login_str} {url_str} {twitter_str} {bio_str} {company_str} {location_str} {date_str} {email_str}"
+        )
+    }
+}
+
+pub async fn search_users(
+    github: &dyn GitHubTransport,
+    search_query: &str,
+) -> ResearchResult<String> {
+    let mut out = String::from("USERS: \n");
+    for hit in search_users_hits(github, search_query).await? {
+        out.push_str(&hit.to_line());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+pub async fn search_users_hits(
+    github: &dyn GitHubTransport,
+    search_query: &str,
+) -> ResearchResult<Vec<UserHit>> {
     #[derive(Debug, Deserialize)]
     struct User {
         name: Option<String>,
@@ -1675,6 +3339,8 @@ pub async fn search_users(github_token: &str, search_query: &str) -> Option<Stri
     #[derive(Debug, Deserialize)]
     struct UserEdge {
         edges: Option<Vec<Option<UserNode>>>,
+        #[serde(rename = "pageInfo")]
+        page_info: Option<PageCursor>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -1682,119 +3348,95 @@ pub async fn search_users(github_token: &str, search_query: &str) -> Option<Stri
         search: Option<UserEdge>,
     }
 
-    #[derive(Debug, Deserialize)]
-    struct UserRoot {
-        data: Option<UserSearch>,
-    }
+    let base_url = github.graphql_url();
+    let mut hits = vec![];
 
-    let base_url = "https://api.github.com/graphql";
-    let mut out = String::from("USERS: \n");
+    let max_results = max_search_results();
+    let mut n_users = 0;
+    let mut cursor: Option<String> = None;
 
-    let query = format!(
-        r#"
-        query {{
-            search(query: "{search_query}", type: USER, first: 100) {{
-                edges {{
-                    node {{
-                        ... on User {{
-                            name
-                            login
-                            url
-                            twitterUsername
-                            bio
-                            company
-                            location
-                            createdAt
-                            email
-                        }}
-                    }}
-                }}
-            }}
-        }}
-        "#,
-        search_query = search_query
-    );
+    loop {
+        let query = GraphQlQuery::new(
+            r#"
+            query ($searchQuery: String!, $cursor: String) {
+                rateLimit {
+                    cost
+                    remaining
+                    resetAt
+                }
+                search(query: $searchQuery, type: USER, first: 100, after: $cursor) {
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                    edges {
+                        node {
+                            ... on User {
+                                name
+                                login
+                                url
+                                twitterUsername
+                                bio
+                                company
+                                location
+                                createdAt
+                                email
+                            }
+                        }
+                    }
+                }
+            }
+            "#,
+        )
+        .variable("searchQuery", search_query)
+        .variable("cursor", &cursor);
 
-    match github_http_post(&github_token, base_url, &query).await {
-        None => {
-            log::error!("Failed to send the request to get UserRoot: {}", base_url);
-            return None;
-        }
-        Some(res) => match serde_json::from_slice::<UserRoot>(res.as_slice()) {
+        match github_graphql::<UserSearch>(github, &query).await {
             Err(e) => {
-                log::error!("Failed to parse the response for UserRoot: {}", e);
-                return None;
+                log::error!("Failed to get users from {}: {}", base_url, e);
+                if cursor.is_none() {
+                    return Err(e);
+                }
+                break;
             }
             Ok(results) => {
-                if let Some(search) = &results.data {
-                    if let Some(edges) = &search.search {
-                        for edge_option in edges.edges.as_ref().unwrap_or(&vec![]) {
-                            if let Some(edge) = edge_option {
-                                if let Some(user) = &edge.node {
-                                    let login_str = match &user.login {
-                                        Some(login) => format!("Login: {},", login),
-                                        None => {
-                                            continue;
-                                        }
-                                    };
-                                    let name_str = match &user.name {
-                                        Some(name) => format!("Name: {},", name),
-                                        None => String::new(),
-                                    };
-
-                                    let url_str = match &user.url {
-                                        Some(url) => format!("Url: {},", url),
-                                        None => String::new(),
-                                    };
-
-                                    let twitter_str = match &user.twitter_username {
-                                        Some(twitter) => format!("Twitter: {},", twitter),
-                                        None => String::new(),
-                                    };
-
-                                    let bio_str = match &user.bio {
-                                        Some(bio) => format!("Bio: {},", bio),
-                                        None => String::new(),
-                                    };
-
-                                    let company_str = match &user.company {
-                                        Some(company) => format!("Company: {},", company),
-                                        None => String::new(),
-                                    };
-
-                                    let location_str = match &user.location {
-                                        Some(location) => format!("Location: {},", location),
-                                        None => String::new(),
-                                    };
-
-                                    let date_str = match &user.created_at {
-                                        Some(date) => {
-                                            format!(
-                                                "Created At: {},",
-                                                date.date_naive().to_string()
-                                            )
-                                        }
-                                        None => String::new(),
-                                    };
-
-                                    let email_str = match &user.email {
-                                        Some(email) => format!("Email: {}", email),
-                                        None => String::new(),
-                                    };
-
-                                    out.push_str(
-                                            &format!(
-                                                "{name_str} {login_str} {url_str} {twitter_str} {bio_str} {company_str} {location_str} {date_str} {email_str}\n"
-                                            )
-                                        );
-                                }
+                let search = match &results.data.search {
+                    Some(search) => search,
+                    None => break,
+                };
+                for edge in search.edges.iter().flatten().flatten() {
+                    if let Some(user) = &edge.node {
+                        let login = match &user.login {
+                            Some(login) => login.clone(),
+                            None => {
+                                continue;
                             }
-                        }
+                        };
+
+                        hits.push(UserHit {
+                            name: user.name.clone(),
+                            login,
+                            url: user.url.clone(),
+                            twitter_username: user.twitter_username.clone(),
+                            bio: user.bio.clone(),
+                            company: user.company.clone(),
+                            location: user.location.clone(),
+                            created_at: user.created_at.map(|date| date.date_naive()),
+                            email: user.email.clone(),
+                        });
+                        n_users += 1;
                     }
                 }
+                if n_users >= max_results {
+                    break;
+                }
+                match search.page_info.as_ref().and_then(|p| p.next_cursor()) {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
             }
-        },
+        }
     }
 
-    Some(out)
+    Ok(hits)
 }
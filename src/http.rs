@@ -0,0 +1,112 @@
+// HTTP for the GitHub transport, the OpenAI-compatible LLM client and the sinks:
+// `http_req` under the `flows` feature, `ureq` under `native`. Error statuses are
+// returned as responses, only failures to get an answer at all are errors.
+//
+// Both clients block. Under `native` each request runs on tokio's blocking pool, so joined
+// futures overlap even on a current-thread runtime. The wasm runtime has no threads to
+// hand the request to, so under `flows` requests still run one after another.
+use crate::transport::GitHubResponse;
+
+#[cfg(feature = "flows")]
+pub async fn send(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+) -> Result<GitHubResponse, Box<dyn std::error::Error>> {
+    use http_req::{request::Method, request::Request, uri::Uri};
+    use std::collections::HashMap;
+
+    let uri = Uri::try_from(url)?;
+    let mut writer = Vec::new();
+
+    let mut request = Request::new(&uri);
+    request.method(match method {
+        "POST" => Method::POST,
+        "PUT" => Method::PUT,
+        _ => Method::GET,
+    });
+    for (key, val) in headers {
+        request.header(*key, *val);
+    }
+    if let Some(body) = body {
+        request.header("Content-Length", &body.len()).body(body);
+    }
+
+    let res = request.send(&mut writer)?;
+    let headers = res
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<HashMap<String, String>>();
+
+    Ok(GitHubResponse {
+        status: u16::from(res.status_code()),
+        headers,
+        body: writer,
+    })
+}
+
+#[cfg(feature = "native")]
+pub async fn send(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: Option<&[u8]>,
+) -> Result<GitHubResponse, Box<dyn std::error::Error>> {
+    let method = method.to_string();
+    let url = url.to_string();
+    let headers = headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+    let body = body.map(|b| b.to_vec());
+
+    let res = tokio::task::spawn_blocking(move || {
+        send_blocking(&method, &url, &headers, body.as_deref()).map_err(|e| e.to_string())
+    })
+    .await?;
+    res.map_err(|e| Box::new(std::io::Error::other(e)) as Box<dyn std::error::Error>)
+}
+
+#[cfg(feature = "native")]
+fn send_blocking(
+    method: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: Option<&[u8]>,
+) -> Result<GitHubResponse, Box<dyn std::error::Error>> {
+    use std::io::Read;
+
+    let mut request = ureq::request(method, url);
+    for (key, val) in headers {
+        request = request.set(key, val);
+    }
+    let result = match body {
+        Some(body) => request.send_bytes(body),
+        None => request.call(),
+    };
+    let res = match result {
+        Ok(res) => res,
+        Err(ureq::Error::Status(_, res)) => res,
+        Err(e) => return Err(Box::new(e)),
+    };
+
+    let headers = res
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let val = res.header(&name)?.to_string();
+            Some((name, val))
+        })
+        .collect();
+    let status = res.status();
+    let mut body = Vec::new();
+    res.into_reader().read_to_end(&mut body)?;
+
+    Ok(GitHubResponse {
+        status,
+        headers,
+        body,
+    })
+}
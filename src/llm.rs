@@ -0,0 +1,388 @@
+#[cfg(feature = "flows")]
+use openai_flows::{
+    chat::{ChatModel, ChatOptions},
+    OpenAIFlows,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type LlmFuture<'a> = Pin<Box<dyn Future<Output = Option<String>> + 'a>>;
+
+pub trait LlmBackend: Send + Sync {
+    // Number of tokens the model accepts for prompt plus generation.
+    fn token_budget(&self) -> usize;
+
+    // Names the backend and model, so answers cached for one aren't served for another.
+    fn model_id(&self) -> String;
+
+    fn complete<'a>(
+        &'a self,
+        chat_id: &'a str,
+        system_prompt: &'a str,
+        user_prompt: &'a str,
+        max_tokens: u16,
+    ) -> LlmFuture<'a>;
+
+    // Asks `usr_prompt_1`, then `usr_prompt_2` with the first answer kept in the conversation.
+    fn chain<'a>(
+        &'a self,
+        chat_id: &'a str,
+        sys_prompt_1: &'a str,
+        usr_prompt_1: &'a str,
+        gen_len_1: u16,
+        usr_prompt_2: &'a str,
+        gen_len_2: u16,
+    ) -> LlmFuture<'a>;
+}
+
+#[cfg(feature = "flows")]
+pub struct OpenAIFlowsBackend;
+
+#[cfg(feature = "flows")]
+impl OpenAIFlowsBackend {
+    // Picks the 4k model when the prompt comfortably fits, the 16k one otherwise.
+    fn model_for(prompt_len: usize, max_tokens: u16) -> ChatModel {
+        match prompt_len / 4 + max_tokens as usize > 3_500 {
+            true => ChatModel::GPT35Turbo16K,
+            false => ChatModel::GPT35Turbo,
+        }
+    }
+}
+
+#[cfg(feature = "flows")]
+impl LlmBackend for OpenAIFlowsBackend {
+    fn token_budget(&self) -> usize {
+        16_000
+    }
+
+    fn model_id(&self) -> String {
+        "openai-flows/gpt-3.5-turbo".to_string()
+    }
+
+    fn complete<'a>(
+        &'a self,
+        chat_id: &'a str,
+        system_prompt: &'a str,
+        user_prompt: &'a str,
+        max_tokens: u16,
+    ) -> LlmFuture<'a> {
+        Box::pin(async move {
+            let openai = OpenAIFlows::new();
+            let co = ChatOptions {
+                model: Self::model_for(system_prompt.len() + user_prompt.len(), max_tokens),
+                restart: true,
+                system_prompt: Some(system_prompt),
+                max_tokens: Some(max_tokens),
+                temperature: Some(0.7),
+                ..Default::default()
+            };
+
+            match openai.chat_completion(chat_id, user_prompt, &co).await {
+                Ok(r) => Some(r.choice),
+                Err(_e) => {
+                    log::error!("{}, GPT generation error {:?}", chat_id, _e);
+                    None
+                }
+            }
+        })
+    }
+
+    fn chain<'a>(
+        &'a self,
+        chat_id: &'a str,
+        sys_prompt_1: &'a str,
+        usr_prompt_1: &'a str,
+        gen_len_1: u16,
+        usr_prompt_2: &'a str,
+        gen_len_2: u16,
+    ) -> LlmFuture<'a> {
+        Box::pin(async move {
+            let openai = OpenAIFlows::new();
+
+            let co_1 = ChatOptions {
+                model: ChatModel::GPT35Turbo16K,
+                restart: true,
+                system_prompt: Some(sys_prompt_1),
+                max_tokens: Some(gen_len_1),
+                temperature: Some(0.7),
+                ..Default::default()
+            };
+
+            let res_1 = match openai.chat_completion(chat_id, usr_prompt_1, &co_1).await {
+                Ok(r) => r,
+                Err(_e) => {
+                    log::error!("{}, Step 1 GPT generation error {:?}", chat_id, _e);
+                    return None;
+                }
+            };
+
+            let sys_prompt_2 = json!([{"role": "system", "content": sys_prompt_1},
+            {"role": "user", "content": usr_prompt_1},
+            {"role": "assistant", "content": &res_1.choice}])
+            .to_string();
+
+            let co_2 = ChatOptions {
+                model: ChatModel::GPT35Turbo16K,
+                restart: false,
+                system_prompt: Some(&sys_prompt_2),
+                max_tokens: Some(gen_len_2),
+                temperature: Some(0.7),
+                ..Default::default()
+            };
+            match openai.chat_completion(chat_id, usr_prompt_2, &co_2).await {
+                Ok(res_2) => Some(res_2.choice),
+                Err(_e) => {
+                    log::error!("{}, Step 2 GPT generation error {:?}", chat_id, _e);
+                    None
+                }
+            }
+        })
+    }
+}
+
+// Talks to any server implementing the OpenAI `/chat/completions` API,
+// e.g. a local llama.cpp or vLLM instance.
+pub struct OpenAICompatibleBackend {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    context_tokens: usize,
+}
+
+impl OpenAICompatibleBackend {
+    pub fn new(base_url: &str, model: &str) -> Self {
+        OpenAICompatibleBackend {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            model: model.to_string(),
+            context_tokens: 4_096,
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    pub fn with_context_tokens(mut self, context_tokens: usize) -> Self {
+        self.context_tokens = context_tokens;
+        self
+    }
+
+    async fn chat(&self, messages: &serde_json::Value, max_tokens: u16) -> Option<String> {
+        #[derive(Deserialize)]
+        struct ChatResponse {
+            choices: Vec<Choice>,
+        }
+
+        #[derive(Deserialize)]
+        struct Choice {
+            message: Message,
+        }
+
+        #[derive(Deserialize)]
+        struct Message {
+            content: String,
+        }
+
+        let url = format!("{}/chat/completions", self.base_url);
+        let body = json!({
+            "model": self.model,
+            "messages": messages,
+            "max_tokens": max_tokens,
+            "temperature": 0.7,
+        })
+        .to_string();
+        let auth = self
+            .api_key
+            .as_ref()
+            .map(|key| format!("Bearer {key}"))
+            .unwrap_or_default();
+
+        let mut headers = vec![("Content-Type", "application/json")];
+        if !auth.is_empty() {
+            headers.push(("Authorization", auth.as_str()));
+        }
+
+        match crate::http::send("POST", &url, &headers, Some(body.as_bytes())).await {
+            Ok(res) => {
+                if !res.is_success() {
+                    log::error!("LLM endpoint http error {}", res.status);
+                    return None;
+                }
+                match serde_json::from_slice::<ChatResponse>(&res.body) {
+                    Ok(mut r) if !r.choices.is_empty() => Some(r.choices.remove(0).message.content),
+                    Ok(_) => {
+                        log::error!("LLM endpoint returned no choices");
+                        None
+                    }
+                    Err(_e) => {
+                        log::error!("Error parsing LLM endpoint response: {:?}", _e);
+                        None
+                    }
+                }
+            }
+            Err(_e) => {
+                log::error!("Error getting response from LLM endpoint {}: {:?}", url, _e);
+                None
+            }
+        }
+    }
+}
+
+impl LlmBackend for OpenAICompatibleBackend {
+    fn token_budget(&self) -> usize {
+        self.context_tokens
+    }
+
+    fn model_id(&self) -> String {
+        format!("{} {}", self.base_url, self.model)
+    }
+
+    fn complete<'a>(
+        &'a self,
+        _chat_id: &'a str,
+        system_prompt: &'a str,
+        user_prompt: &'a str,
+        max_tokens: u16,
+    ) -> LlmFuture<'a> {
+        Box::pin(async move {
+            let messages = json!([{"role": "system", "content": system_prompt},
+            {"role": "user", "content": user_prompt}]);
+            self.chat(&messages, max_tokens).await
+        })
+    }
+
+    fn chain<'a>(
+        &'a self,
+        _chat_id: &'a str,
+        sys_prompt_1: &'a str,
+        usr_prompt_1: &'a str,
+        gen_len_1: u16,
+        usr_prompt_2: &'a str,
+        gen_len_2: u16,
+    ) -> LlmFuture<'a> {
+        Box::pin(async move {
+            let mut messages = vec![
+                json!({"role": "system", "content": sys_prompt_1}),
+                json!({"role": "user", "content": usr_prompt_1}),
+            ];
+            let res_1 = self.chat(&json!(messages), gen_len_1).await?;
+
+            messages.push(json!({"role": "assistant", "content": res_1}));
+            messages.push(json!({"role": "user", "content": usr_prompt_2}));
+            self.chat(&json!(messages), gen_len_2).await
+        })
+    }
+}
+
+// Returns fixed answers keyed by chat id, so analyzers can run without a model.
+pub struct CannedBackend {
+    responses: HashMap<String, String>,
+    fallback: String,
+}
+
+impl CannedBackend {
+    pub fn new(fallback: &str) -> Self {
+        CannedBackend {
+            responses: HashMap::new(),
+            fallback: fallback.to_string(),
+        }
+    }
+
+    pub fn with_response(mut self, chat_id: &str, response: &str) -> Self {
+        self.responses
+            .insert(chat_id.to_string(), response.to_string());
+        self
+    }
+
+    // The file holds a JSON object of chat id to response; the `default` key is the fallback.
+    pub fn from_file(path: &str) -> Option<Self> {
+        let raw = std::fs::read(path).ok()?;
+        let mut responses = serde_json::from_slice::<HashMap<String, String>>(&raw).ok()?;
+        let fallback = responses.remove("default").unwrap_or_default();
+        Some(CannedBackend {
+            responses,
+            fallback,
+        })
+    }
+
+    fn response_for(&self, chat_id: &str) -> Option<String> {
+        Some(
+            self.responses
+                .get(chat_id)
+                .unwrap_or(&self.fallback)
+                .to_string(),
+        )
+    }
+}
+
+impl LlmBackend for CannedBackend {
+    fn token_budget(&self) -> usize {
+        16_000
+    }
+
+    fn model_id(&self) -> String {
+        "canned".to_string()
+    }
+
+    fn complete<'a>(
+        &'a self,
+        chat_id: &'a str,
+        _system_prompt: &'a str,
+        _user_prompt: &'a str,
+        _max_tokens: u16,
+    ) -> LlmFuture<'a> {
+        Box::pin(async move { self.response_for(chat_id) })
+    }
+
+    fn chain<'a>(
+        &'a self,
+        chat_id: &'a str,
+        _sys_prompt_1: &'a str,
+        _usr_prompt_1: &'a str,
+        _gen_len_1: u16,
+        _usr_prompt_2: &'a str,
+        _gen_len_2: u16,
+    ) -> LlmFuture<'a> {
+        Box::pin(async move { self.response_for(chat_id) })
+    }
+}
+
+fn openai_compatible_from_env() -> OpenAICompatibleBackend {
+    let base_url = env::var("llm_api_base").unwrap_or("http://localhost:8080/v1".to_string());
+    let model = env::var("llm_model").unwrap_or("gpt-3.5-turbo".to_string());
+    let mut backend = OpenAICompatibleBackend::new(&base_url, &model);
+    if let Ok(key) = env::var("llm_api_key") {
+        backend = backend.with_api_key(&key);
+    }
+    if let Some(n) = env::var("llm_context_tokens")
+        .ok()
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        backend = backend.with_context_tokens(n);
+    }
+    backend
+}
+
+// `llm_backend` selects `openai-flows`, `openai-compatible` or `canned`. The default is
+// `openai-flows` with the `flows` feature and `openai-compatible` with `native`.
+pub fn llm_from_env() -> Box<dyn LlmBackend> {
+    match env::var("llm_backend").unwrap_or_default().as_str() {
+        "openai-compatible" => Box::new(openai_compatible_from_env()),
+        "canned" => {
+            let canned = env::var("llm_canned_responses")
+                .ok()
+                .and_then(|path| CannedBackend::from_file(&path));
+            Box::new(canned.unwrap_or(CannedBackend::new("canned summary for testing")))
+        }
+        #[cfg(feature = "flows")]
+        _ => Box::new(OpenAIFlowsBackend),
+        #[cfg(not(feature = "flows"))]
+        _ => Box::new(openai_compatible_from_env()),
+    }
+}
@@ -0,0 +1,938 @@
+// Markdown, HTML and JSON renderings of a `Report`, an `OrgReport`, a `ContributorProfile`
+// and an `ActivityReport`. Markdown and HTML are built from the same list of blocks so
+// both show the same sections in the same order.
+use crate::github_data_fetchers::{
+    ContributionItem, GitMemory, LanguagePortfolio, LanguageSkill, RepoContributions,
+    ReviewerActivity,
+};
+use crate::reports::{
+    ActivityReport, ContributorProfile, OrgReport, Report, ReportSection, RATE_LIMITED_NOTE,
+    SUMMARY_UNAVAILABLE_NOTE,
+};
+
+pub(crate) enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    Items(Vec<Entry>),
+    Table(Vec<String>, Vec<Vec<String>>),
+    Note(String),
+}
+
+pub(crate) struct Entry {
+    pub title: String,
+    pub url: String,
+    pub byline: String,
+    pub summary: Option<String>,
+}
+
+impl Report {
+    pub fn title(&self) -> String {
+        let subject = match &self.user_name {
+            Some(user_name) => format!("{user_name} in {}/{}", self.owner, self.repo),
+            None => format!("{}/{}", self.owner, self.repo),
+        };
+        match &self.window {
+            Some(window) => format!("Report on {subject} {}", window.describe()),
+            None => format!("Report on {subject}"),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        markdown(report_blocks(self))
+    }
+
+    // A standalone page with inline styles, so it can be mailed or hosted as-is.
+    pub fn to_html(&self) -> String {
+        html_page(&self.title(), &self.to_html_fragment())
+    }
+
+    // The report's HTML without the page around it, for embedding in other documents.
+    pub fn to_html_fragment(&self) -> String {
+        html_fragment(report_blocks(self))
+    }
+
+    // Every section with all of its `GitMemory` items, for feeding into other tools.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl OrgReport {
+    pub fn title(&self) -> String {
+        let subject = match &self.user_name {
+            Some(user_name) => format!("{user_name} in {}", self.owner),
+            None => self.owner.clone(),
+        };
+        match &self.window {
+            Some(window) => format!("Report on {subject} {}", window.describe()),
+            None => format!("Report on {subject}"),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        markdown(org_blocks(self))
+    }
+
+    pub fn to_html(&self) -> String {
+        html_page(&self.title(), &self.to_html_fragment())
+    }
+
+    pub fn to_html_fragment(&self) -> String {
+        html_fragment(org_blocks(self))
+    }
+
+    // Includes every repo's full report.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl ContributorProfile {
+    pub fn title(&self) -> String {
+        let subject = format!("{} for {}/{}", self.user_name, self.owner, self.repo);
+        match &self.window {
+            Some(window) => format!(
+                "Contributor profile of {subject}, activity {}",
+                window.describe()
+            ),
+            None => format!("Contributor profile of {subject}"),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        markdown(profile_blocks(self))
+    }
+
+    pub fn to_html(&self) -> String {
+        html_page(&self.title(), &self.to_html_fragment())
+    }
+
+    pub fn to_html_fragment(&self) -> String {
+        html_fragment(profile_blocks(self))
+    }
+
+    // Includes the raw profile, repo and issue data the analysis was based on.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl ActivityReport {
+    pub fn title(&self) -> String {
+        match &self.window {
+            Some(window) => format!(
+                "Activity of {} across GitHub {}",
+                self.user_name,
+                window.describe()
+            ),
+            None => format!("Activity of {} across GitHub", self.user_name),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        markdown(activity_blocks(self))
+    }
+
+    pub fn to_html(&self) -> String {
+        html_page(&self.title(), &self.to_html_fragment())
+    }
+
+    pub fn to_html_fragment(&self) -> String {
+        html_fragment(activity_blocks(self))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl LanguagePortfolio {
+    pub fn title(&self) -> String {
+        format!("Languages of {}", self.login)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        markdown(portfolio_blocks(self))
+    }
+
+    pub fn to_html(&self) -> String {
+        html_page(&self.title(), &self.to_html_fragment())
+    }
+
+    pub fn to_html_fragment(&self) -> String {
+        html_fragment(portfolio_blocks(self))
+    }
+}
+
+fn markdown(blocks: Vec<Block>) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(level as usize), text))
+            }
+            Block::Paragraph(text) => out.push_str(&format!("{}\n\n", text.trim())),
+            Block::Items(entries) => {
+                for entry in entries {
+                    let title = entry.title.replace('[', "\\[").replace(']', "\\]");
+                    out.push_str(&format!("- [{}]({})", title, entry.url));
+                    if !entry.byline.is_empty() {
+                        out.push_str(&format!(" {}", entry.byline));
+                    }
+                    out.push('\n');
+                    if let Some(summary) = entry.summary {
+                        for line in summary.lines().filter(|l| !l.trim().is_empty()) {
+                            out.push_str(&format!("  {}\n", line.trim()));
+                        }
+                    }
+                }
+                out.push('\n');
+            }
+            Block::Table(header, rows) => {
+                out.push_str(&format!("| {} |\n", header.join(" | ")));
+                out.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+                for row in rows {
+                    out.push_str(&format!("| {} |\n", row.join(" | ")));
+                }
+                out.push('\n');
+            }
+            Block::Note(text) => out.push_str(&format!("> {}\n\n", text)),
+        }
+    }
+    out.trim_end().to_string() + "\n"
+}
+
+fn html_page(title: &str, fragment: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; max-width: 52em; margin: 2em auto; padding: 0 1em; line-height: 1.5; color: #24292f; }}
+a {{ color: #0969da; }}
+li {{ margin-bottom: 0.6em; }}
+li p {{ margin: 0.2em 0 0; }}
+.byline {{ color: #57606a; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #d0d7de; padding: 0.3em 0.6em; text-align: left; }}
+.note {{ border-left: 4px solid #d4a72c; padding-left: 0.8em; color: #57606a; }}
+</style>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+        escape_html(title),
+        fragment
+    )
+}
+
+fn html_fragment(blocks: Vec<Block>) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                body.push_str(&format!("<h{level}>{}</h{level}>\n", escape_html(&text)))
+            }
+            Block::Paragraph(text) => {
+                for para in text.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                    body.push_str(&format!(
+                        "<p>{}</p>\n",
+                        escape_html(para.trim()).replace('\n', "<br>\n")
+                    ));
+                }
+            }
+            Block::Items(entries) => {
+                body.push_str("<ul>\n");
+                for entry in entries {
+                    body.push_str(&format!(
+                        "<li><a href=\"{}\">{}</a>",
+                        escape_html(&entry.url),
+                        escape_html(&entry.title)
+                    ));
+                    if !entry.byline.is_empty() {
+                        body.push_str(&format!(
+                            " <span class=\"byline\">{}</span>",
+                            escape_html(&entry.byline)
+                        ));
+                    }
+                    if let Some(summary) = entry.summary {
+                        body.push_str(&format!(
+                            "<p>{}</p>",
+                            escape_html(summary.trim()).replace('\n', "<br>\n")
+                        ));
+                    }
+                    body.push_str("</li>\n");
+                }
+                body.push_str("</ul>\n");
+            }
+            Block::Table(header, rows) => {
+                body.push_str("<table>\n<tr>");
+                for cell in header {
+                    body.push_str(&format!("<th>{}</th>", escape_html(&cell)));
+                }
+                body.push_str("</tr>\n");
+                for row in rows {
+                    body.push_str("<tr>");
+                    for cell in row {
+                        body.push_str(&format!("<td>{}</td>", escape_html(&cell)));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</table>\n");
+            }
+            Block::Note(text) => {
+                body.push_str(&format!("<p class=\"note\">{}</p>\n", escape_html(&text)))
+            }
+        }
+    }
+    body
+}
+
+pub(crate) fn report_blocks(report: &Report) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading(1, report.title())];
+
+    if let Some(profile) = &report.profile {
+        blocks.push(Block::Heading(2, "About".to_string()));
+        blocks.push(Block::Paragraph(profile.payload.clone()));
+    }
+
+    if report.is_empty() {
+        blocks.push(Block::Paragraph(report.to_text()));
+        return blocks;
+    }
+
+    blocks.push(Block::Heading(2, "Summary".to_string()));
+    blocks.push(match &report.synthesis {
+        Some(synthesis) => Block::Paragraph(synthesis.clone()),
+        None => Block::Note(SUMMARY_UNAVAILABLE_NOTE.to_string()),
+    });
+
+    for (title, section) in [
+        ("Commits", &report.commits),
+        ("Issues", &report.issues),
+        ("Pull requests", &report.pull_requests),
+    ] {
+        blocks.push(Block::Heading(
+            2,
+            format!("{title} ({} found)", section.found.len()),
+        ));
+        blocks.push(section_block(section));
+    }
+
+    if !report.reviews.is_empty() {
+        blocks.push(Block::Heading(2, "Reviews".to_string()));
+        blocks.push(reviews_block(&report.reviews));
+    }
+
+    if !report.discussions.items.is_empty() {
+        blocks.push(Block::Heading(
+            2,
+            format!(
+                "Discussions ({} referenced)",
+                report.discussions.found.len()
+            ),
+        ));
+        blocks.push(section_block(&report.discussions));
+    }
+
+    if report.rate_limited {
+        blocks.push(Block::Note(RATE_LIMITED_NOTE.to_string()));
+    }
+    blocks
+}
+
+// The cross-repo summary, a table of the active repos, then each one's own summary.
+pub(crate) fn org_blocks(org: &OrgReport) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading(1, org.title())];
+
+    if org.reports.is_empty() {
+        blocks.push(Block::Paragraph(match &org.user_name {
+            Some(user_name) => format!(
+                "No activity by {user_name} found in the repos of {}.",
+                org.owner
+            ),
+            None => format!("No activity found in the repos of {}.", org.owner),
+        }));
+    } else {
+        if let Some(synthesis) = &org.synthesis {
+            blocks.push(Block::Heading(2, "Summary".to_string()));
+            blocks.push(Block::Paragraph(synthesis.clone()));
+        }
+
+        blocks.push(Block::Heading(
+            2,
+            format!("Repositories ({})", org.reports.len()),
+        ));
+        let header = [
+            "Repository",
+            "Commits",
+            "Issues",
+            "Pull requests",
+            "Reviews",
+            "Discussions",
+        ]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+        let rows = org
+            .reports
+            .iter()
+            .map(|report| {
+                vec![
+                    report.repo.clone(),
+                    report.commits.found.len().to_string(),
+                    report.issues.found.len().to_string(),
+                    report.pull_requests.found.len().to_string(),
+                    report
+                        .reviews
+                        .iter()
+                        .map(|r| r.reviews())
+                        .sum::<usize>()
+                        .to_string(),
+                    report.discussions.found.len().to_string(),
+                ]
+            })
+            .collect();
+        blocks.push(Block::Table(header, rows));
+
+        for report in &org.reports {
+            blocks.push(Block::Heading(
+                2,
+                format!("{}/{}", report.owner, report.repo),
+            ));
+            blocks.push(Block::Paragraph(
+                report
+                    .synthesis
+                    .clone()
+                    .unwrap_or_else(|| "No summary generated.".to_string()),
+            ));
+        }
+    }
+
+    if !org.quiet.is_empty() {
+        blocks.push(Block::Note(format!(
+            "No activity in: {}.",
+            org.quiet.join(", ")
+        )));
+    }
+    if !org.failed.is_empty() {
+        blocks.push(Block::Note(format!(
+            "Could not report on: {}.",
+            org.failed.join(", ")
+        )));
+    }
+    if org.rate_limited {
+        blocks.push(Block::Note(RATE_LIMITED_NOTE.to_string()));
+    }
+    blocks
+}
+
+pub(crate) fn profile_blocks(profile: &ContributorProfile) -> Vec<Block> {
+    let mut blocks = vec![
+        Block::Heading(1, profile.title()),
+        Block::Heading(2, format!("Fit with {}/{}", profile.owner, profile.repo)),
+        Block::Paragraph(profile.analysis.clone()),
+    ];
+
+    // The fetchers' text starts with a heading line of their own.
+    let profile_text = profile.user_profile.lines().skip(1).collect::<Vec<_>>();
+    if !profile_text.is_empty() {
+        blocks.push(Block::Heading(2, "Profile".to_string()));
+        blocks.push(Block::Paragraph(profile_text.join("\n\n")));
+    }
+
+    if !profile.portfolio.skills.is_empty() {
+        blocks.push(Block::Heading(2, "Languages".to_string()));
+        blocks.push(skills_block(
+            &profile.portfolio.skills,
+            Some(&profile.project_languages),
+        ));
+        blocks.push(Block::Heading(2, "Repositories".to_string()));
+        blocks.push(portfolio_repos_block(&profile.portfolio));
+    }
+
+    if !profile.discussions.is_empty() {
+        blocks.push(Block::Heading(2, "Discussions".to_string()));
+        blocks.push(Block::Items(
+            profile.discussions.iter().map(memory_entry).collect(),
+        ));
+    }
+
+    if profile.rate_limited {
+        blocks.push(Block::Note(RATE_LIMITED_NOTE.to_string()));
+    }
+    blocks
+}
+
+// The summary, a table of all repos, then the issues, pull requests and reviews of the
+// ten busiest ones.
+pub(crate) fn activity_blocks(report: &ActivityReport) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading(1, report.title())];
+    let history = &report.history;
+
+    if history.is_empty() {
+        blocks.push(Block::Paragraph(format!(
+            "No public contributions by {} found.",
+            report.user_name
+        )));
+    } else {
+        if let Some(summary) = &report.summary {
+            blocks.push(Block::Heading(2, "Summary".to_string()));
+            blocks.push(Block::Paragraph(summary.clone()));
+        }
+
+        blocks.push(Block::Heading(
+            2,
+            format!("Repositories ({})", history.repos.len()),
+        ));
+        blocks.push(contributions_table(&history.repos));
+
+        for repo in history.repos.iter().take(10) {
+            let mut entries = vec![];
+            for (kind, items) in [
+                ("issue", &repo.issues),
+                ("pull request", &repo.pull_requests),
+                ("review", &repo.reviews),
+            ] {
+                entries.extend(items.iter().map(|item| contribution_entry(kind, item)));
+            }
+            if !entries.is_empty() {
+                blocks.push(Block::Heading(3, repo.name_with_owner.clone()));
+                blocks.push(Block::Items(entries));
+            }
+        }
+    }
+
+    if history.restricted_contributions > 0 {
+        blocks.push(Block::Note(format!(
+            "{} contributions to private repositories are not shown.",
+            history.restricted_contributions
+        )));
+    }
+    if report.rate_limited {
+        blocks.push(Block::Note(RATE_LIMITED_NOTE.to_string()));
+    }
+    blocks
+}
+
+fn contributions_table(repos: &[RepoContributions]) -> Block {
+    let header = [
+        "Repository",
+        "Commits",
+        "Issues",
+        "Pull requests",
+        "Reviews",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect();
+    let rows = repos
+        .iter()
+        .map(|repo| {
+            vec![
+                repo.name_with_owner.clone(),
+                repo.commits.to_string(),
+                repo.issue_count.to_string(),
+                repo.pull_request_count.to_string(),
+                repo.review_count.to_string(),
+            ]
+        })
+        .collect();
+    Block::Table(header, rows)
+}
+
+fn contribution_entry(kind: &str, item: &ContributionItem) -> Entry {
+    Entry {
+        title: item.title.clone(),
+        url: item.url.clone(),
+        byline: format!(
+            "({kind}, {}, {})",
+            item.state.to_lowercase().replace('_', " "),
+            item.occurred_at.date_naive()
+        ),
+        summary: None,
+    }
+}
+
+// Lists every item found, with the summary when the item was analyzed.
+fn section_block(section: &ReportSection) -> Block {
+    if section.found.is_empty() {
+        return Block::Paragraph("None found.".to_string());
+    }
+    let entries = section
+        .found
+        .iter()
+        .map(
+            |url| match section.items.iter().find(|gm| &gm.source_url == url) {
+                Some(gm) => memory_entry(gm),
+                None => Entry {
+                    title: url.clone(),
+                    url: url.clone(),
+                    byline: String::new(),
+                    summary: None,
+                },
+            },
+        )
+        .collect();
+    Block::Items(entries)
+}
+
+fn memory_entry(gm: &GitMemory) -> Entry {
+    let title = match gm.tag_line.lines().next().map(str::trim) {
+        Some(line) if !line.is_empty() => line.to_string(),
+        _ => gm.source_url.clone(),
+    };
+    let byline = match gm.name.is_empty() {
+        true => format!("({})", gm.date),
+        false => format!("by {} ({})", gm.name, gm.date),
+    };
+    Entry {
+        title,
+        url: gm.source_url.clone(),
+        byline,
+        summary: match gm.payload.trim().is_empty() {
+            true => None,
+            false => Some(gm.payload.clone()),
+        },
+    }
+}
+
+// Languages over 1% of the portfolio's code, marking the ones a project uses if given.
+fn skills_block(skills: &[LanguageSkill], project_languages: Option<&[String]>) -> Block {
+    let mut header = vec![
+        "Language".to_string(),
+        "Share".to_string(),
+        "Repos".to_string(),
+    ];
+    if project_languages.is_some() {
+        header.push("Used by the project".to_string());
+    }
+    let rows = skills
+        .iter()
+        .filter(|s| s.share >= 0.01)
+        .map(|s| {
+            let mut row = vec![
+                s.language.clone(),
+                format!("{:.0}%", s.share * 100.0),
+                s.repos.to_string(),
+            ];
+            if let Some(project_languages) = project_languages {
+                row.push(match project_languages.contains(&s.language) {
+                    true => "yes".to_string(),
+                    false => String::new(),
+                });
+            }
+            row
+        })
+        .collect();
+    Block::Table(header, rows)
+}
+
+// The ten most starred repos.
+fn portfolio_blocks(portfolio: &LanguagePortfolio) -> Vec<Block> {
+    vec![
+        Block::Heading(1, portfolio.title()),
+        skills_block(&portfolio.skills, None),
+        Block::Heading(2, "Repositories".to_string()),
+        portfolio_repos_block(portfolio),
+    ]
+}
+
+fn portfolio_repos_block(portfolio: &LanguagePortfolio) -> Block {
+    let mut repos = portfolio.repos.iter().collect::<Vec<_>>();
+    repos.sort_by_key(|repo| std::cmp::Reverse(repo.stars));
+    let entries = repos
+        .into_iter()
+        .take(10)
+        .map(|repo| {
+            let mut byline = vec![match repo.owned {
+                true => "owned".to_string(),
+                false => "contributed to".to_string(),
+            }];
+            byline.push(format!("{} stars", repo.stars));
+            byline.extend(
+                repo.languages
+                    .iter()
+                    .take(3)
+                    .map(|(language, _)| language.clone()),
+            );
+            Entry {
+                title: repo.name_with_owner.clone(),
+                url: repo.url.clone(),
+                byline: format!("({})", byline.join(", ")),
+                summary: repo.description.clone().filter(|d| !d.trim().is_empty()),
+            }
+        })
+        .collect();
+    Block::Items(entries)
+}
+
+fn reviews_block(reviews: &[ReviewerActivity]) -> Block {
+    let header = [
+        "Reviewer",
+        "Pull requests",
+        "Approvals",
+        "Change requests",
+        "Comment reviews",
+        "Review comments",
+        "Average turnaround",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect();
+    let rows = reviews
+        .iter()
+        .map(|r| {
+            vec![
+                r.login.clone(),
+                r.pull_requests.len().to_string(),
+                r.approvals.to_string(),
+                r.change_requests.to_string(),
+                r.commented_reviews.to_string(),
+                r.review_comments.to_string(),
+                r.average_turnaround_hours()
+                    .map(|h| format!("{:.1} hours", h))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    Block::Table(header, rows)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_data_fetchers::{ContributionHistory, PortfolioRepo};
+    use crate::reports::sample_report;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn report_markdown_links_every_item_found() {
+        let expected = r"# Report on alice in o/r from 2024-01-01 to 2024-02-01
+
+## About
+
+Tools for <fast> & safe builds.
+
+## Summary
+
+Alice made the client robust.
+
+## Commits (2 found)
+
+- [Add \[rate\] limit handling](https://github.com/o/r/commit/a1) by alice (2024-01-03)
+  Waits out rate limits.
+- [https://github.com/o/r/commit/b2](https://github.com/o/r/commit/b2)
+
+## Issues (1 found)
+
+- [Crash on empty input](https://github.com/o/r/issues/41) by alice (2024-01-03)
+  Reported a crash.
+
+## Pull requests (0 found)
+
+None found.
+
+## Reviews
+
+| Reviewer | Pull requests | Approvals | Change requests | Comment reviews | Review comments | Average turnaround |
+| --- | --- | --- | --- | --- | --- | --- |
+| bob | 1 | 1 | 0 | 0 | 2 | 24.0 hours |
+";
+        assert_eq!(sample_report().to_markdown(), expected);
+    }
+
+    #[test]
+    fn report_html_is_escaped_and_wrapped_in_a_page() {
+        let report = sample_report();
+        let fragment = report.to_html_fragment();
+        assert!(fragment.starts_with(
+            "<h1>Report on alice in o/r from 2024-01-01 to 2024-02-01</h1>\n<h2>About</h2>\n<p>Tools for &lt;fast&gt; &amp; safe builds.</p>\n"
+        ));
+        assert!(fragment.contains(
+            "<li><a href=\"https://github.com/o/r/commit/a1\">Add [rate] limit handling</a> <span class=\"byline\">by alice (2024-01-03)</span><p>Waits out rate limits.</p></li>\n"
+        ));
+        assert!(fragment.contains(
+            "<tr><td>bob</td><td>1</td><td>1</td><td>0</td><td>0</td><td>2</td><td>24.0 hours</td></tr>\n"
+        ));
+        assert!(!fragment.contains("<fast>"));
+
+        let page = report.to_html();
+        assert!(page.starts_with("<!DOCTYPE html>\n"));
+        assert!(
+            page.contains("<title>Report on alice in o/r from 2024-01-01 to 2024-02-01</title>")
+        );
+        assert!(page.ends_with(&format!("<body>\n{fragment}</body>\n</html>\n")));
+    }
+
+    #[test]
+    fn report_json_keeps_every_item() {
+        let json = sample_report().to_json();
+        let report = serde_json::from_str::<Report>(&json).unwrap();
+        assert_eq!(report.commits.found.len(), 2);
+        let commit = &report.commits.items[0];
+        assert_eq!(commit.tag_line, "Add [rate] limit handling\n\nDetails.");
+        assert_eq!(commit.payload, "Waits out rate limits.");
+        assert_eq!(
+            report.issues.items[0].source_url,
+            "https://github.com/o/r/issues/41"
+        );
+        assert_eq!(report.reviews[0].turnaround_hours, [24.0]);
+        assert_eq!(report.window, sample_report().window);
+    }
+
+    #[test]
+    fn empty_reports_say_so() {
+        let report = Report {
+            owner: "o".to_string(),
+            repo: "r".to_string(),
+            user_name: Some("alice".to_string()),
+            rate_limited: true,
+            ..Default::default()
+        };
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("# Report on alice in o/r\n\nNo useful data found for alice"));
+        assert!(!markdown.contains("## Commits"));
+        // The note is part of the text here, not a block of its own.
+        assert!(markdown.ends_with(&format!("\n{RATE_LIMITED_NOTE}\n")));
+    }
+
+    #[test]
+    fn org_reports_list_repos_in_a_table() {
+        let org = OrgReport {
+            owner: "o".to_string(),
+            reports: vec![sample_report()],
+            quiet: vec!["sleepy".to_string()],
+            failed: vec!["broken".to_string()],
+            synthesis: Some("One busy repo.".to_string()),
+            ..Default::default()
+        };
+        let markdown = org.to_markdown();
+        assert!(markdown.starts_with("# Report on o\n\n## Summary\n\nOne busy repo.\n\n"));
+        assert!(markdown.contains(
+            "| Repository | Commits | Issues | Pull requests | Reviews | Discussions |\n| --- | --- | --- | --- | --- | --- |\n| r | 2 | 1 | 0 | 1 | 0 |\n"
+        ));
+        assert!(markdown.contains("## o/r\n\nAlice made the client robust.\n"));
+        assert!(markdown.ends_with("> No activity in: sleepy.\n\n> Could not report on: broken.\n"));
+
+        let html = org.to_html_fragment();
+        assert!(
+            html.contains("<tr><td>r</td><td>2</td><td>1</td><td>0</td><td>1</td><td>0</td></tr>")
+        );
+    }
+
+    #[test]
+    fn activity_reports_show_items_with_their_state() {
+        let item = |title: &str, state: &str| ContributionItem {
+            title: title.to_string(),
+            url: format!("https://github.com/o/r/{}", title.to_lowercase()),
+            state: state.to_string(),
+            occurred_at: Utc.with_ymd_and_hms(2024, 1, 5, 12, 0, 0).unwrap(),
+        };
+        let report = ActivityReport {
+            user_name: "alice".to_string(),
+            history: ContributionHistory {
+                login: "alice".to_string(),
+                repos: vec![RepoContributions {
+                    name_with_owner: "o/r".to_string(),
+                    commits: 4,
+                    issue_count: 1,
+                    issues: vec![item("Crash", "CLOSED")],
+                    review_count: 1,
+                    reviews: vec![item("Refactor", "CHANGES_REQUESTED")],
+                    ..Default::default()
+                }],
+                restricted_contributions: 3,
+                ..Default::default()
+            },
+            summary: Some("Busy week.".to_string()),
+            ..Default::default()
+        };
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| o/r | 4 | 1 | 0 | 1 |\n"));
+        assert!(markdown.contains(
+            "### o/r\n\n- [Crash](https://github.com/o/r/crash) (issue, closed, 2024-01-05)\n- [Refactor](https://github.com/o/r/refactor) (review, changes requested, 2024-01-05)\n"
+        ));
+        assert!(markdown.ends_with("> 3 contributions to private repositories are not shown.\n"));
+    }
+
+    #[test]
+    fn contributor_profiles_mark_the_project_languages() {
+        let skill = |language: &str, share: f64| LanguageSkill {
+            language: language.to_string(),
+            bytes: 1000,
+            repos: 2,
+            share,
+        };
+        let profile = ContributorProfile {
+            owner: "o".to_string(),
+            repo: "r".to_string(),
+            user_name: "alice".to_string(),
+            user_profile: "USER PROFILE\nalice writes Rust\nBased in Lisbon".to_string(),
+            project_languages: vec!["Rust".to_string()],
+            portfolio: LanguagePortfolio {
+                login: "alice".to_string(),
+                repos: vec![PortfolioRepo {
+                    name_with_owner: "alice/tool".to_string(),
+                    url: "https://github.com/alice/tool".to_string(),
+                    stars: 12,
+                    owned: true,
+                    languages: vec![("Rust".to_string(), 1000)],
+                    ..Default::default()
+                }],
+                skills: vec![skill("Rust", 0.8), skill("Go", 0.15), skill("Make", 0.005)],
+            },
+            analysis: "A good fit.".to_string(),
+            ..Default::default()
+        };
+        let markdown = profile.to_markdown();
+        assert!(markdown.starts_with(
+            "# Contributor profile of alice for o/r\n\n## Fit with o/r\n\nA good fit.\n\n## Profile\n\nalice writes Rust\n\nBased in Lisbon\n\n"
+        ));
+        assert!(markdown.contains("| Rust | 80% | 2 | yes |\n| Go | 15% | 2 |  |\n"));
+        assert!(!markdown.contains("Make"));
+        assert!(markdown
+            .contains("- [alice/tool](https://github.com/alice/tool) (owned, 12 stars, Rust)\n"));
+    }
+
+    #[test]
+    fn portfolio_html_is_escaped_and_titled() {
+        let portfolio = LanguagePortfolio {
+            login: "alice".to_string(),
+            repos: vec![PortfolioRepo {
+                name_with_owner: "alice/<tool>".to_string(),
+                url: "https://github.com/alice/tool".to_string(),
+                stars: 12,
+                owned: true,
+                languages: vec![("Rust".to_string(), 1000)],
+                ..Default::default()
+            }],
+            skills: vec![LanguageSkill {
+                language: "Rust".to_string(),
+                bytes: 1000,
+                repos: 1,
+                share: 1.0,
+            }],
+        };
+        let fragment = portfolio.to_html_fragment();
+        assert!(fragment.starts_with("<h1>Languages of alice</h1>\n"));
+        assert!(fragment.contains("<td>Rust</td><td>100%</td><td>1</td>"));
+        assert!(fragment.contains("alice/&lt;tool&gt;"));
+        assert!(!fragment.contains("<tool>"));
+
+        let page = portfolio.to_html();
+        assert!(page.contains("<title>Languages of alice</title>"));
+        assert!(page.ends_with(&format!("<body>\n{fragment}</body>\n</html>\n")));
+    }
+}
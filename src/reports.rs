@@ -1,102 +1,795 @@
-/* use crate::data_analyzers::*;
+use crate::data_analyzers::*;
+use crate::error::{ResearchError, ResearchResult};
 use crate::github_data_fetchers::*;
-use chrono::{Duration, Utc};
+use crate::llm::LlmBackend;
+use crate::time_window::TimeWindow;
+use crate::transport::GitHubTransport;
+use chrono::Duration;
 use log;
-use slack_flows::send_message_to_channel;
-pub async fn weekly_report(
-    owner: &str,
-    repo: &str,
-    user_name: Option<&str>,
-    key: Option<&str>,
-) -> Option<String> {
-    let key = match key {
-        Some(key) => key,
-        None => "contributors",
+use serde::{Deserialize, Serialize};
+
+pub(crate) const RATE_LIMITED_NOTE: &str =
+    "Note: partial data due to rate limiting, GitHub stopped answering before all items were fetched.";
+
+pub(crate) const SUMMARY_UNAVAILABLE_NOTE: &str =
+    "Summary unavailable, the model gave no answer. The sections list what was found.";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ReportSection {
+    pub found: Vec<String>,
+    pub items: Vec<GitMemory>,
+    pub summary: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Report {
+    pub owner: String,
+    pub repo: String,
+    pub user_name: Option<String>,
+    pub window: Option<TimeWindow>,
+    pub profile: Option<GitMemory>,
+    pub commits: ReportSection,
+    pub issues: ReportSection,
+    pub pull_requests: ReportSection,
+    pub reviews: Vec<ReviewerActivity>,
+    pub discussions: ReportSection,
+    pub synthesis: Option<String>,
+    pub rate_limited: bool,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.commits.summary.is_empty()
+            && self.issues.summary.is_empty()
+            && self.pull_requests.summary.is_empty()
+            && self.reviews.is_empty()
+            && self.discussions.summary.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            let text = match &self.user_name {
+                Some(user_name) => format!("No useful data found for {user_name}, you may try `/search` to find out more about {user_name}"),
+                None => "No useful data found, nothing to report".to_string(),
+            };
+            return match self.rate_limited {
+                true => format!("{text}\n{}", RATE_LIMITED_NOTE),
+                false => text,
+            };
+        }
+
+        let mut report = vec![
+            format!(
+                "found {} commits:\n{}",
+                self.commits.found.len(),
+                self.commits.found.join("\n")
+            ),
+            format!(
+                "found {} issues:\n{}",
+                self.issues.found.len(),
+                self.issues.found.join("\n")
+            ),
+            format!(
+                "found {} pull requests:\n{}",
+                self.pull_requests.found.len(),
+                self.pull_requests.found.join("\n")
+            ),
+        ];
+        if !self.reviews.is_empty() {
+            report.push(summarize_review_activity(&self.reviews));
+        }
+        if !self.discussions.items.is_empty() {
+            report.push(format!(
+                "{} discussions were referenced in analysis:\n {}",
+                self.discussions.found.len(),
+                self.discussions.found.join("\n")
+            ));
+        }
+        match &self.synthesis {
+            Some(final_summary) => report.push(final_summary.clone()),
+            None => report.push(SUMMARY_UNAVAILABLE_NOTE.to_string()),
+        }
+        if self.rate_limited {
+            report.push(RATE_LIMITED_NOTE.to_string());
+        }
+        report.join("\n")
+    }
+}
+
+// How a user could fit a project, from `ReportEngine::contributor_profile`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ContributorProfile {
+    pub owner: String,
+    pub repo: String,
+    pub user_name: String,
+    pub window: Option<TimeWindow>,
+    pub user_profile: String,
+    // The project's languages, most used first.
+    pub project_languages: Vec<String>,
+    pub portfolio: LanguagePortfolio,
+    pub issues: String,
+    pub discussions: Vec<GitMemory>,
+    pub analysis: String,
+    pub rate_limited: bool,
+}
+
+// What a user did across GitHub in a time window, from `activity_report`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ActivityReport {
+    pub user_name: String,
+    pub window: Option<TimeWindow>,
+    pub history: ContributionHistory,
+    pub summary: Option<String>,
+    pub rate_limited: bool,
+}
+
+// Gathers the user's contributions to public repos in `window`, grouped by repo, and
+// summarizes them. Fails when GitHub doesn't know the user.
+pub async fn activity_report(
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
+    user_name: &str,
+    window: TimeWindow,
+) -> ResearchResult<ActivityReport> {
+    github.reset_rate_limited();
+    let history = get_contribution_history(github, user_name, &window).await?;
+    let summary = match history.is_empty() {
+        true => None,
+        false => match summarize_user_activity(llm, user_name, &history.to_text()).await {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                log::error!("failed to summarize the activity of {}: {}", user_name, e);
+                None
+            }
+        },
     };
-    let (success_or_fail, contributor_count) = populate_contributors(owner, repo).await;
-    if !success_or_fail {
-        log::error!("weekly_report, failed to populate contributors");
+    Ok(ActivityReport {
+        user_name: user_name.to_string(),
+        window: Some(window),
+        history,
+        summary,
+        rate_limited: github.was_rate_limited(),
+    })
+}
+
+// Which of an owner's repos go into an org report. An explicit list of repo names is
+// used as is; otherwise the owner's repos are listed and filtered by topic, archived
+// ones left out unless asked for.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RepoFilter {
+    pub topic: Option<String>,
+    pub include_archived: bool,
+    pub repos: Vec<String>,
+}
+
+impl RepoFilter {
+    fn matches(&self, repo: &OwnerRepo) -> bool {
+        (self.include_archived || !repo.is_archived)
+            && self
+                .topic
+                .as_ref()
+                .is_none_or(|topic| repo.topics.iter().any(|t| t.eq_ignore_ascii_case(topic)))
+    }
+}
+
+// Activity across several repos of one owner, from `org_report`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct OrgReport {
+    pub owner: String,
+    pub user_name: Option<String>,
+    pub window: Option<TimeWindow>,
+    // The repos with activity in the window, each with its own synthesis.
+    pub reports: Vec<Report>,
+    // Repos without activity, and repos that couldn't be reported on.
+    pub quiet: Vec<String>,
+    pub failed: Vec<String>,
+    pub synthesis: Option<String>,
+    pub rate_limited: bool,
+}
+
+// Caps the repos in an org report at `org_max_repos`, 50 by default.
+fn org_max_repos() -> usize {
+    std::env::var("org_max_repos")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(50)
+}
+
+// Joins one summary per repo, each cut to an equal share of `chars` so the first repos
+// don't crowd out the rest when the whole is trimmed for the LLM.
+fn roll_up(parts: &[(&str, String)], chars: usize) -> Option<String> {
+    let parts = parts
+        .iter()
+        .filter(|(_, text)| !text.is_empty())
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
         return None;
     }
+    let share = chars / parts.len();
+    Some(
+        parts
+            .iter()
+            .map(|(repo, text)| {
+                format!("[{repo}] {}", text.chars().take(share).collect::<String>())
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
 
-    let has_user_name = user_name.is_some();
+// Reports on the owner's repos picked by `filter` one after another, then relates the
+// repos' commits, issues, pull requests, reviews and discussions to each other. Fails
+// when GitHub doesn't know the owner or no repo is left after filtering.
+pub async fn org_report(
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
+    owner: &str,
+    filter: &RepoFilter,
+    user_name: Option<String>,
+    window: TimeWindow,
+) -> ResearchResult<OrgReport> {
+    github.reset_rate_limited();
+    let mut repo_names = match filter.repos.is_empty() {
+        true => get_owner_repos(github, owner)
+            .await?
+            .into_iter()
+            .filter(|repo| filter.matches(repo))
+            .map(|repo| repo.name)
+            .collect::<Vec<_>>(),
+        false => filter.repos.clone(),
+    };
+    if repo_names.is_empty() {
+        return Err(ResearchError::NoData(format!(
+            "repos of {owner} to report on"
+        )));
+    }
+    let max_repos = org_max_repos();
+    if repo_names.len() > max_repos {
+        log::warn!(
+            "reporting on {} of the {} repos of {}",
+            max_repos,
+            repo_names.len(),
+            owner
+        );
+        repo_names.truncate(max_repos);
+    }
 
-    let is_code_contributor = is_code_contributor(owner, repo, user_name.unwrap()).await;
+    let mut org = OrgReport {
+        owner: owner.to_string(),
+        user_name: user_name.clone(),
+        window: Some(window),
+        rate_limited: github.was_rate_limited(),
+        ..Default::default()
+    };
+    for repo in repo_names {
+        let engine = ReportEngine::new(github, llm, owner, &repo)
+            .with_user(user_name.clone())
+            .with_window(window);
+        let result = engine.run().await;
+        // Each run starts with a clean flag, so collect it per repo.
+        org.rate_limited |= engine.rate_limited();
+        match result {
+            Ok(report) if report.is_empty() => org.quiet.push(repo),
+            Ok(report) => org.reports.push(report),
+            Err(e) => {
+                log::error!("report on {}/{} failed: {}", owner, repo, e);
+                org.failed.push(repo);
+            }
+        }
+    }
 
-    // if has_user_name && is_code_contributor {
-    //     return new_contributor_report(owner, repo, user_name.unwrap()).await;
-    // }
+    if !org.reports.is_empty() {
+        // The same shares of the budget `correlate_commits_issues_discussions` gives each kind.
+        let chars = |ratio: f32| correlation_section_chars(llm, ratio, TOTAL_RATIO);
+        let parts = |section: fn(&Report) -> String| {
+            org.reports
+                .iter()
+                .map(|r| (r.repo.as_str(), section(r)))
+                .collect::<Vec<_>>()
+        };
+        let profile_data = format!(
+            "Activity in {} repos of {}: {}",
+            org.reports.len(),
+            owner,
+            org.reports
+                .iter()
+                .map(|r| r.repo.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let commits = roll_up(&parts(|r| r.commits.summary.clone()), chars(COMMIT_RATIO));
+        let issues = roll_up(&parts(|r| r.issues.summary.clone()), chars(ISSUE_RATIO));
+        let pull_requests = roll_up(
+            &parts(|r| r.pull_requests.summary.clone()),
+            chars(PULL_REQUEST_RATIO),
+        );
+        let reviews = roll_up(
+            &parts(|r| match r.reviews.is_empty() {
+                true => String::new(),
+                false => summarize_review_activity(&r.reviews),
+            }),
+            chars(REVIEW_RATIO),
+        );
+        let discussions = roll_up(
+            &parts(|r| r.discussions.summary.clone()),
+            chars(DISCUSSION_RATIO),
+        );
 
-    // if has_user_name && !is_code_contributor {
-    //     return current_contributor_report(owner, repo, user_name.unwrap()).await;
-    // }
+        let summaries = ActivitySummaries {
+            profile: Some(&profile_data),
+            commits: commits.as_deref(),
+            issues: issues.as_deref(),
+            pull_requests: pull_requests.as_deref(),
+            reviews: reviews.as_deref(),
+            discussions: discussions.as_deref(),
+        };
+        match correlate_commits_issues_discussions(llm, &summaries, user_name.as_deref()).await {
+            Ok(synthesis) => org.synthesis = Some(synthesis),
+            Err(e) => log::error!("failed to synthesize the report on {}: {}", owner, e),
+        }
+    }
+    Ok(org)
+}
+
+pub struct ReportEngine<'a> {
+    github: &'a dyn GitHubTransport,
+    llm: &'a dyn LlmBackend,
+    owner: String,
+    repo: String,
+    user_name: Option<String>,
+    window: TimeWindow,
+    discussion_lookback_days: i64,
+    concurrency: usize,
+}
 
-    // if !has_user_name {
-    //     return current_repo_report(owner, repo).await;
-    // }
-    None // This is the default return for when user_name is None
+// How many items are summarized at once, `analysis_concurrency` or 4 by default.
+fn analysis_concurrency() -> usize {
+    std::env::var("analysis_concurrency")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(4)
 }
 
-pub async fn new_contributor_report(github_token: &str, owner: &str, repo: &str, user_name: &str) -> Option<String> {
-    let mut home_repo_data = get_readme(owner, repo).await.unwrap_or("".to_string());
-    match get_community_profile_data(owner, repo).await {
-        Some(community_profile_data) => {
-            home_repo_data.push_str(&community_profile_data);
+impl<'a> ReportEngine<'a> {
+    pub fn new(
+        github: &'a dyn GitHubTransport,
+        llm: &'a dyn LlmBackend,
+        owner: &str,
+        repo: &str,
+    ) -> Self {
+        ReportEngine {
+            github,
+            llm,
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            user_name: None,
+            window: TimeWindow::last_days(7),
+            discussion_lookback_days: 30,
+            concurrency: analysis_concurrency(),
         }
-        None => {}
-    };
-    send_message_to_channel("ik8", "ch_home", home_repo_data.clone()).await;
-    let user_profile = get_user_data_by_login(user_name)
+    }
+
+    pub fn with_user(mut self, user_name: Option<String>) -> Self {
+        self.user_name = user_name;
+        self
+    }
+
+    pub fn with_days(mut self, n_days: i64) -> Self {
+        self.window = TimeWindow::last_days(n_days);
+        self
+    }
+
+    pub fn with_window(mut self, window: TimeWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    // Discussions are searched from this many days before the window starts,
+    // since long-running threads are often only updated sporadically.
+    pub fn with_discussion_lookback(mut self, n_days: i64) -> Self {
+        self.discussion_lookback_days = n_days;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn window(&self) -> &TimeWindow {
+        &self.window
+    }
+
+    // Whether any request so far was cut short by GitHub's rate limits.
+    pub fn rate_limited(&self) -> bool {
+        self.github.was_rate_limited()
+    }
+
+    pub fn new_report(&self) -> Report {
+        Report {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            user_name: self.user_name.clone(),
+            window: Some(self.window),
+            ..Default::default()
+        }
+    }
+
+    // Fails with `ResearchError::NotFound` when the repo doesn't exist or is private.
+    pub async fn profile(&self) -> ResearchResult<GitMemory> {
+        is_valid_owner_repo_integrated(self.github, self.llm, &self.owner, &self.repo).await
+    }
+
+    pub async fn is_code_contributor(&self) -> bool {
+        match &self.user_name {
+            Some(user_name) => {
+                is_code_contributor(self.github, &self.owner, &self.repo, user_name).await
+            }
+            None => false,
+        }
+    }
+
+    pub async fn commits(&self) -> ReportSection {
+        let mut section = ReportSection::default();
+        match get_commits_in_range(
+            self.github,
+            &self.owner,
+            &self.repo,
+            self.user_name.clone(),
+            self.window.since,
+            self.window.until,
+        )
         .await
-        .unwrap_or("".to_string());
-    send_message_to_channel("ik8", "ch_pro", user_profile.clone()).await;
-
-    let now = Utc::now();
-    let a_week_ago = now - Duration::days(7);
-    let a_week_ago_str = a_week_ago.format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    // current search result may include issues the user interacted much earlier but updated recently
-    // may need to do 2 separate searches: "commenter:juntao updated:>2023-07-30T02:49:06Z"
-    let issue_query = format!("involves:{user_name} updated:>{a_week_ago_str}");
-    let issues_data = search_issue(&issue_query).await.unwrap_or("".to_string());
-    let mut repos_data = String::new();
-
-    for language in vec!["rust", "javascript", "cpp", "go"] {
-        let temp = get_user_repos_gql(user_name, language)
-            .await
-            .unwrap_or("".to_string());
-        repos_data.push_str(&temp);
-    }
-    send_message_to_channel("ik8", "ch_rep", repos_data.clone()).await;
-
-    let discussion_query = format!("involves:{user_name} updated:>{a_week_ago_str}");
-    let (_, discussion_vec) = search_discussions(&discussion_query).await.unwrap();
-
-    let (discussion_data, _) = analyze_discussions(discussion_vec, Some(user_name)).await;
-    send_message_to_channel("ik8", "ch_dis", discussion_data.clone()).await;
-
-    return correlate_user_and_home_project(
-        &home_repo_data,
-        &user_profile,
-        &issues_data,
-        &repos_data,
-        &discussion_data,
-    )
-    .await;
-}
-pub async fn current_contributor_report(
-    owner: &str,
-    repo: &str,
-    user_name: &str,
-) -> Option<String> {
-    let now = Utc::now();
-    let a_week_ago = now - Duration::days(7);
-    let a_week_ago_str = a_week_ago.format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    // let issue_query = format!("involves:{user_name} updated:>{a_week_ago_str}");
-    // let issues_data = search_issue(&issue_query).await.unwrap_or("".to_string());
+        {
+            Ok((count, mut commits_vec)) => {
+                section.found = commits_vec
+                    .iter()
+                    .map(|com| com.source_url.to_owned())
+                    .collect();
+
+                if count > 0 {
+                    match process_commits(self.github, self.llm, &mut commits_vec, self.concurrency)
+                        .await
+                    {
+                        Ok(summary) => section.summary = summary,
+                        Err(e) => log::error!("processing commits failed: {}", e),
+                    }
+                }
+                section.items = commits_vec;
+            }
+            Err(e) => log::error!("failed to get commits: {}", e),
+        }
+        section
+    }
+
+    pub async fn issues(&self) -> ReportSection {
+        let mut section = ReportSection::default();
+        match get_issues_in_range(
+            self.github,
+            &self.owner,
+            &self.repo,
+            self.user_name.clone(),
+            self.window.since,
+            self.window.until,
+        )
+        .await
+        {
+            Ok((count, issue_vec)) => {
+                section.found = issue_vec
+                    .iter()
+                    .map(|issue| issue.html_url.to_owned())
+                    .collect();
 
-    Some("".to_string())
+                if count > 0 {
+                    match process_issues(
+                        self.github,
+                        self.llm,
+                        issue_vec,
+                        self.user_name.clone(),
+                        self.concurrency,
+                    )
+                    .await
+                    {
+                        Ok((summary, _, issues_vec)) => {
+                            section.summary = summary;
+                            section.items = issues_vec;
+                        }
+                        Err(e) => log::error!("processing issues failed: {}", e),
+                    }
+                }
+            }
+            Err(e) => log::error!("failed to get issues: {}", e),
+        }
+        section
+    }
+
+    // The pull requests section and the review activity, from the same search.
+    pub async fn pull_requests_and_reviews(&self) -> (ReportSection, Vec<ReviewerActivity>) {
+        let mut section = ReportSection::default();
+        let mut reviews = vec![];
+        match get_pull_requests_and_reviews(
+            self.github,
+            &self.owner,
+            &self.repo,
+            self.user_name.clone(),
+            self.window.since,
+            self.window.until,
+        )
+        .await
+        {
+            Ok((pr_vec, activity)) => {
+                reviews = activity;
+                section.found = pr_vec.iter().map(|pr| pr.url.to_owned()).collect();
+
+                if !pr_vec.is_empty() {
+                    match process_pull_requests(
+                        self.llm,
+                        pr_vec,
+                        self.user_name.clone(),
+                        self.concurrency,
+                    )
+                    .await
+                    {
+                        Ok((summary, _, pr_memories)) => {
+                            section.summary = summary;
+                            section.items = pr_memories;
+                        }
+                        Err(e) => log::error!("processing pull requests failed: {}", e),
+                    }
+                }
+            }
+            Err(e) => log::error!("failed to get pull requests and reviews: {}", e),
+        }
+        (section, reviews)
+    }
+
+    pub async fn discussions(&self) -> ReportSection {
+        let mut section = ReportSection::default();
+        let lookback_window = TimeWindow::new(
+            self.window.since - Duration::days(self.discussion_lookback_days),
+            self.window.until,
+        );
+        let range_str = lookback_window.search_range();
+        let (owner, repo) = (&self.owner, &self.repo);
+        let discussion_query = match &self.user_name {
+            Some(user_name) => {
+                format!("repo:{owner}/{repo} involves: {user_name} updated:{range_str}")
+            }
+            None => format!("repo:{owner}/{repo} updated:{range_str}"),
+        };
+        match search_discussions_integrated(
+            self.github,
+            self.llm,
+            &discussion_query,
+            &self.user_name,
+        )
+        .await
+        {
+            Ok((summary, discussion_vec)) => {
+                section.found = discussion_vec
+                    .iter()
+                    .map(|discussion| discussion.source_url.to_owned())
+                    .collect();
+                section.summary = summary;
+                section.items = discussion_vec;
+            }
+            Err(e) => log::error!("failed to get discussions: {}", e),
+        }
+        section
+    }
+
+    // Relates the user's profile, the languages of the repos they own or contribute to and
+    // the issues and discussions they took part in anywhere on GitHub during the window to
+    // what the project is about and is written in. Fails when there is no user or GitHub
+    // doesn't know them.
+    pub async fn contributor_profile(&self) -> ResearchResult<ContributorProfile> {
+        let user_name = self
+            .user_name
+            .clone()
+            .ok_or_else(|| ResearchError::InvalidInput("user name".to_string()))?;
+        self.github.reset_rate_limited();
+        let user_profile = get_user_data_by_login(self.github, &user_name).await?;
+        let range_str = self.window.search_range();
+        let (owner, repo) = (&self.owner, &self.repo);
+
+        let (home_repo_data, project_languages, portfolio, issues, discussions) = futures::join!(
+            async {
+                let mut home_repo_data = String::new();
+                match get_community_profile_data(self.github, owner, repo).await {
+                    Ok(community_profile_data) => {
+                        home_repo_data.push_str(&format!("{community_profile_data}\n"))
+                    }
+                    Err(e) => log::error!("failed to get the community profile: {}", e),
+                }
+                match get_readme(self.github, owner, repo).await {
+                    Ok(readme) => home_repo_data.push_str(&readme),
+                    Err(e) => log::error!("failed to get the readme: {}", e),
+                }
+                home_repo_data
+            },
+            async {
+                get_repo_languages(self.github, owner, repo)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("failed to get the languages of {}/{}: {}", owner, repo, e);
+                        vec![]
+                    })
+            },
+            async {
+                get_user_language_portfolio(self.github, &user_name)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("failed to get the portfolio of {}: {}", user_name, e);
+                        LanguagePortfolio::default()
+                    })
+            },
+            async {
+                let issue_query = format!("involves:{user_name} updated:{range_str}");
+                search_issue(self.github, &issue_query)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("failed to search issues: {}", e);
+                        String::new()
+                    })
+            },
+            async {
+                let discussion_query = format!("involves:{user_name} updated:{range_str}");
+                match search_discussions_integrated(
+                    self.github,
+                    self.llm,
+                    &discussion_query,
+                    &self.user_name,
+                )
+                .await
+                {
+                    Ok((_, discussion_vec)) => discussion_vec,
+                    Err(e) => {
+                        log::error!("failed to get discussions: {}", e);
+                        vec![]
+                    }
+                }
+            }
+        );
+
+        let discussion_data = discussions
+            .iter()
+            .map(|gm| format!("{} {}\n", gm.source_url, gm.payload))
+            .collect::<String>();
+        let repos_data = format!(
+            "The project is written in: {}\n{}",
+            project_languages.join(", "),
+            portfolio.to_text()
+        );
+        let analysis = correlate_user_and_home_project(
+            self.llm,
+            &home_repo_data,
+            &user_profile,
+            &issues,
+            &repos_data,
+            &discussion_data,
+        )
+        .await?;
+
+        Ok(ContributorProfile {
+            owner: owner.clone(),
+            repo: repo.clone(),
+            user_name,
+            window: Some(self.window),
+            user_profile,
+            project_languages,
+            portfolio,
+            issues,
+            discussions,
+            analysis,
+            rate_limited: self.rate_limited(),
+        })
+    }
+
+    pub async fn synthesize(&self, report: &Report) -> ResearchResult<String> {
+        if report.is_empty() {
+            return Err(ResearchError::NoData(format!(
+                "activity in {}/{}",
+                self.owner, self.repo
+            )));
+        }
+        let profile_data = report
+            .profile
+            .as_ref()
+            .map(|gm| format!("About {}/{}: {}", self.owner, self.repo, gm.payload))
+            .unwrap_or_default();
+
+        let reviews = summarize_review_activity(&report.reviews);
+        let summaries = ActivitySummaries {
+            profile: Some(&profile_data),
+            commits: Some(&report.commits.summary),
+            issues: Some(&report.issues.summary),
+            pull_requests: Some(&report.pull_requests.summary),
+            reviews: Some(&reviews),
+            discussions: Some(&report.discussions.summary),
+        };
+        correlate_commits_issues_discussions(self.llm, &summaries, self.user_name.as_deref()).await
+    }
+
+    // Runs the section steps concurrently after the profile; only a failing
+    // profile step aborts the report.
+    pub async fn run(&self) -> ResearchResult<Report> {
+        let mut report = self.new_report();
+        self.github.reset_rate_limited();
+        report.profile = Some(self.profile().await?);
+        (
+            report.commits,
+            report.issues,
+            (report.pull_requests, report.reviews),
+            report.discussions,
+        ) = futures::join!(
+            self.commits(),
+            self.issues(),
+            self.pull_requests_and_reviews(),
+            self.discussions()
+        );
+        report.synthesis = self.synthesize(&report).await.ok();
+        report.rate_limited = self.rate_limited();
+        Ok(report)
+    }
 }
-pub async fn current_repo_report(github_token: &str, owner: &str, repo: &str) -> Option<String> {
-    Some("".to_string())
+
+// A report on o/r with an analyzed commit and issue, a commit that was only found and one
+// reviewer, for the renderer and sink tests.
+#[cfg(test)]
+pub(crate) fn sample_report() -> Report {
+    let memory = |url: &str, tag_line: &str, payload: &str| GitMemory {
+        memory_type: MemoryType::Commit,
+        name: "alice".to_string(),
+        tag_line: tag_line.to_string(),
+        source_url: url.to_string(),
+        payload: payload.to_string(),
+        date: chrono::NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+    };
+    let section = |found: &[&str], items: Vec<GitMemory>| ReportSection {
+        found: found.iter().map(|url| url.to_string()).collect(),
+        summary: items.iter().map(|gm| gm.payload.clone()).collect(),
+        items,
+    };
+    let window = TimeWindow::month(2024, 1).unwrap();
+
+    Report {
+        owner: "o".to_string(),
+        repo: "r".to_string(),
+        user_name: Some("alice".to_string()),
+        window: Some(window),
+        profile: Some(memory(
+            "https://github.com/o/r",
+            "",
+            "Tools for <fast> & safe builds.",
+        )),
+        commits: section(
+            &[
+                "https://github.com/o/r/commit/a1",
+                "https://github.com/o/r/commit/b2",
+            ],
+            vec![memory(
+                "https://github.com/o/r/commit/a1",
+                "Add [rate] limit handling\n\nDetails.",
+                "Waits out rate limits.",
+            )],
+        ),
+        issues: section(
+            &["https://github.com/o/r/issues/41"],
+            vec![memory(
+                "https://github.com/o/r/issues/41",
+                "Crash on empty input",
+                "Reported a crash.",
+            )],
+        ),
+        reviews: vec![ReviewerActivity {
+            login: "bob".to_string(),
+            approvals: 1,
+            review_comments: 2,
+            pull_requests: vec!["https://github.com/o/r/pull/8".to_string()],
+            turnaround_hours: vec![24.0],
+            ..Default::default()
+        }],
+        synthesis: Some("Alice made the client robust.".to_string()),
+        ..Default::default()
+    }
 }
- */
@@ -0,0 +1,360 @@
+// Scheduled reports: the repos subscribed to with `/subscribe`, kept in the store, and the
+// cron schedule they are reported on. Each run covers the time since the previous one.
+use crate::error::{ResearchError, ResearchResult};
+use crate::llm::LlmBackend;
+use crate::reports::ReportEngine;
+use crate::sinks::{deliver, ReportSink};
+use crate::store::{get, set};
+use crate::time_window::TimeWindow;
+use crate::transport::GitHubTransport;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+const SUBSCRIPTIONS_KEY: &str = "report_subscriptions";
+const DEFAULT_SCHEDULE: &str = "0 9 * * 1";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Subscription {
+    pub owner: String,
+    pub repo: String,
+    pub user_name: Option<String>,
+    // When the last scheduled report was made, the start of the next one.
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+impl Subscription {
+    fn is(&self, owner: &str, repo: &str, user_name: Option<&str>) -> bool {
+        self.owner.eq_ignore_ascii_case(owner)
+            && self.repo.eq_ignore_ascii_case(repo)
+            && match (&self.user_name, user_name) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.user_name {
+            Some(user_name) => format!("{user_name} in {}/{}", self.owner, self.repo),
+            None => format!("{}/{}", self.owner, self.repo),
+        }
+    }
+}
+
+pub fn subscriptions() -> Vec<Subscription> {
+    get(SUBSCRIPTIONS_KEY)
+        .and_then(|val| serde_json::from_value(val).ok())
+        .unwrap_or_default()
+}
+
+fn save_subscriptions(subscriptions: &[Subscription]) {
+    set(
+        SUBSCRIPTIONS_KEY,
+        serde_json::to_value(subscriptions).unwrap_or_default(),
+        None,
+    );
+}
+
+// Returns false when the subscription already exists.
+pub fn subscribe(owner: &str, repo: &str, user_name: Option<&str>) -> bool {
+    let mut all = subscriptions();
+    if all.iter().any(|s| s.is(owner, repo, user_name)) {
+        return false;
+    }
+    all.push(Subscription {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        user_name: user_name.map(str::to_string),
+        last_run: None,
+    });
+    save_subscriptions(&all);
+    true
+}
+
+// Returns false when there was no such subscription.
+pub fn unsubscribe(owner: &str, repo: &str, user_name: Option<&str>) -> bool {
+    let mut all = subscriptions();
+    let before = all.len();
+    all.retain(|s| !s.is(owner, repo, user_name));
+    if all.len() == before {
+        return false;
+    }
+    save_subscriptions(&all);
+    true
+}
+
+// Re-reads the subscriptions so ones added or removed during a run are kept.
+fn record_run(done: &Subscription, at: DateTime<Utc>) {
+    let mut all = subscriptions();
+    if let Some(s) = all
+        .iter_mut()
+        .find(|s| s.is(&done.owner, &done.repo, done.user_name.as_deref()))
+    {
+        s.last_run = Some(at);
+        save_subscriptions(&all);
+    }
+}
+
+// A five-field cron expression: minute, hour, day of month, month and day of week (0 or 7
+// is Sunday), each `*`, a number, a range `a-b`, a step `*/n` or `a-b/n`, or a list of
+// those. Times are UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Cron matches either day field when neither starts with `*`, so `*/2` still restricts
+    // the days together with the other field.
+    any_day: bool,
+}
+
+impl Schedule {
+    pub fn parse(expr: &str) -> ResearchResult<Self> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let invalid = || ResearchError::InvalidInput(format!("cron schedule `{expr}`"));
+        if fields.len() != 5 {
+            return Err(invalid());
+        }
+        let field =
+            |i: usize, min: u32, max: u32| parse_field(fields[i], min, max).ok_or_else(invalid);
+
+        let mut weekdays = field(4, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Schedule {
+            expr: fields.join(" "),
+            minutes: field(0, 0, 59)?,
+            hours: field(1, 0, 23)?,
+            days: field(2, 1, 31)?,
+            months: field(3, 1, 12)?,
+            weekdays,
+            any_day: !fields[2].starts_with('*') && !fields[4].starts_with('*'),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+
+    fn matches_day(&self, at: DateTime<Utc>) -> bool {
+        let day = self.days & (1 << at.day()) != 0;
+        let weekday = self.weekdays & (1 << at.weekday().num_days_from_sunday()) != 0;
+        self.months & (1 << at.month()) != 0
+            && match self.any_day {
+                true => day || weekday,
+                false => day && weekday,
+            }
+    }
+
+    pub fn matches(&self, at: DateTime<Utc>) -> bool {
+        self.matches_day(at)
+            && self.hours & (1 << at.hour()) != 0
+            && self.minutes & (1 << at.minute()) != 0
+    }
+
+    // The first matching minute after `after`, looking at most five years ahead.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut at = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(5 * 366);
+        while at <= limit {
+            if !self.matches_day(at) {
+                at = at.date_naive().and_hms_opt(0, 0, 0)?.and_utc() + Duration::days(1);
+            } else if self.hours & (1 << at.hour()) == 0 {
+                at = at.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << at.minute()) == 0 {
+                at += Duration::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+}
+
+// `a`, `a-b`, `*`, each optionally with `/step`, separated by commas.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (a.parse().ok()?, b.parse().ok()?),
+                None => {
+                    let a = range.parse().ok()?;
+                    (a, if part.contains('/') { max } else { a })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Some(bits)
+}
+
+// `report_schedule`, Mondays at 09:00 UTC by default.
+pub fn report_schedule() -> Schedule {
+    let expr = std::env::var("report_schedule").unwrap_or(DEFAULT_SCHEDULE.to_string());
+    Schedule::parse(&expr).unwrap_or_else(|e| {
+        log::error!("{}, using `{}`", e, DEFAULT_SCHEDULE);
+        Schedule::parse(DEFAULT_SCHEDULE).unwrap()
+    })
+}
+
+// Makes a report for every subscription and sends it to `sinks`. A subscription's first
+// report covers the last 7 days; one no sink took is made again, window and all, on the
+// next run. Returns the number of reports delivered to any sink.
+pub async fn run_subscriptions(
+    github: &dyn GitHubTransport,
+    llm: &dyn LlmBackend,
+    sinks: &[Box<dyn ReportSink>],
+) -> usize {
+    let mut delivered = 0;
+    for subscription in subscriptions() {
+        let now = Utc::now();
+        let window = match subscription.last_run {
+            Some(last_run) => TimeWindow::new(last_run, now),
+            None => TimeWindow::last_days(7),
+        };
+        let engine = ReportEngine::new(github, llm, &subscription.owner, &subscription.repo)
+            .with_user(subscription.user_name.clone())
+            .with_window(window);
+        match engine.run().await {
+            Ok(report) => match deliver(sinks, &report).await {
+                0 => log::error!(
+                    "no sink took the scheduled report on {}",
+                    subscription.describe()
+                ),
+                _ => {
+                    delivered += 1;
+                    record_run(&subscription, now);
+                }
+            },
+            Err(e) => log::error!(
+                "scheduled report on {} failed: {}",
+                subscription.describe(),
+                e
+            ),
+        }
+    }
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn fields_take_steps_ranges_and_lists() {
+        let schedule = Schedule::parse("*/15 9-17/4 1,15 1-3,12 *").unwrap();
+        assert!(schedule.matches(at(2024, 1, 1, 9, 0)));
+        assert!(schedule.matches(at(2024, 12, 15, 17, 45)));
+        assert!(schedule.matches(at(2024, 3, 1, 13, 30)));
+        // 10 isn't in 9-17/4, 7 isn't every 15 minutes, 2 isn't in the list of days.
+        assert!(!schedule.matches(at(2024, 1, 1, 10, 0)));
+        assert!(!schedule.matches(at(2024, 1, 1, 9, 7)));
+        assert!(!schedule.matches(at(2024, 1, 2, 9, 0)));
+        assert!(!schedule.matches(at(2024, 4, 1, 9, 0)));
+
+        assert_eq!(
+            Schedule::parse("0  9 *   * 1").unwrap().as_str(),
+            "0 9 * * 1"
+        );
+    }
+
+    #[test]
+    fn sunday_is_0_and_7() {
+        let sunday = at(2024, 1, 7, 9, 0);
+        for expr in ["0 9 * * 0", "0 9 * * 7", "0 9 * * 5-7"] {
+            let schedule = Schedule::parse(expr).unwrap();
+            assert!(schedule.matches(sunday), "{expr}");
+            assert!(!schedule.matches(at(2024, 1, 8, 9, 0)), "{expr}");
+        }
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 13th or any Friday.
+        let schedule = Schedule::parse("0 9 13 * 5").unwrap();
+        assert!(schedule.matches(at(2024, 1, 5, 9, 0)));
+        assert!(schedule.matches(at(2024, 1, 13, 9, 0)));
+        assert!(!schedule.matches(at(2024, 1, 4, 9, 0)));
+
+        // A step starting with `*` restricts the days together with the other field:
+        // the 13th, when it falls on Sunday, Tuesday, Thursday or Saturday.
+        let schedule = Schedule::parse("0 9 13 * */2").unwrap();
+        assert!(schedule.matches(at(2024, 1, 13, 9, 0)));
+        assert!(schedule.matches(at(2024, 2, 13, 9, 0)));
+        assert!(!schedule.matches(at(2024, 3, 13, 9, 0)));
+        assert!(!schedule.matches(at(2024, 1, 2, 9, 0)));
+    }
+
+    #[test]
+    fn next_runs_roll_over_months_and_years() {
+        let schedule = Schedule::parse("30 8 1 * *").unwrap();
+        assert_eq!(
+            schedule.next_after(at(2024, 1, 31, 12, 0)),
+            Some(at(2024, 2, 1, 8, 30))
+        );
+        assert_eq!(
+            schedule.next_after(at(2023, 12, 15, 0, 0)),
+            Some(at(2024, 1, 1, 8, 30))
+        );
+        // A run time itself isn't the next one.
+        assert_eq!(
+            schedule.next_after(at(2024, 2, 1, 8, 30)),
+            Some(at(2024, 3, 1, 8, 30))
+        );
+
+        let leap_day = Schedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap_day.next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+    }
+
+    #[test]
+    fn impossible_schedules_never_run() {
+        let schedule = Schedule::parse("0 0 30 2 *").unwrap();
+        assert_eq!(schedule.next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn bad_expressions_are_rejected() {
+        for expr in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(
+                matches!(Schedule::parse(expr), Err(ResearchError::InvalidInput(_))),
+                "{expr}"
+            );
+        }
+    }
+}
@@ -0,0 +1,526 @@
+// Destinations a finished report can be delivered to. Every sink talks to a configurable
+// URL, so any of them can be pointed at a local stand-in server.
+use crate::discord_functions::{split_message, truncate_chars};
+use crate::error::{ResearchError, ResearchResult};
+use crate::render::{report_blocks, Block};
+use crate::reports::Report;
+use serde_json::json;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = ResearchResult<()>> + 'a>>;
+
+pub trait ReportSink {
+    // Shown in logs, e.g. `slack`.
+    fn name(&self) -> &str;
+
+    fn send<'a>(&'a self, report: &'a Report) -> SinkFuture<'a>;
+}
+
+async fn post_json(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &serde_json::Value,
+) -> ResearchResult<()> {
+    let body = body.to_string();
+    let mut all_headers = vec![("Content-Type", "application/json")];
+    all_headers.extend_from_slice(headers);
+
+    let res = crate::http::send(method, url, &all_headers, Some(body.as_bytes())).await?;
+    match res.is_success() {
+        true => Ok(()),
+        false => {
+            log::error!(
+                "{} {} answered {}: {}",
+                method,
+                url,
+                res.status,
+                String::from_utf8_lossy(&res.body)
+            );
+            Err(ResearchError::Http {
+                status: res.status,
+                url: url.to_string(),
+            })
+        }
+    }
+}
+
+// Posts the report in Slack's incoming webhook format, using Block Kit sections.
+pub struct SlackSink {
+    webhook_url: String,
+}
+
+// Slack caps a message at 50 blocks and a section's text at 3000 characters.
+const SLACK_MAX_BLOCKS: usize = 50;
+const SLACK_SECTION_LIMIT: usize = 3000;
+
+impl SlackSink {
+    pub fn new(webhook_url: &str) -> Self {
+        SlackSink {
+            webhook_url: webhook_url.to_string(),
+        }
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    fn sections(text: &str) -> Vec<serde_json::Value> {
+        split_message(text, SLACK_SECTION_LIMIT)
+            .into_iter()
+            .filter(|chunk| !chunk.is_empty())
+            .map(|chunk| json!({ "type": "section", "text": { "type": "mrkdwn", "text": chunk } }))
+            .collect()
+    }
+
+    pub fn message(report: &Report) -> serde_json::Value {
+        let mut blocks = vec![];
+        for block in report_blocks(report) {
+            match block {
+                Block::Heading(1, text) => blocks.push(json!({
+                    "type": "header",
+                    "text": { "type": "plain_text", "text": truncate_chars(&text, 150) },
+                })),
+                Block::Heading(_, text) => {
+                    blocks.extend(Self::sections(&format!("*{}*", Self::escape(&text))))
+                }
+                Block::Paragraph(text) => blocks.extend(Self::sections(&Self::escape(&text))),
+                Block::Items(entries) => {
+                    let lines = entries
+                        .iter()
+                        .map(|entry| {
+                            let title = Self::escape(&entry.title).replace('|', "/");
+                            let mut line = format!("• <{}|{}>", entry.url, title);
+                            if !entry.byline.is_empty() {
+                                line.push_str(&format!(" {}", Self::escape(&entry.byline)));
+                            }
+                            if let Some(summary) = &entry.summary {
+                                line.push_str(&format!("\n{}", Self::escape(summary.trim())));
+                            }
+                            line
+                        })
+                        .collect::<Vec<_>>();
+                    blocks.extend(Self::sections(&lines.join("\n\n")));
+                }
+                Block::Table(header, rows) => {
+                    let lines = rows
+                        .iter()
+                        .map(|row| {
+                            let cells = header
+                                .iter()
+                                .zip(row)
+                                .skip(1)
+                                .filter(|(_, cell)| !cell.is_empty())
+                                .map(|(name, cell)| format!("{}: {}", name, cell))
+                                .collect::<Vec<_>>();
+                            format!("• *{}* {}", Self::escape(&row[0]), cells.join(", "))
+                        })
+                        .collect::<Vec<_>>();
+                    blocks.extend(Self::sections(&lines.join("\n")));
+                }
+                Block::Note(text) => blocks.push(json!({
+                    "type": "context",
+                    "elements": [{ "type": "mrkdwn", "text": Self::escape(&text) }],
+                })),
+            }
+        }
+        if blocks.len() > SLACK_MAX_BLOCKS {
+            log::warn!("Slack message cut from {} blocks", blocks.len());
+            blocks.truncate(SLACK_MAX_BLOCKS);
+        }
+        json!({ "text": report.title(), "blocks": blocks })
+    }
+}
+
+impl ReportSink for SlackSink {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn send<'a>(&'a self, report: &'a Report) -> SinkFuture<'a> {
+        Box::pin(
+            async move { post_json("POST", &self.webhook_url, &[], &Self::message(report)).await },
+        )
+    }
+}
+
+// Sends the report to a room through the Matrix client-server API, as Markdown with an
+// HTML rendering for clients that show formatted messages.
+pub struct MatrixSink {
+    homeserver: String,
+    access_token: String,
+    room_id: String,
+}
+
+impl MatrixSink {
+    pub fn new(homeserver: &str, access_token: &str, room_id: &str) -> Self {
+        MatrixSink {
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            room_id: room_id.to_string(),
+        }
+    }
+}
+
+impl ReportSink for MatrixSink {
+    fn name(&self) -> &str {
+        "matrix"
+    }
+
+    fn send<'a>(&'a self, report: &'a Report) -> SinkFuture<'a> {
+        Box::pin(async move {
+            // The transaction id only has to be unique per access token.
+            let txn_id = format!("report-{}", chrono::Utc::now().timestamp_micros());
+            let url = format!(
+                "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+                self.homeserver,
+                urlencoding::encode(&self.room_id),
+                txn_id
+            );
+            let auth = format!("Bearer {}", self.access_token);
+            let message = json!({
+                "msgtype": "m.notice",
+                "body": report.to_markdown(),
+                "format": "org.matrix.custom.html",
+                "formatted_body": report.to_html_fragment(),
+            });
+            post_json("PUT", &url, &[("Authorization", auth.as_str())], &message).await
+        })
+    }
+}
+
+// Posts `Report::to_json` to any URL, for feeding reports into other tooling.
+pub struct WebhookSink {
+    url: String,
+    secret: Option<String>,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Self {
+        WebhookSink {
+            url: url.to_string(),
+            secret: None,
+        }
+    }
+
+    // Sent as a bearer token so the receiver can reject other senders.
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        self.secret = Some(secret.to_string());
+        self
+    }
+}
+
+impl ReportSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, report: &'a Report) -> SinkFuture<'a> {
+        Box::pin(async move {
+            let body = serde_json::to_value(report)?;
+            let auth = self
+                .secret
+                .as_ref()
+                .map(|secret| format!("Bearer {secret}"));
+            let headers = match &auth {
+                Some(auth) => vec![("Authorization", auth.as_str())],
+                None => vec![],
+            };
+            post_json("POST", &self.url, &headers, &body).await
+        })
+    }
+}
+
+// Posts the report as embeds to a Discord channel, outside of any slash command.
+#[cfg(feature = "flows")]
+pub struct DiscordSink {
+    client: discord_flows::http::Http,
+    channel_id: u64,
+}
+
+#[cfg(feature = "flows")]
+impl DiscordSink {
+    pub fn new(discord_token: &str, channel_id: u64) -> Self {
+        use discord_flows::Bot;
+
+        let bot = discord_flows::ProvidedBot::new(discord_token);
+        DiscordSink {
+            client: bot.get_client(),
+            channel_id,
+        }
+    }
+}
+
+#[cfg(feature = "flows")]
+impl ReportSink for DiscordSink {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn send<'a>(&'a self, report: &'a Report) -> SinkFuture<'a> {
+        use crate::discord_functions::{pack_embeds, report_embeds};
+
+        Box::pin(async move {
+            for batch in pack_embeds(report_embeds(report)) {
+                if let Err(e) = self
+                    .client
+                    .send_message(self.channel_id, &json!({ "embeds": batch }))
+                    .await
+                {
+                    log::error!(
+                        "error posting report to channel {}: {:?}",
+                        self.channel_id,
+                        e
+                    );
+                    return Err(ResearchError::Transport(e.to_string()));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+// Sinks configured through the environment: `slack_webhook_url`, `matrix_homeserver` with
+// `matrix_access_token` and `matrix_room_id`, and `report_webhook_url` with an optional
+// `report_webhook_secret`.
+pub fn sinks_from_env() -> Vec<Box<dyn ReportSink>> {
+    let mut sinks: Vec<Box<dyn ReportSink>> = vec![];
+    if let Ok(url) = env::var("slack_webhook_url") {
+        sinks.push(Box::new(SlackSink::new(&url)));
+    }
+    if let (Ok(homeserver), Ok(token), Ok(room_id)) = (
+        env::var("matrix_homeserver"),
+        env::var("matrix_access_token"),
+        env::var("matrix_room_id"),
+    ) {
+        sinks.push(Box::new(MatrixSink::new(&homeserver, &token, &room_id)));
+    }
+    if let Ok(url) = env::var("report_webhook_url") {
+        let mut sink = WebhookSink::new(&url);
+        if let Ok(secret) = env::var("report_webhook_secret") {
+            sink = sink.with_secret(&secret);
+        }
+        sinks.push(Box::new(sink));
+    }
+    sinks
+}
+
+// Sends to every sink; a failing sink is logged and doesn't stop the others.
+// Returns the number of sinks that took the report.
+pub async fn deliver(sinks: &[Box<dyn ReportSink>], report: &Report) -> usize {
+    let mut delivered = 0;
+    for sink in sinks {
+        match sink.send(report).await {
+            Ok(()) => delivered += 1,
+            Err(e) => log::error!("failed to deliver the report to {}: {}", sink.name(), e),
+        }
+    }
+    delivered
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::reports::sample_report;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    struct Request {
+        method: String,
+        path: String,
+        // Names are lowercased.
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    impl Request {
+        fn json(&self) -> serde_json::Value {
+            serde_json::from_str(&self.body).unwrap()
+        }
+    }
+
+    // Answers one request per status, in turn, and hands each request it got over.
+    fn serve(statuses: Vec<u16>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(name.to_lowercase(), value.trim().to_string());
+                    }
+                }
+                let length = headers
+                    .get("content-length")
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0);
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+                )
+                .unwrap();
+                tx.send(Request {
+                    method,
+                    path,
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                })
+                .unwrap();
+            }
+        });
+        (base_url, rx)
+    }
+
+    fn section_texts(message: &serde_json::Value) -> Vec<String> {
+        message["blocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|block| block["type"] == "section")
+            .map(|block| block["text"]["text"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn slack_gets_block_kit_sections() {
+        let (base_url, requests) = serve(vec![200]);
+        let sink = SlackSink::new(&format!("{base_url}/services/T1/B2"));
+        let report = sample_report();
+        sink.send(&report).await.unwrap();
+
+        let request = requests.recv().unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("POST", "/services/T1/B2")
+        );
+        assert_eq!(request.headers["content-type"], "application/json");
+
+        let message = request.json();
+        assert_eq!(message["text"], report.title());
+        assert_eq!(message["blocks"][0]["type"], "header");
+        assert_eq!(message["blocks"][0]["text"]["text"], report.title());
+        let sections = section_texts(&message);
+        assert!(sections.contains(&"Tools for &lt;fast&gt; &amp; safe builds.".to_string()));
+        assert!(sections.contains(&"*Commits (2 found)*".to_string()));
+        assert!(sections.contains(
+            &"• <https://github.com/o/r/commit/a1|Add [rate] limit handling> by alice (2024-01-03)\nWaits out rate limits.\n\n• <https://github.com/o/r/commit/b2|https://github.com/o/r/commit/b2>"
+                .to_string()
+        ));
+        assert!(sections.contains(
+            &"• *bob* Pull requests: 1, Approvals: 1, Change requests: 0, Comment reviews: 0, Review comments: 2, Average turnaround: 24.0 hours"
+                .to_string()
+        ));
+    }
+
+    #[test]
+    fn slack_sections_split_long_text_without_losing_any() {
+        let mut report = sample_report();
+        let synthesis = "word ".repeat(1500);
+        report.synthesis = Some(synthesis.clone());
+
+        let sections = section_texts(&SlackSink::message(&report));
+        let start = sections.iter().position(|s| s == "*Summary*").unwrap() + 1;
+        let chunks = &sections[start..start + 3];
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.chars().count() <= SLACK_SECTION_LIMIT));
+        assert_eq!(chunks.join(" "), synthesis.trim());
+    }
+
+    #[tokio::test]
+    async fn matrix_gets_markdown_with_html() {
+        let (base_url, requests) = serve(vec![200]);
+        let sink = MatrixSink::new(&format!("{base_url}/"), "syt_token", "!room:example.org");
+        let report = sample_report();
+        sink.send(&report).await.unwrap();
+
+        let request = requests.recv().unwrap();
+        assert_eq!(request.method, "PUT");
+        assert!(
+            request.path.starts_with(
+                "/_matrix/client/v3/rooms/%21room%3Aexample.org/send/m.room.message/report-"
+            ),
+            "{}",
+            request.path
+        );
+        assert_eq!(request.headers["authorization"], "Bearer syt_token");
+        assert_eq!(request.headers["content-type"], "application/json");
+
+        let message = request.json();
+        assert_eq!(message["msgtype"], "m.notice");
+        assert_eq!(message["body"], report.to_markdown());
+        assert_eq!(message["format"], "org.matrix.custom.html");
+        assert_eq!(message["formatted_body"], report.to_html_fragment());
+    }
+
+    #[tokio::test]
+    async fn webhook_gets_the_report_as_json() {
+        let (base_url, requests) = serve(vec![200, 200]);
+        let report = sample_report();
+
+        let url = format!("{base_url}/reports");
+        WebhookSink::new(&url)
+            .with_secret("s3cret")
+            .send(&report)
+            .await
+            .unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("POST", "/reports")
+        );
+        assert_eq!(request.headers["authorization"], "Bearer s3cret");
+        let sent = serde_json::from_str::<Report>(&request.body).unwrap();
+        assert_eq!(sent.commits.items[0].payload, "Waits out rate limits.");
+        assert_eq!(sent.reviews[0].login, "bob");
+
+        WebhookSink::new(&url).send(&report).await.unwrap();
+        let request = requests.recv().unwrap();
+        assert!(!request.headers.contains_key("authorization"));
+    }
+
+    #[tokio::test]
+    async fn failing_sinks_dont_stop_the_others() {
+        let (base_url, requests) = serve(vec![500, 200]);
+        let sinks: Vec<Box<dyn ReportSink>> = vec![
+            Box::new(WebhookSink::new(&format!("{base_url}/down"))),
+            Box::new(SlackSink::new(&format!("{base_url}/up"))),
+        ];
+        assert_eq!(deliver(&sinks, &sample_report()).await, 1);
+        assert_eq!(requests.recv().unwrap().path, "/down");
+        assert_eq!(requests.recv().unwrap().path, "/up");
+
+        let (base_url, _requests) = serve(vec![403]);
+        let err = WebhookSink::new(&base_url)
+            .send(&sample_report())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ResearchError::Http { status: 403, .. }),
+            "{err}"
+        );
+    }
+}
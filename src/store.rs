@@ -0,0 +1,149 @@
+// Key-value store for caches and remembered users. With the `flows` feature this is
+// the flows.network store; with `native` it is an in-process map, saved to the JSON
+// file named by `store_file` when that is set. Saves are batched: the file is rewritten
+// at most every `store_save_interval_secs` (10 by default) and on `flush`.
+#[cfg(feature = "flows")]
+use serde_json::Value;
+
+#[cfg(feature = "flows")]
+pub fn get(key: &str) -> Option<Value> {
+    store_flows::get(key)
+}
+
+// `ttl_secs` of None keeps the value until it is overwritten or deleted.
+#[cfg(feature = "flows")]
+pub fn set(key: &str, value: Value, ttl_secs: Option<i64>) {
+    use store_flows::{Expire, ExpireKind};
+
+    let expire = ttl_secs.map(|value| Expire {
+        kind: ExpireKind::Ex,
+        value,
+    });
+    store_flows::set(key, value, expire);
+}
+
+#[cfg(feature = "flows")]
+pub fn del(key: &str) {
+    store_flows::del(key);
+}
+
+// Writes are immediate with `flows`.
+#[cfg(feature = "flows")]
+pub fn flush() {}
+
+#[cfg(feature = "native")]
+pub use native::{del, flush, get, set};
+
+#[cfg(feature = "native")]
+mod native {
+    use chrono::Utc;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::env;
+    use std::sync::{Mutex, OnceLock};
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        value: Value,
+        // Unix timestamp after which the entry is gone.
+        expires_at: Option<i64>,
+    }
+
+    struct Store {
+        entries: HashMap<String, Entry>,
+        // Changed since the last save, and when that was.
+        dirty: bool,
+        saved_at: i64,
+    }
+
+    fn store() -> &'static Mutex<Store> {
+        static STORE: OnceLock<Mutex<Store>> = OnceLock::new();
+        STORE.get_or_init(|| {
+            let entries = env::var("store_file")
+                .ok()
+                .and_then(|path| std::fs::read(path).ok())
+                .and_then(|raw| serde_json::from_slice(&raw).ok())
+                .unwrap_or_default();
+            Mutex::new(Store {
+                entries,
+                dirty: false,
+                saved_at: Utc::now().timestamp(),
+            })
+        })
+    }
+
+    fn save_interval_secs() -> i64 {
+        env::var("store_save_interval_secs")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(10)
+    }
+
+    // Drops expired entries and rewrites the file.
+    fn save(store: &mut Store) {
+        let now = Utc::now().timestamp();
+        store
+            .entries
+            .retain(|_, entry| !matches!(entry.expires_at, Some(at) if at <= now));
+        store.dirty = false;
+        store.saved_at = now;
+        if let Ok(path) = env::var("store_file") {
+            match serde_json::to_vec(&store.entries) {
+                Ok(raw) => {
+                    if let Err(e) = std::fs::write(&path, raw) {
+                        log::error!("Failed to save the store to {}: {}", path, e);
+                    }
+                }
+                Err(e) => log::error!("Failed to serialize the store: {}", e),
+            }
+        }
+    }
+
+    fn changed(store: &mut Store) {
+        store.dirty = true;
+        if Utc::now().timestamp() - store.saved_at >= save_interval_secs() {
+            save(store);
+        }
+    }
+
+    pub fn get(key: &str) -> Option<Value> {
+        let store = store().lock().ok()?;
+        let entry = store.entries.get(key)?;
+        match entry.expires_at {
+            Some(at) if at <= Utc::now().timestamp() => None,
+            _ => Some(entry.value.clone()),
+        }
+    }
+
+    pub fn set(key: &str, value: Value, ttl_secs: Option<i64>) {
+        if let Ok(mut store) = store().lock() {
+            let now = Utc::now().timestamp();
+            store.entries.insert(
+                key.to_string(),
+                Entry {
+                    value,
+                    expires_at: ttl_secs.map(|ttl| now + ttl),
+                },
+            );
+            changed(&mut store);
+        }
+    }
+
+    pub fn del(key: &str) {
+        if let Ok(mut store) = store().lock() {
+            if store.entries.remove(key).is_some() {
+                changed(&mut store);
+            }
+        }
+    }
+
+    // Saves whatever changed since the last save; call it before exiting.
+    pub fn flush() {
+        if let Ok(mut store) = store().lock() {
+            if store.dirty {
+                save(&mut store);
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use crate::error::{ResearchError, ResearchResult};
+use crate::github_data_fetchers::get_release_date;
+use crate::transport::GitHubTransport;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+impl TimeWindow {
+    pub fn new(since: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        TimeWindow { since, until }
+    }
+
+    pub fn last_days(n_days: i64) -> Self {
+        let now = Utc::now();
+        TimeWindow {
+            since: now - Duration::days(n_days),
+            until: now,
+        }
+    }
+
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let (next_year, next_month) = match month {
+            12 => (year + 1, 1),
+            _ => (year, month + 1),
+        };
+        Some(TimeWindow {
+            since: start_of_day(NaiveDate::from_ymd_opt(year, month, 1)?),
+            until: start_of_day(NaiveDate::from_ymd_opt(next_year, next_month, 1)?),
+        })
+    }
+
+    pub fn quarter(year: i32, quarter: u32) -> Option<Self> {
+        if !(1..=4).contains(&quarter) {
+            return None;
+        }
+        let first = TimeWindow::month(year, (quarter - 1) * 3 + 1)?;
+        let last = TimeWindow::month(year, quarter * 3)?;
+        Some(TimeWindow {
+            since: first.since,
+            until: last.until,
+        })
+    }
+
+    // The last full calendar month before `now`.
+    pub fn previous_month(now: DateTime<Utc>) -> Option<Self> {
+        match now.month() {
+            1 => TimeWindow::month(now.year() - 1, 12),
+            m => TimeWindow::month(now.year(), m - 1),
+        }
+    }
+
+    // The last full calendar quarter before `now`.
+    pub fn previous_quarter(now: DateTime<Utc>) -> Option<Self> {
+        match (now.month() - 1) / 3 {
+            0 => TimeWindow::quarter(now.year() - 1, 4),
+            q => TimeWindow::quarter(now.year(), q),
+        }
+    }
+
+    // From the publication of `from_tag` to that of `to_tag`, or to now when `to_tag` is None.
+    pub async fn between_releases(
+        github: &dyn GitHubTransport,
+        owner: &str,
+        repo: &str,
+        from_tag: &str,
+        to_tag: Option<&str>,
+    ) -> ResearchResult<Self> {
+        let since = get_release_date(github, owner, repo, from_tag).await?;
+        let until = match to_tag {
+            Some(tag) => get_release_date(github, owner, repo, tag).await?,
+            None => Utc::now(),
+        };
+        Ok(TimeWindow { since, until })
+    }
+
+    // Resolves the slash command options. `since` and `until` are either
+    // `YYYY-MM-DD` dates or release tags; `period` is `month` or `quarter` and can't be
+    // combined with the others.
+    pub async fn resolve(
+        github: &dyn GitHubTransport,
+        owner: &str,
+        repo: &str,
+        days: Option<i64>,
+        since: Option<&str>,
+        until: Option<&str>,
+        period: Option<&str>,
+    ) -> ResearchResult<Self> {
+        if let Some(period) = period {
+            if days.is_some() || since.is_some() || until.is_some() {
+                return Err(ResearchError::InvalidInput(
+                    "combination of `period` with `days`, `since` or `until`".to_string(),
+                ));
+            }
+            let window = match period {
+                "month" => TimeWindow::previous_month(Utc::now()),
+                "quarter" => TimeWindow::previous_quarter(Utc::now()),
+                _ => None,
+            };
+            return window.ok_or_else(|| ResearchError::InvalidInput(format!("period {period}")));
+        }
+
+        let until = match until {
+            Some(s) => Some(parse_date_or_release(github, owner, repo, s, true).await?),
+            None => None,
+        };
+        let since = match since {
+            Some(s) => Some(parse_date_or_release(github, owner, repo, s, false).await?),
+            None => None,
+        };
+        let n_days = days.unwrap_or(7);
+
+        let window = match (since, until) {
+            (Some(since), Some(until)) => TimeWindow { since, until },
+            (Some(since), None) => TimeWindow {
+                since,
+                until: Utc::now(),
+            },
+            (None, Some(until)) => TimeWindow {
+                since: until - Duration::days(n_days),
+                until,
+            },
+            (None, None) => TimeWindow::last_days(n_days),
+        };
+
+        if window.since >= window.until {
+            log::error!("Empty time window: {:?}", window);
+            return Err(ResearchError::InvalidInput(format!(
+                "time range {}",
+                window.describe()
+            )));
+        }
+        Ok(window)
+    }
+
+    pub fn n_days(&self) -> i64 {
+        (self.until - self.since).num_days()
+    }
+
+    // Range syntax for the GitHub search `created:`/`updated:` qualifiers.
+    pub fn search_range(&self) -> String {
+        format!("{}..{}", format_iso(&self.since), format_iso(&self.until))
+    }
+
+    pub fn contains(&self, date: &DateTime<Utc>) -> bool {
+        *date >= self.since && *date <= self.until
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "from {} to {}",
+            self.since.date_naive(),
+            self.until.date_naive()
+        )
+    }
+}
+
+pub fn format_iso(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+async fn parse_date_or_release(
+    github: &dyn GitHubTransport,
+    owner: &str,
+    repo: &str,
+    input: &str,
+    end_of_day: bool,
+) -> ResearchResult<DateTime<Utc>> {
+    match NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        Ok(date) if end_of_day => Ok(start_of_day(date) + Duration::days(1)),
+        Ok(date) => Ok(start_of_day(date)),
+        Err(_) => match get_release_date(github, owner, repo, input).await {
+            Err(e) if e.is_not_found() => Err(ResearchError::InvalidInput(format!(
+                "date or release tag {input}"
+            ))),
+            res => res,
+        },
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::transport::FixtureTransport;
+    use chrono::TimeZone;
+
+    fn fixtures() -> FixtureTransport {
+        FixtureTransport::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn months_end_where_the_next_one_starts() {
+        let december = TimeWindow::month(2023, 12).unwrap();
+        assert_eq!(december, TimeWindow::new(at(2023, 12, 1), at(2024, 1, 1)));
+        let february = TimeWindow::month(2024, 2).unwrap();
+        assert_eq!(february.until, at(2024, 3, 1));
+        assert_eq!(february.n_days(), 29);
+        assert!(TimeWindow::month(2024, 13).is_none());
+    }
+
+    #[test]
+    fn quarters_span_three_months() {
+        let q4 = TimeWindow::quarter(2023, 4).unwrap();
+        assert_eq!(q4, TimeWindow::new(at(2023, 10, 1), at(2024, 1, 1)));
+        assert!(TimeWindow::quarter(2023, 0).is_none());
+        assert!(TimeWindow::quarter(2023, 5).is_none());
+    }
+
+    #[test]
+    fn previous_periods_roll_back_over_the_year() {
+        let january = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+        assert_eq!(
+            TimeWindow::previous_month(january),
+            TimeWindow::month(2023, 12)
+        );
+        assert_eq!(
+            TimeWindow::previous_quarter(january),
+            TimeWindow::quarter(2023, 4)
+        );
+        assert_eq!(
+            TimeWindow::previous_month(at(2024, 3, 1)),
+            TimeWindow::month(2024, 2)
+        );
+        assert_eq!(
+            TimeWindow::previous_quarter(at(2024, 4, 1)),
+            TimeWindow::quarter(2024, 1)
+        );
+        assert_eq!(
+            TimeWindow::previous_quarter(at(2024, 12, 31)),
+            TimeWindow::quarter(2024, 3)
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_reads_dates_and_release_tags() {
+        let github = fixtures();
+        let window = TimeWindow::resolve(
+            &github,
+            "o",
+            "r",
+            None,
+            Some("2024-01-01"),
+            Some("2024-01-07"),
+            None,
+        )
+        .await
+        .unwrap();
+        // `until` takes in the whole day.
+        assert_eq!(window, TimeWindow::new(at(2024, 1, 1), at(2024, 1, 8)));
+
+        let window = TimeWindow::resolve(&github, "o", "r", None, Some("v1.0"), Some("v1.1"), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            window,
+            TimeWindow::new(
+                Utc.with_ymd_and_hms(2024, 2, 1, 10, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 3, 15, 8, 30, 0).unwrap()
+            )
+        );
+
+        let window = TimeWindow::resolve(&github, "o", "r", Some(3), None, Some("v1.0"), None)
+            .await
+            .unwrap();
+        assert_eq!(window.n_days(), 3);
+    }
+
+    async fn assert_invalid(
+        days: Option<i64>,
+        since: Option<&str>,
+        until: Option<&str>,
+        period: Option<&str>,
+    ) {
+        let res = TimeWindow::resolve(&fixtures(), "o", "r", days, since, until, period).await;
+        assert!(
+            matches!(res, Err(ResearchError::InvalidInput(_))),
+            "{days:?} {since:?} {until:?} {period:?}: {res:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_rejects_bad_input() {
+        assert_invalid(None, Some("v9"), None, None).await;
+        assert_invalid(None, Some("2024-01-08"), Some("2024-01-01"), None).await;
+        assert_invalid(None, None, None, Some("year")).await;
+        assert_invalid(Some(7), None, None, Some("month")).await;
+        assert_invalid(None, Some("2024-01-01"), None, Some("quarter")).await;
+    }
+}
@@ -0,0 +1,926 @@
+use crate::octocrab_compat::Rate;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+pub const GITHUB_API_BASE: &str = "https://api.github.com";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitHubResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl GitHubResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<GitHubResponse, Box<dyn std::error::Error>>> + 'a>>;
+
+pub trait GitHubTransport: Send + Sync {
+    fn base_url(&self) -> &str;
+
+    fn graphql_url(&self) -> String {
+        format!("{}/graphql", self.base_url())
+    }
+
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(&'a str, &'a str)]) -> TransportFuture<'a>;
+
+    fn post_graphql<'a>(
+        &'a self,
+        query: &'a str,
+        variables: &'a serde_json::Value,
+    ) -> TransportFuture<'a>;
+
+    fn get_patch<'a>(&'a self, url: &'a str) -> TransportFuture<'a>;
+
+    // True once any request since the last `reset_rate_limited` was cut short by GitHub's
+    // rate limits, meaning results are partial.
+    fn was_rate_limited(&self) -> bool {
+        false
+    }
+
+    // Called when a report starts, so one limited run doesn't mark every later one partial.
+    fn reset_rate_limited(&self) {}
+
+    // Last seen limits per resource (`core`, `search`, `graphql`).
+    fn rate_limits(&self) -> HashMap<String, Rate> {
+        HashMap::new()
+    }
+}
+
+pub struct HttpTransport {
+    token: String,
+    base_url: String,
+}
+
+impl HttpTransport {
+    pub fn new(token: &str) -> Self {
+        HttpTransport {
+            token: token.to_string(),
+            base_url: GITHUB_API_BASE.to_string(),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&[u8]>,
+    ) -> Result<GitHubResponse, Box<dyn std::error::Error>> {
+        let auth = format!("Bearer {}", self.token);
+        let mut all_headers = vec![
+            ("User-Agent", "flows-network connector"),
+            ("Authorization", auth.as_str()),
+        ];
+        all_headers.extend_from_slice(headers);
+        crate::http::send(method, url, &all_headers, body).await
+    }
+}
+
+impl GitHubTransport for HttpTransport {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(&'a str, &'a str)]) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let mut all_headers = vec![("Content-Type", "application/vnd.github.v3+json")];
+            all_headers.extend_from_slice(headers);
+            self.send("GET", url, &all_headers, None).await
+        })
+    }
+
+    fn post_graphql<'a>(
+        &'a self,
+        query: &'a str,
+        variables: &'a serde_json::Value,
+    ) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let body = serde_json::json!({ "query": query, "variables": variables }).to_string();
+            self.send(
+                "POST",
+                &self.graphql_url(),
+                &[("Content-Type", "application/json")],
+                Some(body.as_bytes()),
+            )
+            .await
+        })
+    }
+
+    fn get_patch<'a>(&'a self, url: &'a str) -> TransportFuture<'a> {
+        Box::pin(self.send("GET", url, &[("Content-Type", "plain/text")], None))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    url: String,
+    #[serde(default = "default_status")]
+    status: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    body: serde_json::Value,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+// Replays responses saved as JSON files, one file per request. A fixture's `body`
+// is either a JSON document (returned as-is) or a string (returned verbatim, e.g. a patch).
+// With `recording` set, misses are forwarded to the inner transport and written to disk.
+pub struct FixtureTransport {
+    dir: PathBuf,
+    base_url: String,
+    recording: Option<Box<dyn GitHubTransport>>,
+}
+
+impl FixtureTransport {
+    pub fn new(dir: &str) -> Self {
+        FixtureTransport {
+            dir: PathBuf::from(dir),
+            base_url: GITHUB_API_BASE.to_string(),
+            recording: None,
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn recording(dir: &str, inner: Box<dyn GitHubTransport>) -> Self {
+        FixtureTransport {
+            dir: PathBuf::from(dir),
+            base_url: inner.base_url().to_string(),
+            recording: Some(inner),
+        }
+    }
+
+    // `GET /repos/o/r/commits?page=2` is read from `GET_repos_o_r_commits_page_2.json`: the
+    // method and path with everything but letters, digits, `-` and `.` turned into `_`.
+    // A non-default representation adds its type, e.g. `.patch`, and a request body (only
+    // GraphQL posts have one) adds a hash of it. Names over 200 characters are cut and end
+    // in a hash of the whole name instead.
+    pub fn fixture_path(
+        &self,
+        method: &str,
+        url: &str,
+        accept: Option<&str>,
+        body: &str,
+    ) -> PathBuf {
+        let path = url.strip_prefix(&self.base_url).unwrap_or(url);
+        let mut name = format!("{method}{path}")
+            .chars()
+            .map(
+                |c| match c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    true => c,
+                    false => '_',
+                },
+            )
+            .collect::<String>();
+        if let Some(accept) = accept {
+            name.push('.');
+            name.push_str(&accept.replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+        }
+        if !body.is_empty() {
+            name.push_str(&format!("_{:x}", fixture_hash(body)));
+        }
+        if name.len() > 200 {
+            let hash = fixture_hash(&name);
+            name.truncate(183);
+            name.push_str(&format!("_{hash:016x}"));
+        }
+        self.dir.join(format!("{name}.json"))
+    }
+
+    async fn replay<'a, F>(
+        &'a self,
+        method: &str,
+        url: &str,
+        accept: Option<&str>,
+        body: &str,
+        forward: F,
+    ) -> Result<GitHubResponse, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&'a dyn GitHubTransport) -> TransportFuture<'a>,
+    {
+        let path = self.fixture_path(method, url, accept, body);
+
+        if let Ok(raw) = std::fs::read(&path) {
+            let fixture = serde_json::from_slice::<Fixture>(&raw)?;
+            let body = match fixture.body {
+                serde_json::Value::String(s) => s.into_bytes(),
+                other => serde_json::to_vec(&other)?,
+            };
+            return Ok(GitHubResponse {
+                status: fixture.status,
+                headers: fixture.headers,
+                body,
+            });
+        }
+
+        match &self.recording {
+            None => {
+                log::error!("No fixture for {} {} at {:?}", method, url, path);
+                Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("missing fixture {:?}", path),
+                )))
+            }
+            Some(inner) => {
+                let res = forward(inner.as_ref()).await?;
+                let body = match serde_json::from_slice::<serde_json::Value>(&res.body) {
+                    Ok(json) => json,
+                    Err(_) => serde_json::Value::String(String::from_utf8_lossy(&res.body).into()),
+                };
+                let fixture = Fixture {
+                    url: url.to_string(),
+                    status: res.status,
+                    headers: res.headers.clone(),
+                    body,
+                };
+                std::fs::create_dir_all(&self.dir)?;
+                std::fs::write(&path, serde_json::to_vec_pretty(&fixture)?)?;
+                Ok(res)
+            }
+        }
+    }
+}
+
+impl GitHubTransport for FixtureTransport {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(&'a str, &'a str)]) -> TransportFuture<'a> {
+        let accept = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Accept"))
+            .map(|(_, v)| *v);
+        Box::pin(self.replay("GET", url, accept, "", |inner| inner.get(url, headers)))
+    }
+
+    fn post_graphql<'a>(
+        &'a self,
+        query: &'a str,
+        variables: &'a serde_json::Value,
+    ) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let url = self.graphql_url();
+            let body = format!("{query}{variables}");
+            self.replay("POST", &url, None, &body, |inner| {
+                inner.post_graphql(query, variables)
+            })
+            .await
+        })
+    }
+
+    fn get_patch<'a>(&'a self, url: &'a str) -> TransportFuture<'a> {
+        Box::pin(self.replay("GET", url, Some("patch"), "", |inner| inner.get_patch(url)))
+    }
+}
+
+fn fixture_hash(text: &str) -> u64 {
+    use std::hash::Hasher;
+    use twox_hash::XxHash;
+
+    let mut hasher = XxHash::with_seed(0);
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+// Remembers ETag / Last-Modified per URL and Accept header in the store and turns repeated
+// GETs into conditional requests; a 304 is answered from the cache and doesn't count against
+// the rate limit. Entries expire after `http_cache_ttl_secs`, a day by default.
+pub struct CachingTransport {
+    inner: Box<dyn GitHubTransport>,
+    ttl_secs: i64,
+}
+
+impl CachingTransport {
+    pub fn new(inner: Box<dyn GitHubTransport>) -> Self {
+        let ttl_secs = std::env::var("http_cache_ttl_secs")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(24 * 3600);
+        CachingTransport { inner, ttl_secs }
+    }
+
+    pub fn with_ttl_secs(mut self, ttl_secs: i64) -> Self {
+        self.ttl_secs = ttl_secs;
+        self
+    }
+
+    fn cache_key(url: &str, headers: &[(&str, &str)]) -> String {
+        use std::hash::Hasher;
+        use twox_hash::XxHash;
+
+        let accept = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Accept"))
+            .map_or("", |(_, value)| *value);
+        let mut hasher = XxHash::with_seed(0);
+        hasher.write(url.as_bytes());
+        hasher.write_u8(0);
+        hasher.write(accept.as_bytes());
+        format!("http-{:x}", hasher.finish())
+    }
+}
+
+impl GitHubTransport for CachingTransport {
+    fn base_url(&self) -> &str {
+        self.inner.base_url()
+    }
+
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(&'a str, &'a str)]) -> TransportFuture<'a> {
+        Box::pin(self.get_cached(url, headers))
+    }
+
+    fn post_graphql<'a>(
+        &'a self,
+        query: &'a str,
+        variables: &'a serde_json::Value,
+    ) -> TransportFuture<'a> {
+        self.inner.post_graphql(query, variables)
+    }
+
+    fn get_patch<'a>(&'a self, url: &'a str) -> TransportFuture<'a> {
+        self.inner.get_patch(url)
+    }
+
+    fn was_rate_limited(&self) -> bool {
+        self.inner.was_rate_limited()
+    }
+
+    fn reset_rate_limited(&self) {
+        self.inner.reset_rate_limited()
+    }
+
+    fn rate_limits(&self) -> HashMap<String, Rate> {
+        self.inner.rate_limits()
+    }
+}
+
+impl CachingTransport {
+    async fn get_cached(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<GitHubResponse, Box<dyn std::error::Error>> {
+        let key = Self::cache_key(url, headers);
+        let cached = crate::store::get(&key)
+            .and_then(|val| serde_json::from_value::<CachedResponse>(val).ok());
+
+        let mut all_headers = headers.to_vec();
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                all_headers.push(("If-None-Match", etag.as_str()));
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                all_headers.push(("If-Modified-Since", last_modified.as_str()));
+            }
+        }
+
+        let res = self.inner.get(url, &all_headers).await?;
+        if res.status == 304 {
+            if let Some(cached) = cached {
+                log::info!("Not modified, using cached response for {}", url);
+                return Ok(GitHubResponse {
+                    status: 200,
+                    headers: cached.headers,
+                    body: cached.body.into_bytes(),
+                });
+            }
+        }
+
+        let etag = res.header("ETag").map(|s| s.to_string());
+        let last_modified = res.header("Last-Modified").map(|s| s.to_string());
+        if res.is_success() && (etag.is_some() || last_modified.is_some()) {
+            let entry = CachedResponse {
+                etag,
+                last_modified,
+                headers: res.headers.clone(),
+                body: String::from_utf8_lossy(&res.body).into_owned(),
+            };
+            crate::store::set(
+                &key,
+                serde_json::to_value(&entry).unwrap_or_default(),
+                Some(self.ttl_secs),
+            );
+        }
+        Ok(res)
+    }
+}
+
+#[derive(Default)]
+struct RateLimitState {
+    rates: HashMap<String, Rate>,
+    limited: bool,
+}
+
+// Tracks `X-RateLimit-*` headers and the GraphQL `rateLimit` object, sleeps until the
+// window resets when that is at most `max_wait_secs` away, and otherwise gives up and
+// remembers that data is partial.
+pub struct RateLimitTransport {
+    inner: Box<dyn GitHubTransport>,
+    max_wait_secs: u64,
+    state: Mutex<RateLimitState>,
+}
+
+impl RateLimitTransport {
+    pub fn new(inner: Box<dyn GitHubTransport>) -> Self {
+        RateLimitTransport {
+            inner,
+            max_wait_secs: 60,
+            state: Mutex::new(RateLimitState::default()),
+        }
+    }
+
+    pub fn with_max_wait_secs(mut self, max_wait_secs: u64) -> Self {
+        self.max_wait_secs = max_wait_secs;
+        self
+    }
+
+    fn record(&self, resource_hint: &str, res: &GitHubResponse) {
+        let header_num = |name: &str| {
+            res.header(name)
+                .and_then(|v| v.trim().parse::<usize>().ok())
+        };
+        let mut state = self.state.lock().unwrap();
+
+        if let (Some(limit), Some(remaining), Some(reset)) = (
+            header_num("X-RateLimit-Limit"),
+            header_num("X-RateLimit-Remaining"),
+            header_num("X-RateLimit-Reset"),
+        ) {
+            let resource = res.header("X-RateLimit-Resource").unwrap_or(resource_hint);
+            state.rates.insert(
+                resource.to_string(),
+                Rate {
+                    limit,
+                    used: header_num("X-RateLimit-Used").unwrap_or(limit - remaining.min(limit)),
+                    remaining,
+                    reset,
+                },
+            );
+        }
+
+        #[derive(Deserialize)]
+        struct GraphQlRateLimit {
+            cost: Option<usize>,
+            limit: Option<usize>,
+            remaining: Option<usize>,
+            #[serde(rename = "resetAt")]
+            reset_at: Option<DateTime<Utc>>,
+        }
+
+        if resource_hint == "graphql" {
+            let rate_limit = serde_json::from_slice::<serde_json::Value>(&res.body)
+                .ok()
+                .and_then(|v| v.pointer("/data/rateLimit").cloned())
+                .and_then(|v| serde_json::from_value::<GraphQlRateLimit>(v).ok());
+            if let Some(rl) = rate_limit {
+                log::info!(
+                    "GraphQL query cost {:?}, {:?} points remaining",
+                    rl.cost,
+                    rl.remaining
+                );
+                let entry = state.rates.entry("graphql".to_string()).or_default();
+                if let Some(limit) = rl.limit {
+                    entry.limit = limit;
+                }
+                if let Some(remaining) = rl.remaining {
+                    entry.remaining = remaining;
+                    entry.used = entry.limit.saturating_sub(remaining);
+                }
+                if let Some(reset_at) = rl.reset_at {
+                    entry.reset = reset_at.timestamp().max(0) as usize;
+                }
+            }
+        }
+    }
+
+    // Seconds to wait before retrying, or None when the response isn't rate limited.
+    fn retry_after(res: &GitHubResponse) -> Option<u64> {
+        let remaining = res.header("X-RateLimit-Remaining").map(|v| v.trim());
+        let graphql_limited = res.status == 200
+            && serde_json::from_slice::<serde_json::Value>(&res.body)
+                .ok()
+                .and_then(|v| v.get("errors").cloned())
+                .and_then(|errors| errors.as_array().cloned())
+                .is_some_and(|errors| {
+                    errors
+                        .iter()
+                        .any(|e| e.get("type").and_then(|t| t.as_str()) == Some("RATE_LIMITED"))
+                });
+        let limited = res.status == 429
+            || graphql_limited
+            || (res.status == 403
+                && (remaining == Some("0") || res.header("Retry-After").is_some()));
+        if !limited {
+            return None;
+        }
+
+        if let Some(secs) = res
+            .header("Retry-After")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+        {
+            return Some(secs);
+        }
+        let reset = res
+            .header("X-RateLimit-Reset")
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .unwrap_or(0);
+        Some((reset - Utc::now().timestamp()).max(1) as u64)
+    }
+
+    async fn send<'a, F>(
+        &'a self,
+        resource: &str,
+        send: F,
+    ) -> Result<GitHubResponse, Box<dyn std::error::Error>>
+    where
+        F: Fn() -> TransportFuture<'a>,
+    {
+        let exhausted_for = self
+            .state
+            .lock()
+            .unwrap()
+            .rates
+            .get(resource)
+            .and_then(|rate| match rate.remaining == 0 {
+                true => Some((rate.reset as i64 - Utc::now().timestamp()).max(0) as u64),
+                false => None,
+            });
+        if let Some(wait) = exhausted_for {
+            if wait > self.max_wait_secs {
+                log::error!(
+                    "GitHub {} rate limit exhausted for another {}s, skipping request",
+                    resource,
+                    wait
+                );
+                self.state.lock().unwrap().limited = true;
+                return Ok(GitHubResponse {
+                    status: 429,
+                    ..Default::default()
+                });
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+        }
+
+        let mut attempts = 0;
+        loop {
+            let res = send().await?;
+            self.record(resource, &res);
+
+            let wait = match Self::retry_after(&res) {
+                None => return Ok(res),
+                Some(wait) => wait,
+            };
+            attempts += 1;
+            if attempts > 2 || wait > self.max_wait_secs {
+                log::error!(
+                    "GitHub rate limit hit on {}, retry in {}s, giving up",
+                    resource,
+                    wait
+                );
+                self.state.lock().unwrap().limited = true;
+                return Ok(res);
+            }
+            log::info!("GitHub rate limit hit on {}, sleeping {}s", resource, wait);
+            tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+        }
+    }
+}
+
+impl GitHubTransport for RateLimitTransport {
+    fn base_url(&self) -> &str {
+        self.inner.base_url()
+    }
+
+    fn get<'a>(&'a self, url: &'a str, headers: &'a [(&'a str, &'a str)]) -> TransportFuture<'a> {
+        let path = url.strip_prefix(self.inner.base_url()).unwrap_or(url);
+        let resource = match path.starts_with("/search/") {
+            true => "search",
+            false => "core",
+        };
+        Box::pin(self.send(resource, || self.inner.get(url, headers)))
+    }
+
+    fn post_graphql<'a>(
+        &'a self,
+        query: &'a str,
+        variables: &'a serde_json::Value,
+    ) -> TransportFuture<'a> {
+        Box::pin(self.send("graphql", || self.inner.post_graphql(query, variables)))
+    }
+
+    fn get_patch<'a>(&'a self, url: &'a str) -> TransportFuture<'a> {
+        Box::pin(self.send("core", || self.inner.get_patch(url)))
+    }
+
+    fn was_rate_limited(&self) -> bool {
+        self.state.lock().unwrap().limited
+    }
+
+    fn reset_rate_limited(&self) {
+        self.state.lock().unwrap().limited = false;
+    }
+
+    fn rate_limits(&self) -> HashMap<String, Rate> {
+        self.state.lock().unwrap().rates.clone()
+    }
+}
+
+// `github_api_base` points the fetchers at a stand-in server, `github_fixtures_dir`
+// replays saved responses instead, and `github_fixtures_record=true` records them.
+// Live requests wait out rate limits for up to `github_rate_limit_max_wait_secs` and go
+// through the ETag cache unless `github_http_cache=false`.
+pub fn transport_from_env(token: &str) -> Box<dyn GitHubTransport> {
+    let base_url = env::var("github_api_base").unwrap_or(GITHUB_API_BASE.to_string());
+    let http = HttpTransport::new(token).with_base_url(&base_url);
+
+    match env::var("github_fixtures_dir") {
+        Ok(dir) => match env::var("github_fixtures_record").as_deref() {
+            Ok("true") => Box::new(FixtureTransport::recording(&dir, Box::new(http))),
+            _ => Box::new(FixtureTransport::new(&dir).with_base_url(&base_url)),
+        },
+        Err(_) => {
+            let mut limited = RateLimitTransport::new(Box::new(http));
+            if let Some(secs) = env::var("github_rate_limit_max_wait_secs")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+            {
+                limited = limited.with_max_wait_secs(secs);
+            }
+            match env::var("github_http_cache").as_deref() {
+                Ok("false") => Box::new(limited),
+                _ => Box::new(CachingTransport::new(Box::new(limited))),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_are_named_by_method_and_path() {
+        let fixtures = FixtureTransport::new("fixtures");
+        let url = format!("{GITHUB_API_BASE}/repos/o/r/commits?since=2024-01-01T00:00:00Z&page=2");
+        assert_eq!(
+            fixtures.fixture_path("GET", &url, None, ""),
+            PathBuf::from("fixtures/GET_repos_o_r_commits_since_2024-01-01T00_00_00Z_page_2.json")
+        );
+    }
+
+    #[test]
+    fn patches_and_posts_get_their_own_fixtures() {
+        let fixtures = FixtureTransport::new("fixtures");
+        let url = "https://github.com/o/r/commit/a1";
+        let json = fixtures.fixture_path("GET", url, None, "");
+        let patch = fixtures.fixture_path("GET", url, Some("patch"), "");
+        assert_ne!(json, patch);
+        assert!(patch.to_string_lossy().ends_with(".patch.json"));
+
+        let graphql = fixtures.graphql_url();
+        assert_ne!(
+            fixtures.fixture_path("POST", &graphql, None, "query a"),
+            fixtures.fixture_path("POST", &graphql, None, "query b")
+        );
+    }
+
+    #[test]
+    fn long_fixture_names_stay_distinct() {
+        let fixtures = FixtureTransport::new("fixtures");
+        let long = |page: u8| {
+            let url = format!(
+                "{GITHUB_API_BASE}/search/issues?q={}&page={page}",
+                "x".repeat(300)
+            );
+            fixtures.fixture_path("GET", &url, None, "")
+        };
+        let name = long(1).file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.len() <= 205, "{name}");
+        assert_ne!(long(1), long(2));
+    }
+
+    // Serves a body per Accept header with an ETag, and a 304 to requests that send it back.
+    #[derive(Clone, Default)]
+    struct EtagServer {
+        not_modified: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl EtagServer {
+        fn not_modified(&self) -> usize {
+            self.not_modified.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    impl GitHubTransport for EtagServer {
+        fn base_url(&self) -> &str {
+            GITHUB_API_BASE
+        }
+
+        fn get<'a>(
+            &'a self,
+            _url: &'a str,
+            headers: &'a [(&'a str, &'a str)],
+        ) -> TransportFuture<'a> {
+            Box::pin(async move { Ok(self.respond(headers)) })
+        }
+
+        fn post_graphql<'a>(
+            &'a self,
+            _query: &'a str,
+            _variables: &'a serde_json::Value,
+        ) -> TransportFuture<'a> {
+            Box::pin(async { Err("EtagServer only answers GET requests".into()) })
+        }
+
+        fn get_patch<'a>(&'a self, _url: &'a str) -> TransportFuture<'a> {
+            Box::pin(async { Err("EtagServer only answers GET requests".into()) })
+        }
+    }
+
+    impl EtagServer {
+        fn respond(&self, headers: &[(&str, &str)]) -> GitHubResponse {
+            let header = |name: &str| {
+                headers
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            };
+            let body = header("Accept").unwrap_or("json".to_string());
+            let etag = format!("\"{body}\"");
+            if header("If-None-Match") == Some(etag.clone()) {
+                self.not_modified
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                return GitHubResponse {
+                    status: 304,
+                    headers: HashMap::new(),
+                    body: vec![],
+                };
+            }
+            GitHubResponse {
+                status: 200,
+                headers: HashMap::from([("etag".to_string(), etag)]),
+                body: body.into_bytes(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn cached_responses_are_kept_per_accept_header() {
+        let server = EtagServer::default();
+        let cache = CachingTransport::new(Box::new(server.clone()));
+        let url = format!("{GITHUB_API_BASE}/repos/o/r/readme?per-accept");
+        let raw = [("Accept", "raw")];
+
+        assert_eq!(cache.get(&url, &[]).await.unwrap().body, b"json");
+        assert_eq!(cache.get(&url, &raw).await.unwrap().body, b"raw");
+        // Both are answered from the cache now, each with its own body.
+        assert_eq!(cache.get(&url, &[]).await.unwrap().body, b"json");
+        assert_eq!(cache.get(&url, &raw).await.unwrap().body, b"raw");
+        assert_eq!(server.not_modified(), 2);
+    }
+
+    #[tokio::test]
+    async fn cached_responses_expire() {
+        let server = EtagServer::default();
+        let url = format!("{GITHUB_API_BASE}/repos/o/r/readme?expiring");
+        let expired = CachingTransport::new(Box::new(server.clone())).with_ttl_secs(0);
+        expired.get(&url, &[]).await.unwrap();
+        expired.get(&url, &[]).await.unwrap();
+        assert_eq!(server.not_modified(), 0);
+
+        let fresh = CachingTransport::new(Box::new(server.clone())).with_ttl_secs(3600);
+        fresh.get(&url, &[]).await.unwrap();
+        fresh.get(&url, &[]).await.unwrap();
+        assert_eq!(server.not_modified(), 1);
+    }
+
+    // Answers each URL's first request with a 429 asking to retry after `retry_after`
+    // seconds, and later ones with a 200.
+    struct LimitedOnce {
+        retry_after: u64,
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl GitHubTransport for LimitedOnce {
+        fn base_url(&self) -> &str {
+            GITHUB_API_BASE
+        }
+
+        fn get<'a>(
+            &'a self,
+            url: &'a str,
+            _headers: &'a [(&'a str, &'a str)],
+        ) -> TransportFuture<'a> {
+            Box::pin(async move {
+                let mut seen = self.seen.lock().unwrap();
+                if seen.iter().any(|u| u == url) {
+                    return Ok(GitHubResponse {
+                        status: 200,
+                        ..Default::default()
+                    });
+                }
+                seen.push(url.to_string());
+                Ok(GitHubResponse {
+                    status: 429,
+                    headers: HashMap::from([(
+                        "Retry-After".to_string(),
+                        self.retry_after.to_string(),
+                    )]),
+                    body: vec![],
+                })
+            })
+        }
+
+        fn post_graphql<'a>(
+            &'a self,
+            _query: &'a str,
+            _variables: &'a serde_json::Value,
+        ) -> TransportFuture<'a> {
+            Box::pin(async { Err("LimitedOnce only answers GET requests".into()) })
+        }
+
+        fn get_patch<'a>(&'a self, _url: &'a str) -> TransportFuture<'a> {
+            Box::pin(async { Err("LimitedOnce only answers GET requests".into()) })
+        }
+    }
+
+    fn limited_once(retry_after: u64) -> LimitedOnce {
+        LimitedOnce {
+            retry_after,
+            seen: Mutex::new(vec![]),
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limit_waits_dont_block_other_requests() {
+        let github = RateLimitTransport::new(Box::new(limited_once(1)));
+        let started = std::time::Instant::now();
+        let (a, b) = futures::join!(
+            github.get("https://api.github.com/a", &[]),
+            github.get("https://api.github.com/b", &[])
+        );
+        assert_eq!((a.unwrap().status, b.unwrap().status), (200, 200));
+        // Both waits ran at the same time.
+        assert!(
+            started.elapsed() < std::time::Duration::from_millis(1900),
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(!github.was_rate_limited());
+    }
+
+    #[tokio::test]
+    async fn rate_limited_flag_is_reset_per_report() {
+        let github = RateLimitTransport::new(Box::new(limited_once(3600)));
+        let res = github.get("https://api.github.com/a", &[]).await.unwrap();
+        assert_eq!(res.status, 429);
+        assert!(github.was_rate_limited());
+
+        github.reset_rate_limited();
+        assert!(!github.was_rate_limited());
+        let res = github.get("https://api.github.com/a", &[]).await.unwrap();
+        assert_eq!(res.status, 200);
+        assert!(!github.was_rate_limited());
+    }
+}
@@ -1,12 +1,12 @@
-use http_req::{request::Method, request::Request, response, uri::Uri};
+use crate::error::{ResearchError, ResearchResult};
+use crate::llm::LlmBackend;
+use crate::store::{get, set};
+use crate::transport::{GitHubResponse, GitHubTransport};
 use log;
-use openai_flows::{
-    chat::{ChatModel, ChatOptions},
-    OpenAIFlows,
-};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashSet;
-use store_flows::{get, set};
 
 /*
 use crypto::{symmetriccipher, buffer, aes, blockmodes};
@@ -44,7 +44,7 @@ pub fn squeeze_fit_commits_issues(commits: &str, issues: &str, split: f32) -> (S
     let issues_len = issues_vec.len();
 
     if commits_len + issues_len > 44_000 {
-        let commits_to_take = (44_000 as f32 * split) as usize;
+        let commits_to_take = (44_000_f32 * split) as usize;
         match commits_len > commits_to_take {
             true => commits_vec.truncate(commits_to_take),
             false => {
@@ -128,144 +128,190 @@ pub fn squeeze_fit_post_texts(inp_str: &str, max_len: u16, split: f32) -> String
 }
 
 pub async fn chain_of_chat(
+    llm: &dyn LlmBackend,
     sys_prompt_1: &str,
     usr_prompt_1: &str,
     chat_id: &str,
     gen_len_1: u16,
     usr_prompt_2: &str,
     gen_len_2: u16,
-    error_tag: &str,
-) -> Option<String> {
-    let openai = OpenAIFlows::new();
-
-    let co_1 = ChatOptions {
-        model: ChatModel::GPT35Turbo16K,
-        restart: true,
-        system_prompt: Some(sys_prompt_1),
-        max_tokens: Some(gen_len_1),
-        temperature: Some(0.7),
-        ..Default::default()
-    };
-
-    match openai.chat_completion(chat_id, usr_prompt_1, &co_1).await {
-        Ok(res_1) => {
-            let sys_prompt_2 = serde_json::json!([{"role": "system", "content": sys_prompt_1},
-    {"role": "user", "content": usr_prompt_1},
-    {"role": "assistant", "content": &res_1.choice}])
-            .to_string();
-
-            let co_2 = ChatOptions {
-                model: ChatModel::GPT35Turbo16K,
-                restart: false,
-                system_prompt: Some(&sys_prompt_2),
-                max_tokens: Some(gen_len_2),
-                temperature: Some(0.7),
-                ..Default::default()
-            };
-            match openai.chat_completion(chat_id, usr_prompt_2, &co_2).await {
-                Ok(res_2) => {
-                    if res_2.choice.len() < 10 {
-                        log::error!(
-                            "{}, GPT generation went sideway: {:?}",
-                            error_tag,
-                            res_2.choice
-                        );
-                        return None;
-                    }
-                    return Some(res_2.choice);
-                }
-                Err(_e) => log::error!("{}, Step 2 GPT generation error {:?}", error_tag, _e),
-            };
+) -> ResearchResult<String> {
+    match llm
+        .chain(
+            chat_id,
+            sys_prompt_1,
+            usr_prompt_1,
+            gen_len_1,
+            usr_prompt_2,
+            gen_len_2,
+        )
+        .await
+    {
+        Some(res) => {
+            if res.len() < 10 {
+                log::error!("{}, GPT generation went sideway: {:?}", chat_id, res);
+                return Err(ResearchError::Llm(format!(
+                    "{chat_id}: generation too short"
+                )));
+            }
+            Ok(res)
+        }
+        None => {
+            log::error!("{}, GPT generation error", chat_id);
+            Err(ResearchError::Llm(chat_id.to_string()))
         }
-        Err(_e) => log::error!("{}, Step 1 GPT generation error {:?}", error_tag, _e),
     }
-
-    None
 }
 
-pub async fn github_http_fetch(token: &str, url: &str) -> Option<Vec<u8>> {
-    let url = Uri::try_from(url).unwrap();
-    let mut writer = Vec::new();
+pub async fn github_http_fetch(github: &dyn GitHubTransport, url: &str) -> ResearchResult<Vec<u8>> {
+    Ok(github_fetch_with_header(github, url).await?.body)
+}
 
-    match Request::new(&url)
-        .method(Method::GET)
-        .header("User-Agent", "flows-network connector")
-        .header("Content-Type", "application/vnd.github.v3+json")
-        .header("Authorization", &format!("Bearer {token}"))
-        .send(&mut writer)
-    {
+pub async fn github_fetch_with_header(
+    github: &dyn GitHubTransport,
+    url: &str,
+) -> ResearchResult<GitHubResponse> {
+    match github.get(url, &[]).await {
         Ok(res) => {
-            if !res.status_code().is_success() {
-                log::error!("Github http error {:?}", res.status_code());
-                return None;
+            if !res.is_success() {
+                log::error!("Github http error {:?}", res.status);
+                return Err(ResearchError::from_response(url, &res));
             };
-
-            Some(writer)
+            Ok(res)
         }
-        Err(_e) => {
-            log::error!("Error getting response from Github: {:?}", _e);
-            None
+        Err(e) => {
+            log::error!("Error getting response from Github: {:?}", e);
+            Err(e.into())
         }
     }
 }
 
-pub fn github_fetch_with_header(
-    token: &str,
-    url: &str,
-) -> Result<(response::Response, Vec<u8>), Box<dyn std::error::Error>> {
-    let uri = Uri::try_from(url)?;
-    let mut writer = std::io::Cursor::new(Vec::new());
-
-    let response = match Request::new(&uri)
-        .method(Method::GET)
-        .header("User-Agent", "flows-network connector")
-        .header("Content-Type", "application/vnd.github.v3+json")
-        .header("Authorization", &format!("Bearer {}", token))
-        .send(&mut writer)
-    {
+// A GraphQL document plus its variables. User input only ever goes into `variables`,
+// so quotes or braces in a search string can't change the query itself.
+#[derive(Debug, Clone)]
+pub struct GraphQlQuery {
+    query: &'static str,
+    variables: serde_json::Map<String, Value>,
+}
+
+impl GraphQlQuery {
+    pub fn new(query: &'static str) -> Self {
+        GraphQlQuery {
+            query,
+            variables: serde_json::Map::new(),
+        }
+    }
+
+    // `None` values are sent as `null`, which GraphQL treats like an omitted argument.
+    pub fn variable<V: serde::Serialize>(mut self, name: &str, value: V) -> Self {
+        self.variables.insert(
+            name.to_string(),
+            serde_json::to_value(value).unwrap_or(Value::Null),
+        );
+        self
+    }
+}
+
+pub async fn github_http_post(
+    github: &dyn GitHubTransport,
+    query: &GraphQlQuery,
+) -> ResearchResult<Vec<u8>> {
+    let url = github.graphql_url();
+    let variables = Value::Object(query.variables.clone());
+    match github.post_graphql(query.query, &variables).await {
         Ok(res) => {
-            if !res.status_code().is_success() {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "github_fetch_with_header encountered Github http error",
-                )));
+            if !res.is_success() {
+                log::error!("Github http error {:?}", res.status);
+                return Err(ResearchError::from_response(&url, &res));
             };
-            res
+            Ok(res.body)
         }
         Err(e) => {
             log::error!("Error getting response from Github: {:?}", e);
-            return Err(Box::new(e));
+            Err(e.into())
         }
-    };
+    }
+}
 
-    Ok((response, writer.into_inner()))
+#[derive(Debug, Deserialize)]
+pub struct GraphQlError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub path: Vec<Value>,
 }
 
-pub async fn github_http_post(token: &str, base_url: &str, query: &str) -> Option<Vec<u8>> {
-    let base_url = Uri::try_from(base_url).unwrap();
-    let mut writer = Vec::new();
-
-    let query = serde_json::json!({"query": query});
-    match Request::new(&base_url)
-        .method(Method::POST)
-        .header("User-Agent", "flows-network connector")
-        .header("Content-Type", "application/json")
-        .header("Authorization", &format!("Bearer {}", token))
-        .header("Content-Length", &query.to_string().len())
-        .body(&query.to_string().into_bytes())
-        .send(&mut writer)
-    {
-        Ok(res) => {
-            if !res.status_code().is_success() {
-                log::error!("Github http error {:?}", res.status_code());
-                return None;
-            };
-            Some(writer)
+#[derive(Debug, Deserialize)]
+struct GraphQlEnvelope<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+// A GraphQL answer that carried data. `errors` is only non-empty for partial results,
+// e.g. when some nodes of a search couldn't be resolved.
+#[derive(Debug)]
+pub struct GraphQlResponse<T> {
+    pub data: T,
+    pub errors: Vec<GraphQlError>,
+}
+
+impl<T> GraphQlResponse<T> {
+    pub fn is_partial(&self) -> bool {
+        !self.errors.is_empty()
+    }
+}
+
+fn describe_graphql_errors(errors: &[GraphQlError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.message.as_str())
+        .collect::<Vec<&str>>()
+        .join("; ")
+}
+
+// Posts `query` and splits the response into data and errors; a response without data
+// is a failure carrying GitHub's error messages.
+pub async fn github_graphql<T: DeserializeOwned>(
+    github: &dyn GitHubTransport,
+    query: &GraphQlQuery,
+) -> ResearchResult<GraphQlResponse<T>> {
+    let body = github_http_post(github, query).await?;
+    let envelope = match serde_json::from_slice::<GraphQlEnvelope<T>>(&body) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            log::error!("Failed to parse the GraphQL response: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    match envelope.data {
+        Some(data) => {
+            if !envelope.errors.is_empty() {
+                log::warn!(
+                    "Partial GraphQL response: {}",
+                    describe_graphql_errors(&envelope.errors)
+                );
+            }
+            Ok(GraphQlResponse {
+                data,
+                errors: envelope.errors,
+            })
+        }
+        None if envelope
+            .errors
+            .iter()
+            .any(|e| e.kind.as_deref() == Some("RATE_LIMITED")) =>
+        {
+            Err(ResearchError::RateLimited(github.graphql_url()))
         }
-        Err(_e) => {
-            log::error!("Error getting response from Github: {:?}", _e);
-            None
+        None if !envelope.errors.is_empty() => {
+            let messages = describe_graphql_errors(&envelope.errors);
+            log::error!("GraphQL query failed: {}", messages);
+            Err(ResearchError::GraphQl(messages))
         }
+        None => Err(ResearchError::NoData("GraphQL response".to_string())),
     }
 }
 
@@ -280,7 +326,7 @@ pub async fn save_user(owner: &str, repo: &str, user_name: &str) -> bool {
 
     let mut existing_users: HashSet<String> = get(key)
         .and_then(|val| serde_json::from_value(val).ok())
-        .unwrap_or_else(HashSet::new);
+        .unwrap_or_default();
 
     // Check if the user_name already exists
     let already_exists = existing_users.contains(user_name);
@@ -300,3 +346,207 @@ pub async fn save_user(owner: &str, repo: &str, user_name: &str) -> bool {
     // If the user_name was added, return true; otherwise, return false
     !already_exists
 }
+
+// Summaries are keyed by the backend and model, the source URL, both prompts and the
+// full source text, since the prompts only carry a trimmed copy of it. An issue that gains
+// new comments, a changed prompt or another model all miss the cache.
+pub fn summary_cache_key(
+    llm: &dyn LlmBackend,
+    url: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    source_text: &str,
+) -> String {
+    use std::hash::Hasher;
+    use twox_hash::XxHash;
+    let mut hasher = XxHash::with_seed(0);
+    for part in [
+        llm.model_id().as_str(),
+        url,
+        system_prompt,
+        user_prompt,
+        source_text,
+    ] {
+        hasher.write(part.as_bytes());
+        hasher.write_u8(0);
+    }
+    format!("summary-{:x}", hasher.finish())
+}
+
+pub fn get_cached_summary(key: &str) -> Option<String> {
+    match get(key) {
+        Some(Value::String(summary)) => Some(summary),
+        _ => None,
+    }
+}
+
+pub fn set_cached_summary(key: &str, summary: &str) {
+    let ttl = std::env::var("summary_cache_ttl_secs")
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(7 * 24 * 3600);
+
+    set(key, Value::String(summary.to_string()), Some(ttl));
+}
+
+// `source_text` is everything the prompt was built from, before any trimming.
+pub async fn cached_complete(
+    llm: &dyn LlmBackend,
+    url: &str,
+    source_text: &str,
+    chat_id: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    max_tokens: u16,
+) -> ResearchResult<String> {
+    let key = summary_cache_key(llm, url, system_prompt, user_prompt, source_text);
+    if let Some(summary) = get_cached_summary(&key) {
+        log::info!("Using cached summary for {}", url);
+        return Ok(summary);
+    }
+
+    let summary = llm
+        .complete(chat_id, system_prompt, user_prompt, max_tokens)
+        .await
+        .ok_or_else(|| ResearchError::Llm(chat_id.to_string()))?;
+    set_cached_summary(&key, &summary);
+    Ok(summary)
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::llm::OpenAICompatibleBackend;
+    use crate::transport::{TransportFuture, GITHUB_API_BASE};
+
+    #[test]
+    fn summary_cache_key_covers_model_prompts_and_source() {
+        let llm = OpenAICompatibleBackend::new("http://localhost:8080/v1", "small");
+        let other_model = OpenAICompatibleBackend::new("http://localhost:8080/v1", "large");
+        let url = "https://github.com/o/r/issues/1";
+        let key = summary_cache_key(&llm, url, "system", "user", "full text");
+
+        assert_eq!(
+            key,
+            summary_cache_key(&llm, url, "system", "user", "full text")
+        );
+        assert_ne!(
+            key,
+            summary_cache_key(&other_model, url, "system", "user", "full text")
+        );
+        assert_ne!(
+            key,
+            summary_cache_key(&llm, url, "other system", "user", "full text")
+        );
+        // The prompt only carries a trimmed copy, so a change past the cut still counts.
+        assert_ne!(
+            key,
+            summary_cache_key(&llm, url, "system", "user", "full text, edited")
+        );
+        // Parts are separated, so moving text between them changes the key.
+        assert_ne!(
+            key,
+            summary_cache_key(&llm, url, "systemuser", "", "full text")
+        );
+    }
+
+    // Answers every GraphQL post with the same status and body.
+    struct GraphQlStub {
+        status: u16,
+        body: &'static str,
+    }
+
+    impl GitHubTransport for GraphQlStub {
+        fn base_url(&self) -> &str {
+            GITHUB_API_BASE
+        }
+
+        fn get<'a>(
+            &'a self,
+            _url: &'a str,
+            _headers: &'a [(&'a str, &'a str)],
+        ) -> TransportFuture<'a> {
+            Box::pin(async { Err("GraphQlStub only answers GraphQL posts".into()) })
+        }
+
+        fn post_graphql<'a>(
+            &'a self,
+            _query: &'a str,
+            _variables: &'a Value,
+        ) -> TransportFuture<'a> {
+            Box::pin(async move {
+                Ok(GitHubResponse {
+                    status: self.status,
+                    body: self.body.as_bytes().to_vec(),
+                    ..Default::default()
+                })
+            })
+        }
+
+        fn get_patch<'a>(&'a self, _url: &'a str) -> TransportFuture<'a> {
+            Box::pin(async { Err("GraphQlStub only answers GraphQL posts".into()) })
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Viewer {
+        login: String,
+    }
+
+    async fn viewer(status: u16, body: &'static str) -> ResearchResult<GraphQlResponse<Viewer>> {
+        let query = GraphQlQuery::new("query { viewer { login } }");
+        github_graphql(&GraphQlStub { status, body }, &query).await
+    }
+
+    #[tokio::test]
+    async fn graphql_data_with_errors_is_partial() {
+        let res = viewer(
+            200,
+            r#"{"data": {"login": "alice"}, "errors": [{"message": "Could not resolve a node", "type": "NOT_FOUND", "path": ["search", "nodes", 3]}]}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.data.login, "alice");
+        assert!(res.is_partial());
+        assert_eq!(res.errors[0].kind.as_deref(), Some("NOT_FOUND"));
+        assert_eq!(res.errors[0].path[2], 3);
+
+        let res = viewer(200, r#"{"data": {"login": "alice"}}"#)
+            .await
+            .unwrap();
+        assert!(!res.is_partial());
+    }
+
+    #[tokio::test]
+    async fn graphql_errors_without_data_fail() {
+        let err = viewer(
+            200,
+            r#"{"errors": [{"message": "Field 'x' doesn't exist"}, {"message": "Bad argument"}]}"#,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, ResearchError::GraphQl(msg) if msg == "Field 'x' doesn't exist; Bad argument"),
+            "{err}"
+        );
+
+        let err = viewer(
+            200,
+            r#"{"data": null, "errors": [{"message": "API rate limit exceeded", "type": "RATE_LIMITED"}]}"#,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ResearchError::RateLimited(_)), "{err}");
+
+        let err = viewer(200, r#"{"data": null}"#).await.unwrap_err();
+        assert!(matches!(err, ResearchError::NoData(_)), "{err}");
+
+        let err = viewer(200, "<html>").await.unwrap_err();
+        assert!(matches!(err, ResearchError::Parse(_)), "{err}");
+
+        let err = viewer(401, r#"{"message": "Bad credentials"}"#)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ResearchError::Unauthorized(_)), "{err}");
+    }
+}
@@ -0,0 +1,124 @@
+// Checks that joined requests and the `.buffered` analyzers overlap on the current-thread
+// runtime the bot and the CLI use, against a local server that answers slowly.
+use github_research_tool::data_analyzers::process_commits;
+use github_research_tool::github_data_fetchers::{GitMemory, MemoryType};
+use github_research_tool::llm::{LlmBackend, OpenAICompatibleBackend};
+use github_research_tool::transport::HttpTransport;
+use github_research_tool::utils::github_http_fetch;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DELAY: Duration = Duration::from_millis(300);
+
+// Answers every request after `DELAY` with a chat completion, which also does as a patch,
+// and keeps the highest number of requests it was handling at once.
+#[derive(Clone, Default)]
+struct SlowServer {
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl SlowServer {
+    fn start(&self) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = self.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = server.clone();
+                std::thread::spawn(move || server.handle(stream));
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    fn handle(&self, stream: TcpStream) {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        let _ = reader.read_exact(&mut body);
+
+        std::thread::sleep(DELAY);
+        let answer = r#"{"choices":[{"message":{"content":"a summary of the change"}}]}"#;
+        let _ = write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            answer.len(),
+            answer
+        );
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+#[tokio::test]
+async fn joined_requests_overlap() {
+    let server = SlowServer::default();
+    let base_url = server.start();
+    let github = HttpTransport::new("token").with_base_url(&base_url);
+    let llm = OpenAICompatibleBackend::new(&base_url, "model");
+
+    let readme_url = format!("{base_url}/repos/o/r/readme");
+    let languages_url = format!("{base_url}/repos/o/r/languages");
+
+    let started = std::time::Instant::now();
+    let (readme, languages, summary) = futures::join!(
+        github_http_fetch(&github, &readme_url),
+        github_http_fetch(&github, &languages_url),
+        llm.complete("overlap", "system", "user", 16),
+    );
+    assert!(readme.is_ok() && languages.is_ok());
+    assert_eq!(summary.as_deref(), Some("a summary of the change"));
+
+    assert_eq!(server.max_in_flight(), 3);
+    assert!(started.elapsed() < DELAY * 2, "{:?}", started.elapsed());
+}
+
+#[tokio::test]
+async fn commits_are_summarized_concurrently() {
+    let server = SlowServer::default();
+    let base_url = server.start();
+    let github = HttpTransport::new("token").with_base_url(&base_url);
+    let llm = OpenAICompatibleBackend::new(&base_url, "model");
+
+    let mut commits = (0..4)
+        .map(|i| GitMemory {
+            memory_type: MemoryType::Commit,
+            name: "alice".to_string(),
+            tag_line: format!("Change {i}"),
+            source_url: format!("{base_url}/o/r/commit/c{i}"),
+            payload: String::new(),
+            date: chrono::NaiveDate::default(),
+        })
+        .collect::<Vec<_>>();
+
+    // Each commit is a patch download followed by a completion.
+    let started = std::time::Instant::now();
+    let summaries = process_commits(&github, &llm, &mut commits, 4)
+        .await
+        .unwrap();
+    assert_eq!(summaries.lines().count(), 4, "{summaries}");
+
+    assert_eq!(server.max_in_flight(), 4);
+    assert!(started.elapsed() < DELAY * 4, "{:?}", started.elapsed());
+}
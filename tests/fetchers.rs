@@ -0,0 +1,208 @@
+// Runs the fetchers against the responses saved in `tests/fixtures`, see
+// `FixtureTransport::fixture_path` for how the files are named.
+use chrono::{DateTime, TimeZone, Utc};
+use github_research_tool::error::ResearchError;
+use github_research_tool::github_data_fetchers::{
+    get_commits_in_range, get_issues_in_range, get_pull_requests_and_reviews,
+    search_discussions_integrated, search_repository, search_repository_hits,
+};
+use github_research_tool::llm::{LlmBackend, LlmFuture};
+use github_research_tool::transport::FixtureTransport;
+use std::sync::Mutex;
+
+fn fixtures() -> FixtureTransport {
+    FixtureTransport::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+}
+
+fn window() -> (DateTime<Utc>, DateTime<Utc>) {
+    (
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2024, 1, 8, 0, 0, 0).unwrap(),
+    )
+}
+
+// Answers every prompt with the same text and keeps the prompts it was given.
+struct RecordingLlm {
+    prompts: Mutex<Vec<String>>,
+}
+
+impl LlmBackend for RecordingLlm {
+    fn token_budget(&self) -> usize {
+        16_000
+    }
+
+    fn model_id(&self) -> String {
+        "recording".to_string()
+    }
+
+    fn complete<'a>(
+        &'a self,
+        chat_id: &'a str,
+        _system_prompt: &'a str,
+        user_prompt: &'a str,
+        _max_tokens: u16,
+    ) -> LlmFuture<'a> {
+        self.prompts.lock().unwrap().push(user_prompt.to_string());
+        Box::pin(async move { Some(format!("summary from {chat_id}")) })
+    }
+
+    fn chain<'a>(
+        &'a self,
+        chat_id: &'a str,
+        _sys_prompt_1: &'a str,
+        usr_prompt_1: &'a str,
+        gen_len_1: u16,
+        _usr_prompt_2: &'a str,
+        _gen_len_2: u16,
+    ) -> LlmFuture<'a> {
+        self.complete(chat_id, "", usr_prompt_1, gen_len_1)
+    }
+}
+
+#[tokio::test]
+async fn commits_in_range_follow_pages_and_keep_the_window() {
+    let (since, until) = window();
+    let (count, commits) = get_commits_in_range(&fixtures(), "o", "r", None, since, until)
+        .await
+        .unwrap();
+
+    // The commit without a GitHub author and the one authored before `since` are left out.
+    assert_eq!(count, 2);
+    let urls = commits
+        .iter()
+        .map(|c| c.source_url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        urls,
+        [
+            "https://github.com/o/r/commit/a1",
+            "https://github.com/o/r/commit/d4"
+        ]
+    );
+    assert_eq!(commits[0].name, "alice");
+    assert_eq!(commits[0].tag_line, "Add rate limit handling");
+    assert_eq!(commits[1].date.to_string(), "2024-01-07");
+}
+
+#[tokio::test]
+async fn commits_in_range_fail_on_a_missing_repo() {
+    let (since, until) = window();
+    let err = get_commits_in_range(
+        &fixtures(),
+        "o",
+        "r",
+        Some("ghost".to_string()),
+        since,
+        until,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, ResearchError::NotFound(_)), "{err}");
+}
+
+#[tokio::test]
+async fn issues_in_range_stop_after_the_last_page() {
+    let (since, until) = window();
+    let (count, issues) = get_issues_in_range(&fixtures(), "o", "r", None, since, until)
+        .await
+        .unwrap();
+
+    assert_eq!(count, 2);
+    assert_eq!(issues[0].number, 41);
+    assert_eq!(issues[0].user.login, "alice");
+    assert_eq!(issues[1].html_url, "https://github.com/o/r/issues/42");
+    assert!(issues[1].body.is_none());
+}
+
+#[tokio::test]
+async fn discussions_follow_search_and_comment_pages() {
+    let llm = RecordingLlm {
+        prompts: Mutex::new(vec![]),
+    };
+    let (text, discussions) = search_discussions_integrated(
+        &fixtures(),
+        &llm,
+        "repo:o/r updated:2023-12-02T00:00:00Z..2024-01-08T00:00:00Z",
+        &Some("alice".to_string()),
+    )
+    .await
+    .unwrap();
+
+    let titles = discussions
+        .iter()
+        .map(|d| d.tag_line.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, ["Plans for the 2.0 API", "Release cadence"]);
+    assert_eq!(discussions[0].name, "alice");
+    assert_eq!(discussions[0].payload, "summary from discussion99");
+    assert!(text.starts_with("DISCUSSIONS"));
+    assert!(text.contains("https://github.com/o/r/discussions/8"));
+
+    // The second page of comments and its replies made it into the summarized text.
+    let prompts = llm.prompts.lock().unwrap();
+    assert_eq!(prompts.len(), 2);
+    assert!(prompts[0].contains("bob comments: 'Drop the v1 search endpoint."));
+    assert!(prompts[0].contains("carol comments: 'Agreed, and keep the GraphQL one."));
+    assert!(prompts[0].contains("alice replied: 'Done in the 2.0 branch."));
+}
+
+#[tokio::test]
+async fn reviews_follow_review_and_comment_pages() {
+    let (since, until) = window();
+    let (pull_requests, reviews) =
+        get_pull_requests_and_reviews(&fixtures(), "o", "r", Some("Bob".to_string()), since, until)
+            .await
+            .unwrap();
+
+    // Pull request 7 was only reviewed during the window, so it isn't in the section.
+    let numbers = pull_requests.iter().map(|pr| pr.number).collect::<Vec<_>>();
+    assert_eq!(numbers, [8]);
+
+    // Carol is filtered out, and the login matches regardless of case. Bob's review from
+    // before the window and Alice's review of her own pull request don't count.
+    assert_eq!(reviews.len(), 1);
+    let bob = &reviews[0];
+    assert_eq!(bob.login, "bob");
+    assert_eq!(
+        (bob.approvals, bob.change_requests, bob.commented_reviews),
+        (1, 1, 0)
+    );
+    // One comment on the first page of the second review page, two on the next.
+    assert_eq!(bob.review_comments, 3);
+    assert_eq!(
+        bob.pull_requests,
+        [
+            "https://github.com/o/r/pull/7",
+            "https://github.com/o/r/pull/8"
+        ]
+    );
+    // Bob first reviewed pull request 7 before the window, so only 8 has a turnaround.
+    assert_eq!(bob.turnaround_hours, [24.0]);
+}
+
+#[tokio::test]
+async fn repository_search_keeps_every_field_of_the_hits() {
+    let hits = search_repository_hits(&fixtures(), "topic:fixtures")
+        .await
+        .unwrap();
+
+    // The repo without a creation date is left out.
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].name.as_deref(), Some("r"));
+    assert_eq!(hits[0].url.as_deref(), Some("https://github.com/o/r"));
+    assert_eq!((hits[0].stars, hits[0].forks), (Some(42), Some(7)));
+    assert_eq!(hits[1].created_at.to_string(), "2023-05-02");
+    // Only the text form shortens long descriptions.
+    assert_eq!(hits[1].description.as_ref().unwrap().chars().count(), 400);
+
+    let text = search_repository(&fixtures(), "topic:fixtures")
+        .await
+        .unwrap();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[1],
+        "Name: r, Description: Tools for fast builds., Url: https://github.com/o/r Created At: 2022-03-01 Stars: 42, Forks: 7"
+    );
+    assert!(lines[2].len() < 400, "{}", lines[2]);
+}